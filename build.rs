@@ -13,18 +13,8 @@ fn setup_opengl()
     let out_dir = env::var("OUT_DIR").unwrap();
     let mut file_gl = File::create(&Path::new(&out_dir).join("bindings.rs")).unwrap();
 
-    use gl_generator::{StructGenerator, DebugStructGenerator, Registry, Fallbacks, Api, Profile};
-    let registry = Registry::new(Api::Gl, (4, 3), Profile::Core, Fallbacks::All, []);
-
-    if env::var("CARGO_FEATURE_DEBUG").is_ok() {
-        registry.write_bindings(
-            DebugStructGenerator,
-            &mut file_gl
-        ).unwrap();
-    } else {
-        registry.write_bindings(
-            StructGenerator,
-            &mut file_gl
-        ).unwrap();
-    }
+    use gl_generator::{StructGenerator, Registry, Fallbacks, Api, Profile};
+    Registry::new(Api::Gl, (4, 3), Profile::Core, Fallbacks::All, [])
+        .write_bindings(StructGenerator, &mut file_gl)
+        .unwrap();
 }
@@ -49,7 +49,8 @@ fn main() {
                         renderer.next_debug_type();
                         println!("{:?}", renderer.debug_type());
                     }
-                }
+                },
+                _ => {}
             }
         }
         time += frame_input.elapsed_time;
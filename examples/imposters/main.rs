@@ -91,7 +91,8 @@ fn main() {
                         renderer.next_debug_type();
                         println!("{:?}", renderer.debug_type());
                     }
-                }
+                },
+                _ => {}
             }
         }
 
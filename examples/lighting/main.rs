@@ -75,7 +75,8 @@ fn main() {
                         renderer.next_debug_type();
                         println!("{:?}", renderer.debug_type());
                     }
-                }
+                },
+                _ => {}
             }
             handle_surface_parameters(&event, &mut plane);
             handle_surface_parameters(&event, &mut monkey);
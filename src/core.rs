@@ -3,9 +3,20 @@ pub mod program;
 pub mod rendertarget;
 pub mod state;
 pub mod texture;
+pub mod cpu_texture;
+pub mod sampler;
+pub mod renderbuffer;
 pub mod types;
 pub mod camera;
 pub mod image_effect;
+pub mod error_check;
+pub mod debug_group;
+pub mod capabilities;
+pub mod resources;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod external_state;
+#[cfg(feature = "image-io")]
+pub mod screenshot;
 
 pub use crate::gl::Gl;
 pub use crate::gl::consts;
@@ -15,9 +26,20 @@ pub use program::*;
 pub use rendertarget::*;
 pub use state::*;
 pub use texture::*;
+pub use cpu_texture::*;
+pub use sampler::*;
+pub use renderbuffer::*;
 pub use types::*;
 pub use camera::*;
 pub use image_effect::*;
+pub use error_check::*;
+pub use debug_group::*;
+pub use capabilities::*;
+pub use resources::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use external_state::*;
+#[cfg(feature = "image-io")]
+pub use screenshot::*;
 
 #[derive(Debug)]
 pub enum Error {
@@ -31,7 +53,10 @@ pub enum Error {
     #[cfg(feature = "image-io")]
     Image(image::ImageError),
     FailedToCreateTexture {message: String},
-    BufferUpdateFailed {message: String}
+    FailedToCreateSampler {message: String},
+    FailedToCreateBuffer {message: String},
+    BufferUpdateFailed {message: String},
+    GLError {operation: String, errors: Vec<GLErrorType>, messages: Vec<String>}
 }
 
 #[cfg(feature = "image-io")]
@@ -1,19 +1,25 @@
 use crate::core::Error;
+use crate::core::error_check::check_gl_errors;
+use crate::core::resources::{self, ResourceCategory};
 use crate::gl::Gl;
 use crate::gl::consts;
 
 pub struct VertexBuffer {
     gl: Gl,
-    id: crate::gl::Buffer
+    id: crate::gl::Buffer,
+    resource: usize
 }
 
 impl VertexBuffer
 {
     pub fn new_with_static_f32(gl: &Gl, data: &[f32]) -> Result<VertexBuffer, Error>
     {
-        let id = gl.create_buffer().unwrap();
-        let mut buffer = VertexBuffer { gl: gl.clone(), id };
+        let id = gl.create_buffer()
+            .ok_or_else(|| Error::FailedToCreateBuffer {message: "Failed to create vertex buffer".to_string()})?;
+        let resource = resources::register(gl, ResourceCategory::VertexBuffer, 0);
+        let mut buffer = VertexBuffer { gl: gl.clone(), id, resource };
         buffer.fill_with_static_f32(data);
+        check_gl_errors(gl, "creating a vertex buffer")?;
         Ok(buffer)
     }
 
@@ -22,13 +28,17 @@ impl VertexBuffer
         self.bind();
         self.gl.buffer_data_f32(consts::ARRAY_BUFFER, data, consts::STATIC_DRAW);
         self.gl.unbind_buffer(consts::ARRAY_BUFFER);
+        resources::set_bytes(&self.gl, self.resource, std::mem::size_of_val(data));
     }
 
     pub fn new_with_dynamic_f32(gl: &Gl, data: &[f32]) -> Result<VertexBuffer, Error>
     {
-        let id = gl.create_buffer().unwrap();
-        let mut buffer = VertexBuffer { gl: gl.clone(), id };
+        let id = gl.create_buffer()
+            .ok_or_else(|| Error::FailedToCreateBuffer {message: "Failed to create vertex buffer".to_string()})?;
+        let resource = resources::register(gl, ResourceCategory::VertexBuffer, 0);
+        let mut buffer = VertexBuffer { gl: gl.clone(), id, resource };
         buffer.fill_with_dynamic_f32(data);
+        check_gl_errors(gl, "creating a vertex buffer")?;
         Ok(buffer)
     }
 
@@ -37,12 +47,20 @@ impl VertexBuffer
         self.bind();
         self.gl.buffer_data_f32(consts::ARRAY_BUFFER, data, consts::DYNAMIC_DRAW);
         self.gl.unbind_buffer(consts::ARRAY_BUFFER);
+        resources::set_bytes(&self.gl, self.resource, std::mem::size_of_val(data));
     }
 
     pub(crate) fn bind(&self)
     {
         self.gl.bind_buffer(consts::ARRAY_BUFFER, &self.id);
     }
+
+    /// Names this vertex buffer in graphics debuggers such as RenderDoc (KHR_debug) and in the report of `resources`.
+    pub fn set_label(&self, label: &str)
+    {
+        self.gl.object_label(consts::BUFFER, &self.id, label);
+        resources::set_label(&self.gl, self.resource, label);
+    }
 }
 
 impl Drop for VertexBuffer
@@ -50,23 +68,28 @@ impl Drop for VertexBuffer
     fn drop(&mut self)
     {
         self.gl.delete_buffer(&self.id);
+        resources::unregister(&self.gl, self.resource);
     }
 }
 
 pub struct ElementBuffer {
     gl: Gl,
     id: crate::gl::Buffer,
-    count: usize
+    count: usize,
+    resource: usize
 }
 
 impl ElementBuffer
 {
     pub fn new_with_u32(gl: &Gl, data: &[u32]) -> Result<ElementBuffer, Error>
     {
-        let id = gl.create_buffer().unwrap();
-        let mut buffer = ElementBuffer{ gl: gl.clone(), id, count: 0 };
+        let id = gl.create_buffer()
+            .ok_or_else(|| Error::FailedToCreateBuffer {message: "Failed to create element buffer".to_string()})?;
+        let resource = resources::register(gl, ResourceCategory::ElementBuffer, 0);
+        let mut buffer = ElementBuffer{ gl: gl.clone(), id, count: 0, resource };
         buffer.fill_with_u32(data);
         buffer.count = data.len();
+        check_gl_errors(gl, "creating an element buffer")?;
         Ok(buffer)
     }
 
@@ -75,7 +98,7 @@ impl ElementBuffer
         self.bind();
         self.gl.buffer_data_u32(consts::ELEMENT_ARRAY_BUFFER, data, consts::STATIC_DRAW);
         self.gl.unbind_buffer(consts::ELEMENT_ARRAY_BUFFER);
-
+        resources::set_bytes(&self.gl, self.resource, std::mem::size_of_val(data));
     }
 
     pub fn count(&self) -> usize {
@@ -86,6 +109,13 @@ impl ElementBuffer
     {
         self.gl.bind_buffer(consts::ELEMENT_ARRAY_BUFFER, &self.id);
     }
+
+    /// Names this element buffer in graphics debuggers such as RenderDoc (KHR_debug) and in the report of `resources`.
+    pub fn set_label(&self, label: &str)
+    {
+        self.gl.object_label(consts::BUFFER, &self.id, label);
+        resources::set_label(&self.gl, self.resource, label);
+    }
 }
 
 impl Drop for ElementBuffer
@@ -93,6 +123,88 @@ impl Drop for ElementBuffer
     fn drop(&mut self)
     {
         self.gl.delete_buffer(&self.id);
+        resources::unregister(&self.gl, self.resource);
+    }
+}
+
+/// The parameters of one draw of `Program::multi_draw_elements_indirect`, which draws `count` indices starting at `first_index` of the element buffer.
+/// The indices are offset by `base_vertex` and the instanced vertex attributes start at the element `base_instance`.
+#[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct DrawElementsIndirectCommand {
+    pub count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub base_vertex: i32,
+    pub base_instance: u32
+}
+
+/// A buffer of draw commands which is read by the GPU, see `Program::draw_elements_indirect` and `Program::multi_draw_elements_indirect`.
+/// Requires OpenGL 4.3, see `Capabilities::multi_draw_indirect`.
+#[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+pub struct DrawIndirectBuffer {
+    gl: Gl,
+    id: crate::gl::Buffer,
+    count: usize,
+    resource: usize
+}
+
+#[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+impl DrawIndirectBuffer
+{
+    pub fn new(gl: &Gl, commands: &[DrawElementsIndirectCommand]) -> Result<DrawIndirectBuffer, Error>
+    {
+        if !crate::core::capabilities(gl).multi_draw_indirect {
+            Err(Error::FailedToCreateBuffer {message: "Indirect draws require OpenGL 4.3 or the ARB_multi_draw_indirect extension".to_string()})?;
+        }
+        let id = gl.create_buffer()
+            .ok_or_else(|| Error::FailedToCreateBuffer {message: "Failed to create draw indirect buffer".to_string()})?;
+        let resource = resources::register(gl, ResourceCategory::DrawIndirectBuffer, 0);
+        let mut buffer = DrawIndirectBuffer{ gl: gl.clone(), id, count: 0, resource };
+        buffer.fill(commands);
+        check_gl_errors(gl, "creating a draw indirect buffer")?;
+        Ok(buffer)
+    }
+
+    pub fn fill(&mut self, commands: &[DrawElementsIndirectCommand])
+    {
+        let mut data = Vec::with_capacity(commands.len() * 5);
+        for command in commands {
+            data.extend_from_slice(&[command.count, command.instance_count, command.first_index, command.base_vertex as u32, command.base_instance]);
+        }
+        self.bind();
+        self.gl.buffer_data_u32(consts::DRAW_INDIRECT_BUFFER, &data, consts::STATIC_DRAW);
+        self.gl.unbind_buffer(consts::DRAW_INDIRECT_BUFFER);
+        self.count = commands.len();
+        resources::set_bytes(&self.gl, self.resource, std::mem::size_of_val(&data[..]));
+    }
+
+    /// The number of draw commands.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub(crate) fn bind(&self)
+    {
+        self.gl.bind_buffer(consts::DRAW_INDIRECT_BUFFER, &self.id);
+    }
+
+    /// Names this draw indirect buffer in graphics debuggers such as RenderDoc (KHR_debug) and in the report of `resources`.
+    pub fn set_label(&self, label: &str)
+    {
+        self.gl.object_label(consts::BUFFER, &self.id, label);
+        resources::set_label(&self.gl, self.resource, label);
+    }
+}
+
+#[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+impl Drop for DrawIndirectBuffer
+{
+    fn drop(&mut self)
+    {
+        self.gl.delete_buffer(&self.id);
+        resources::unregister(&self.gl, self.resource);
     }
 }
 
@@ -100,14 +212,16 @@ pub struct UniformBuffer {
     gl: Gl,
     id: crate::gl::Buffer,
     offsets: Vec<usize>,
-    data: Vec<f32>
+    data: Vec<f32>,
+    resource: usize
 }
 
 impl UniformBuffer
 {
     pub fn new(gl: &Gl, sizes: &[u32]) -> Result<UniformBuffer, Error>
     {
-        let id = gl.create_buffer().unwrap();
+        let id = gl.create_buffer()
+            .ok_or_else(|| Error::FailedToCreateBuffer {message: "Failed to create uniform buffer".to_string()})?;
 
         let mut offsets = Vec::new();
         let mut length = 0;
@@ -116,7 +230,8 @@ impl UniformBuffer
             offsets.push(length);
             length += *size as usize;
         }
-        Ok(UniformBuffer{ gl: gl.clone(), id, offsets, data: vec![0.0; length as usize] })
+        let resource = resources::register(gl, ResourceCategory::UniformBuffer, length * std::mem::size_of::<f32>());
+        Ok(UniformBuffer{ gl: gl.clone(), id, offsets, data: vec![0.0; length as usize], resource })
     }
 
     pub(crate) fn bind(&self, id: u32)
@@ -134,7 +249,7 @@ impl UniformBuffer
         self.data.splice(offset..offset+length, data.iter().cloned());
         self.send();
         //TODO: Send to GPU (glBufferSubData)
-        Ok(())
+        check_gl_errors(&self.gl, "updating a uniform buffer")
     }
 
     pub fn get(&self, index: usize) -> Result<&[f32], Error>
@@ -160,6 +275,13 @@ impl UniformBuffer
         self.gl.buffer_data_f32(consts::UNIFORM_BUFFER, &self.data, consts::STATIC_DRAW);
         self.gl.unbind_buffer(consts::UNIFORM_BUFFER);
     }
+
+    /// Names this uniform buffer in graphics debuggers such as RenderDoc (KHR_debug) and in the report of `resources`.
+    pub fn set_label(&self, label: &str)
+    {
+        self.gl.object_label(consts::BUFFER, &self.id, label);
+        resources::set_label(&self.gl, self.resource, label);
+    }
 }
 
 impl Drop for UniformBuffer
@@ -167,6 +289,7 @@ impl Drop for UniformBuffer
     fn drop(&mut self)
     {
         self.gl.delete_buffer(&self.id);
+        resources::unregister(&self.gl, self.resource);
     }
 }
 
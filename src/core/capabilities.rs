@@ -0,0 +1,95 @@
+use crate::core::*;
+
+// Defined by the EXT_texture_filter_anisotropic extension, the value is the same on desktop and web.
+const MAX_TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84FF;
+
+/// The version, limits and extensions of a context, which are queried once when the context is created, see `capabilities`.
+#[derive(Clone, Debug, Default)]
+pub struct Capabilities {
+    /// The OpenGL or WebGL version reported by the driver.
+    pub version: String,
+    pub shading_language_version: String,
+    pub renderer: String,
+    /// The names of the supported extensions, for example `GL_KHR_debug` on desktop and `EXT_color_buffer_float` on web.
+    pub extensions: Vec<String>,
+    pub max_texture_size: usize,
+    pub max_cube_map_texture_size: usize,
+    pub max_array_texture_layers: usize,
+    pub max_draw_buffers: usize,
+    pub max_color_attachments: usize,
+    pub max_samples: u32,
+    /// The maximum anisotropy of `Sampler::set_anisotropy` or `None` if anisotropic filtering is not supported.
+    pub max_anisotropy: Option<f32>,
+    /// Whether 16 and 32 bit float textures can be rendered to, which requires `EXT_color_buffer_float` on OpenGL ES and web.
+    pub float_render_targets: bool,
+    /// Whether 32 bit float textures can be linearly filtered, which requires `OES_texture_float_linear` on OpenGL ES and web.
+    pub float_linear_filtering: bool,
+    /// Whether depth textures can be linearly filtered without a depth compare `Sampler`, which is not the case on OpenGL ES and web.
+    pub depth_linear_filtering: bool,
+    /// Whether `DrawIndirectBuffer` and the indirect draws of `Program` are supported, which requires OpenGL 4.3 or `ARB_multi_draw_indirect`.
+    pub multi_draw_indirect: bool,
+    /// Whether the driver reports messages through `DEBUG_OUTPUT`, which requires OpenGL 4.3 or `KHR_debug` and is not available on web.
+    pub debug_output: bool
+}
+
+impl Capabilities {
+    pub(crate) fn query(gl: &crate::gl::Glstruct) -> Capabilities
+    {
+        let extensions = gl.get_extensions();
+        // On the web, an extension is only enabled when it is requested
+        #[cfg(target_arch = "wasm32")]
+        let supported = |name: &str| gl.supports_extension(name);
+        #[cfg(not(target_arch = "wasm32"))]
+        let supported = |name: &str| extensions.iter().any(|extension| extension.trim_start_matches("GL_") == name);
+
+        let desktop = cfg!(not(any(target_arch = "wasm32", feature = "gles")));
+        let max_anisotropy = if supported("EXT_texture_filter_anisotropic") || supported("ARB_texture_filter_anisotropic") {
+            Some(gl.get_parameter_f32(MAX_TEXTURE_MAX_ANISOTROPY_EXT))
+        } else { None };
+        let version = gl.get_string(consts::VERSION);
+        let multi_draw_indirect = desktop && (version_at_least(&version, 4, 3)
+            || (supported("ARB_multi_draw_indirect") && supported("ARB_base_instance")));
+        let debug_output = cfg!(not(target_arch = "wasm32")) && ((desktop && version_at_least(&version, 4, 3)) || supported("KHR_debug"));
+        Capabilities {
+            version,
+            shading_language_version: gl.get_string(consts::SHADING_LANGUAGE_VERSION),
+            renderer: gl.get_string(consts::RENDERER),
+            max_texture_size: gl.get_parameter_f32(consts::MAX_TEXTURE_SIZE) as usize,
+            max_cube_map_texture_size: gl.get_parameter_f32(consts::MAX_CUBE_MAP_TEXTURE_SIZE) as usize,
+            max_array_texture_layers: gl.get_parameter_f32(consts::MAX_ARRAY_TEXTURE_LAYERS) as usize,
+            max_draw_buffers: gl.get_parameter_f32(consts::MAX_DRAW_BUFFERS) as usize,
+            max_color_attachments: gl.get_parameter_f32(consts::MAX_COLOR_ATTACHMENTS) as usize,
+            max_samples: gl.get_parameter_f32(consts::MAX_SAMPLES) as u32,
+            max_anisotropy,
+            float_render_targets: desktop || supported("EXT_color_buffer_float"),
+            float_linear_filtering: desktop || supported("OES_texture_float_linear"),
+            depth_linear_filtering: desktop,
+            multi_draw_indirect,
+            debug_output,
+            extensions
+        }
+    }
+
+    /// Whether the extension is supported, where the `GL_` prefix of the names on desktop can be left out.
+    pub fn supports_extension(&self, name: &str) -> bool
+    {
+        let name = name.trim_start_matches("GL_");
+        self.extensions.iter().any(|extension| extension.trim_start_matches("GL_") == name)
+    }
+}
+
+// The version string of a desktop context starts with the version number, for example "4.6.0 NVIDIA 440.100"
+fn version_at_least(version: &str, major: u32, minor: u32) -> bool
+{
+    let mut numbers = version.split(|c: char| !c.is_ascii_digit()).filter_map(|number| number.parse::<u32>().ok());
+    match (numbers.next(), numbers.next()) {
+        (Some(version_major), Some(version_minor)) => (version_major, version_minor) >= (major, minor),
+        _ => false
+    }
+}
+
+/// The capabilities of the given context, which for example can be used to choose texture formats and sizes.
+pub fn capabilities(gl: &Gl) -> &Capabilities
+{
+    gl.capabilities()
+}
@@ -0,0 +1,64 @@
+use crate::core::*;
+
+/// The pixels of a `CPUTexture`, where float data is used for high dynamic range images.
+#[derive(Clone, Debug)]
+pub enum TextureData {
+    U8(Vec<u8>),
+    F32(Vec<f32>)
+}
+
+/// A decoded image which does not depend on the context, so it can be decoded on a worker thread
+/// and uploaded to a `Texture2D` with `to_texture` on the render thread, see `UploadQueue`.
+#[derive(Clone, Debug)]
+pub struct CPUTexture {
+    pub width: u32,
+    pub height: u32,
+    pub data: TextureData,
+    pub min_filter: Interpolation,
+    pub mag_filter: Interpolation,
+    pub mip_map_filter: Option<Interpolation>,
+    pub wrap_s: Wrapping,
+    pub wrap_t: Wrapping
+}
+
+impl CPUTexture {
+    /// Decodes an image, high dynamic range images (.hdr) results in float data.
+    #[cfg(feature = "image-io")]
+    pub fn from_bytes(min_filter: Interpolation, mag_filter: Interpolation, mip_map_filter: Option<Interpolation>,
+           wrap_s: Wrapping, wrap_t: Wrapping, bytes: &[u8]) -> Result<CPUTexture, Error>
+    {
+        let (width, height, data) = texture::decode_image(bytes)?;
+        Ok(CPUTexture { width, height, data, min_filter, mag_filter, mip_map_filter, wrap_s, wrap_t })
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "image-io"))]
+    pub fn from_file(min_filter: Interpolation, mag_filter: Interpolation, mip_map_filter: Option<Interpolation>,
+           wrap_s: Wrapping, wrap_t: Wrapping, path: &str) -> Result<CPUTexture, Error>
+    {
+        Self::from_bytes(min_filter, mag_filter, mip_map_filter, wrap_s, wrap_t, &std::fs::read(path)?)
+    }
+
+    /// The size of the pixel data.
+    pub fn bytes(&self) -> usize
+    {
+        match self.data {
+            TextureData::U8(ref data) => data.len(),
+            TextureData::F32(ref data) => data.len() * std::mem::size_of::<f32>()
+        }
+    }
+
+    pub fn to_texture(&self, gl: &Gl) -> Result<Texture2D, Error>
+    {
+        match self.data {
+            TextureData::U8(ref data) => Texture2D::new_with_u8(gl, self.min_filter, self.mag_filter, self.mip_map_filter,
+                                                                self.wrap_s, self.wrap_t, self.width, self.height, data),
+            TextureData::F32(ref data) => {
+                let format = texture::float_format(gl, false, self.min_filter, self.mag_filter, self.mip_map_filter);
+                let mut texture = Texture2D::new(gl, self.width as usize, self.height as usize,
+                    self.min_filter, self.mag_filter, self.mip_map_filter, self.wrap_s, self.wrap_t, format)?;
+                texture.fill_with_f32(data)?;
+                Ok(texture)
+            }
+        }
+    }
+}
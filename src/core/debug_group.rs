@@ -0,0 +1,22 @@
+use crate::core::*;
+
+/// A named group of calls shown in graphics debuggers such as RenderDoc (KHR_debug), which ends when it is dropped.
+/// Does nothing if the extension is not supported or on the web.
+pub struct DebugGroup {
+    gl: Gl
+}
+
+impl DebugGroup {
+    pub fn new(gl: &Gl, name: &str) -> DebugGroup
+    {
+        gl.push_debug_group(name);
+        DebugGroup { gl: gl.clone() }
+    }
+}
+
+impl Drop for DebugGroup {
+    fn drop(&mut self)
+    {
+        self.gl.pop_debug_group();
+    }
+}
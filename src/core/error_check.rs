@@ -0,0 +1,88 @@
+use crate::core::*;
+
+/// How errors reported by the graphics driver are handled, see `set_error_check`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ErrorCheck {
+    /// Errors are ignored. This is the default, since checking for errors forces the CPU to wait for the GPU.
+    Disabled,
+    /// Errors are returned as `Error::GLError` from the operation that caused them.
+    Enabled,
+    /// Same as `Enabled` in release builds, but panics with a backtrace in debug builds.
+    Panic
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum GLErrorType {
+    InvalidEnum,
+    InvalidValue,
+    InvalidOperation,
+    InvalidFramebufferOperation,
+    OutOfMemory,
+    Unknown(u32)
+}
+
+impl GLErrorType {
+    fn from_code(code: u32) -> GLErrorType
+    {
+        match code {
+            consts::INVALID_ENUM => GLErrorType::InvalidEnum,
+            consts::INVALID_VALUE => GLErrorType::InvalidValue,
+            consts::INVALID_OPERATION => GLErrorType::InvalidOperation,
+            consts::INVALID_FRAMEBUFFER_OPERATION => GLErrorType::InvalidFramebufferOperation,
+            consts::OUT_OF_MEMORY => GLErrorType::OutOfMemory,
+            _ => GLErrorType::Unknown(code)
+        }
+    }
+}
+
+/// Sets how errors reported by the graphics driver are handled for the given context.
+/// When enabled on desktop, the messages of the driver (KHR_debug) are also captured and included in the errors.
+pub fn set_error_check(gl: &Gl, error_check: ErrorCheck)
+{
+    gl.error_check().set(error_check);
+    gl.set_debug_messages(error_check != ErrorCheck::Disabled);
+    if error_check != ErrorCheck::Disabled {
+        // Discard errors from before error checking was enabled
+        while gl.get_error() != consts::NO_ERROR {}
+    }
+}
+
+pub fn error_check(gl: &Gl) -> ErrorCheck
+{
+    gl.error_check().get()
+}
+
+/// Returns the errors reported by the graphics driver since the last check as an `Error::GLError` where `operation` describes what was done.
+/// This is called by the operations in this crate, but can also be called after using the context directly.
+pub fn check_gl_errors(gl: &Gl, operation: &str) -> Result<(), Error>
+{
+    let error_check = gl.error_check().get();
+    if error_check == ErrorCheck::Disabled {
+        return Ok(());
+    }
+
+    let mut errors = Vec::new();
+    loop {
+        let code = gl.get_error();
+        // A lost context keeps reporting the same error
+        if code == consts::NO_ERROR || errors.len() >= 8 {
+            break;
+        }
+        errors.push(GLErrorType::from_code(code));
+    }
+    if cfg!(feature = "debug") {
+        for message in gl.take_debug_output() {
+            eprintln!("{}: {}", operation, message);
+        }
+    }
+    let messages = gl.take_debug_messages();
+    if errors.is_empty() && messages.is_empty() {
+        return Ok(());
+    }
+
+    let error = Error::GLError {operation: operation.to_string(), errors, messages};
+    if error_check == ErrorCheck::Panic && cfg!(debug_assertions) {
+        panic!("{:?}\n{}", error, std::backtrace::Backtrace::force_capture());
+    }
+    Err(error)
+}
@@ -0,0 +1,179 @@
+use crate::core::*;
+
+// The texture units and uniform buffer binding points which are saved, the crate itself only uses the first few.
+const SAVED_TEXTURE_UNITS: u32 = 16;
+const SAVED_UNIFORM_BUFFER_BINDINGS: u32 = 16;
+
+/// The state of a context as queried from the driver, which is used to restore the state of an application
+/// after rendering with this crate into its context, see `EmbeddedContext`.
+/// This includes the render state, the clear values, the viewport and the bound objects.
+#[derive(Clone, Debug)]
+pub struct ExternalState {
+    enabled: Vec<(u32, bool)>,
+    #[cfg(not(target_arch = "wasm32"))]
+    debug_output: Option<bool>,
+    blend_func: [i32; 4],
+    blend_equation: [i32; 2],
+    blend_color: [f32; 4],
+    color_mask: [i32; 4],
+    cull_face: i32,
+    depth_func: i32,
+    depth_mask: i32,
+    stencil_func: [i32; 3],
+    stencil_op: [i32; 3],
+    stencil_mask: i32,
+    polygon_offset: [f32; 2],
+    line_width: f32,
+    #[cfg(not(feature = "gles"))]
+    polygon_mode: [i32; 2],
+    scissor: [i32; 4],
+    viewport: [i32; 4],
+    clear_color: [f32; 4],
+    clear_depth: f32,
+    clear_stencil: i32,
+    pack_alignment: i32,
+    unpack_alignment: i32,
+    program: i32,
+    vertex_array: i32,
+    array_buffer: i32,
+    uniform_buffer: i32,
+    uniform_buffer_bindings: Vec<i32>,
+    draw_framebuffer: i32,
+    read_framebuffer: i32,
+    renderbuffer: i32,
+    active_texture: i32,
+    textures: Vec<[i32; 4]>
+}
+
+const CAPABILITIES: [u32; 6] = [consts::BLEND, consts::CULL_FACE, consts::DEPTH_TEST, consts::STENCIL_TEST, consts::SCISSOR_TEST, consts::POLYGON_OFFSET_FILL];
+
+impl ExternalState {
+    /// Queries the current state of the context. This forces the CPU to wait for the GPU, so it should be done at most once per frame.
+    pub fn save(gl: &Gl) -> ExternalState
+    {
+        let integer = |pname: u32| { let mut value = [0]; gl.get_parameter_i32v(pname, &mut value); value[0] };
+
+        let mut state = ExternalState {
+            enabled: CAPABILITIES.iter().map(|cap| (*cap, gl.is_enabled(*cap))).collect(),
+            // Changed by `set_error_check`
+            #[cfg(not(target_arch = "wasm32"))]
+            debug_output: if capabilities(gl).debug_output { Some(gl.is_enabled(consts::DEBUG_OUTPUT)) } else { None },
+            blend_func: [integer(consts::BLEND_SRC_RGB), integer(consts::BLEND_DST_RGB), integer(consts::BLEND_SRC_ALPHA), integer(consts::BLEND_DST_ALPHA)],
+            blend_equation: [integer(consts::BLEND_EQUATION_RGB), integer(consts::BLEND_EQUATION_ALPHA)],
+            blend_color: [0.0; 4],
+            color_mask: [0; 4],
+            cull_face: integer(consts::CULL_FACE_MODE),
+            depth_func: integer(consts::DEPTH_FUNC),
+            depth_mask: integer(consts::DEPTH_WRITEMASK),
+            stencil_func: [integer(consts::STENCIL_FUNC), integer(consts::STENCIL_REF), integer(consts::STENCIL_VALUE_MASK)],
+            stencil_op: [integer(consts::STENCIL_FAIL), integer(consts::STENCIL_PASS_DEPTH_FAIL), integer(consts::STENCIL_PASS_DEPTH_PASS)],
+            stencil_mask: integer(consts::STENCIL_WRITEMASK),
+            polygon_offset: [gl.get_parameter_f32(consts::POLYGON_OFFSET_FACTOR), gl.get_parameter_f32(consts::POLYGON_OFFSET_UNITS)],
+            line_width: gl.get_parameter_f32(consts::LINE_WIDTH),
+            #[cfg(not(feature = "gles"))]
+            polygon_mode: [0; 2],
+            scissor: [0; 4],
+            viewport: [0; 4],
+            clear_color: [0.0; 4],
+            clear_depth: gl.get_parameter_f32(consts::DEPTH_CLEAR_VALUE),
+            clear_stencil: integer(consts::STENCIL_CLEAR_VALUE),
+            pack_alignment: integer(consts::PACK_ALIGNMENT),
+            unpack_alignment: integer(consts::UNPACK_ALIGNMENT),
+            program: integer(consts::CURRENT_PROGRAM),
+            vertex_array: integer(consts::VERTEX_ARRAY_BINDING),
+            array_buffer: integer(consts::ARRAY_BUFFER_BINDING),
+            uniform_buffer: integer(consts::UNIFORM_BUFFER_BINDING),
+            uniform_buffer_bindings: (0..SAVED_UNIFORM_BUFFER_BINDINGS).map(|index| gl.get_parameter_indexed_i32(consts::UNIFORM_BUFFER_BINDING, index)).collect(),
+            draw_framebuffer: integer(consts::DRAW_FRAMEBUFFER_BINDING),
+            read_framebuffer: integer(consts::READ_FRAMEBUFFER_BINDING),
+            renderbuffer: integer(consts::RENDERBUFFER_BINDING),
+            active_texture: integer(consts::ACTIVE_TEXTURE),
+            textures: Vec::new()
+        };
+        gl.get_parameter_f32v(consts::BLEND_COLOR, &mut state.blend_color);
+        gl.get_parameter_i32v(consts::COLOR_WRITEMASK, &mut state.color_mask);
+        #[cfg(not(feature = "gles"))]
+        gl.get_parameter_i32v(consts::POLYGON_MODE, &mut state.polygon_mode);
+        gl.get_parameter_i32v(consts::SCISSOR_BOX, &mut state.scissor);
+        gl.get_parameter_i32v(consts::VIEWPORT, &mut state.viewport);
+        gl.get_parameter_f32v(consts::COLOR_CLEAR_VALUE, &mut state.clear_color);
+
+        let texture_units = SAVED_TEXTURE_UNITS.min(gl.get_parameter_f32(consts::MAX_COMBINED_TEXTURE_IMAGE_UNITS) as u32);
+        state.textures = (0..texture_units).map(|unit| {
+            gl.active_texture(consts::TEXTURE0 + unit);
+            [integer(consts::TEXTURE_BINDING_2D), integer(consts::TEXTURE_BINDING_2D_ARRAY), integer(consts::TEXTURE_BINDING_CUBE_MAP), integer(consts::SAMPLER_BINDING)]
+        }).collect();
+        gl.active_texture(state.active_texture as u32);
+        state
+    }
+
+    /// The framebuffer which was bound for drawing.
+    pub(crate) fn draw_framebuffer(&self) -> crate::gl::Framebuffer
+    {
+        self.draw_framebuffer as u32
+    }
+
+    /// Sets the saved state and marks the render state cache as unknown, see `state::invalidate_state`.
+    pub fn restore(&self, gl: &Gl)
+    {
+        for (cap, enabled) in self.enabled.iter() {
+            if *enabled { gl.enable(*cap) } else { gl.disable(*cap) }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(enabled) = self.debug_output {
+            if enabled { gl.enable(consts::DEBUG_OUTPUT) } else { gl.disable(consts::DEBUG_OUTPUT) }
+        }
+        let [src_rgb, dst_rgb, src_alpha, dst_alpha] = self.blend_func;
+        gl.blend_func_separate(src_rgb as u32, dst_rgb as u32, src_alpha as u32, dst_alpha as u32);
+        gl.blend_equation_separate(self.blend_equation[0] as u32, self.blend_equation[1] as u32);
+        let [red, green, blue, alpha] = self.blend_color;
+        gl.blend_color(red, green, blue, alpha);
+        let [red, green, blue, alpha] = self.color_mask;
+        gl.color_mask(red != 0, green != 0, blue != 0, alpha != 0);
+        gl.cull_face(self.cull_face as u32);
+        gl.depth_func(self.depth_func as u32);
+        gl.depth_mask(self.depth_mask != 0);
+        gl.stencil_func(self.stencil_func[0] as u32, self.stencil_func[1], self.stencil_func[2] as u32);
+        gl.stencil_op(self.stencil_op[0] as u32, self.stencil_op[1] as u32, self.stencil_op[2] as u32);
+        gl.stencil_mask(self.stencil_mask as u32);
+        gl.polygon_offset(self.polygon_offset[0], self.polygon_offset[1]);
+        gl.line_width(self.line_width);
+        #[cfg(not(feature = "gles"))]
+        {
+            // The front and back modes are always the same in a core profile
+            gl.polygon_mode(consts::FRONT_AND_BACK, self.polygon_mode[0] as u32);
+        }
+        let [x, y, width, height] = self.scissor;
+        gl.scissor(x, y, width as usize, height as usize);
+        let [x, y, width, height] = self.viewport;
+        gl.viewport(x, y, width as usize, height as usize);
+        let [red, green, blue, alpha] = self.clear_color;
+        gl.clear_color(red, green, blue, alpha);
+        gl.clear_depth(self.clear_depth);
+        gl.clear_stencil(self.clear_stencil);
+        gl.pixel_storei(consts::PACK_ALIGNMENT, self.pack_alignment);
+        gl.pixel_storei(consts::UNPACK_ALIGNMENT, self.unpack_alignment);
+
+        for (unit, [texture_2d, texture_2d_array, texture_cube_map, sampler]) in self.textures.iter().enumerate() {
+            gl.active_texture(consts::TEXTURE0 + unit as u32);
+            gl.bind_texture(consts::TEXTURE_2D, &(*texture_2d as u32));
+            gl.bind_texture(consts::TEXTURE_2D_ARRAY, &(*texture_2d_array as u32));
+            gl.bind_texture(consts::TEXTURE_CUBE_MAP, &(*texture_cube_map as u32));
+            let sampler = *sampler as u32;
+            gl.bind_sampler(unit as u32, if sampler == 0 { None } else { Some(&sampler) });
+        }
+        gl.active_texture(self.active_texture as u32);
+        for (index, buffer) in self.uniform_buffer_bindings.iter().enumerate() {
+            gl.bind_buffer_base(consts::UNIFORM_BUFFER, index as u32, &(*buffer as u32));
+        }
+        gl.bind_buffer(consts::UNIFORM_BUFFER, &(self.uniform_buffer as u32));
+        gl.bind_vertex_array(&(self.vertex_array as u32));
+        gl.bind_buffer(consts::ARRAY_BUFFER, &(self.array_buffer as u32));
+        gl.bind_framebuffer(consts::DRAW_FRAMEBUFFER, Some(&(self.draw_framebuffer as u32)));
+        gl.bind_framebuffer(consts::READ_FRAMEBUFFER, Some(&(self.read_framebuffer as u32)));
+        gl.bind_renderbuffer(consts::RENDERBUFFER, Some(&(self.renderbuffer as u32)));
+        if self.program == 0 { gl.unuse_program() } else { gl.use_program(&(self.program as u32)) }
+
+        state::invalidate_state(gl);
+    }
+}
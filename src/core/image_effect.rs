@@ -11,14 +11,7 @@ impl ImageEffect {
     pub fn new(gl: &Gl, fragment_shader: &str) -> Result<Self, Error>
     {
         let program = program::Program::from_source(&gl,
-                                                    "in vec3 position;
-                                                    in vec2 uv_coordinate;
-                                                    out vec2 uv;
-                                                    void main()
-                                                    {
-                                                        uv = uv_coordinate;
-                                                        gl_Position = vec4(position, 1.0);
-                                                    }",
+                                                    include_str!("shaders/image_effect.vert"),
                                                     fragment_shader)?;
 
         let positions = vec![
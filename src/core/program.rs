@@ -6,9 +6,12 @@ pub struct Program {
     gl: Gl,
     id: crate::gl::Program,
     vertex_attributes: HashMap<String, u32>,
+    // All of the locations which are used by the vertex attributes, where a matrix uses a location per column
+    vertex_attribute_locations: Vec<u32>,
     textures: RefCell<HashMap<String, u32>>,
     uniforms: HashMap<String, crate::gl::UniformLocation>,
-    uniform_blocks: RefCell<HashMap<String, (u32, u32)>>
+    uniform_blocks: RefCell<HashMap<String, (u32, u32)>>,
+    resource: usize
 }
 
 impl Program
@@ -43,11 +46,14 @@ impl Program
         // Init vertex attributes
         let num_attribs = gl.get_program_parameter(&id, consts::ACTIVE_ATTRIBUTES);
         let mut vertex_attributes = HashMap::new();
+        let mut vertex_attribute_locations = Vec::new();
         for i in 0..num_attribs {
             let info = gl.get_active_attrib(&id, i);
             let location = gl.get_attrib_location(&id, &info.name()).unwrap();
             //println!("Attribute location: {}, name: {}, type: {}, size: {}", location, info.name(), info.type_(), info.size());
             vertex_attributes.insert(info.name(), location);
+            let columns = if info.type_() == consts::FLOAT_MAT4 { 4 } else { 1 };
+            vertex_attribute_locations.extend(location..location + columns);
         }
 
         // Init uniforms
@@ -62,8 +68,11 @@ impl Program
             }
         }
 
-        Ok(Program { gl: gl.clone(), id, vertex_attributes, uniforms, uniform_blocks: RefCell::new(HashMap::new()),
-            textures: RefCell::new(HashMap::new())})
+        let resource = resources::register(gl, ResourceCategory::Program, 0);
+        let program = Program { gl: gl.clone(), id, vertex_attributes, vertex_attribute_locations, uniforms, uniform_blocks: RefCell::new(HashMap::new()),
+            textures: RefCell::new(HashMap::new()), resource };
+        check_gl_errors(gl, "creating a program")?;
+        Ok(program)
     }
 
     pub fn add_uniform_int(&self, name: &str, data: &i32) -> Result<(), Error>
@@ -138,16 +147,31 @@ impl Program
     }
 
     pub fn use_texture(&self, texture: &dyn Texture, texture_name: &str) -> Result<(), Error>
+    {
+        let index = self.texture_index(texture_name);
+        self.gl.bind_sampler(index, None);
+        texture.bind(index);
+        self.add_uniform_int(texture_name, &(index as i32))?;
+        Ok(())
+    }
+
+    pub fn use_texture_with_sampler(&self, texture: &dyn Texture, sampler: &Sampler, texture_name: &str) -> Result<(), Error>
+    {
+        let index = self.texture_index(texture_name);
+        sampler.bind(index);
+        texture.bind(index);
+        self.add_uniform_int(texture_name, &(index as i32))?;
+        Ok(())
+    }
+
+    fn texture_index(&self, texture_name: &str) -> u32
     {
         if !self.textures.borrow().contains_key(texture_name) {
             let mut map = self.textures.borrow_mut();
             let index = map.len() as u32;
             map.insert(texture_name.to_owned(), index);
         };
-        let index = self.textures.borrow().get(texture_name).unwrap().clone();
-        texture.bind(index);
-        self.add_uniform_int(texture_name, &(index as i32))?;
-        Ok(())
+        *self.textures.borrow().get(texture_name).unwrap()
     }
 
     pub fn use_uniform_block(&self, buffer: &buffer::UniformBuffer, block_name: &str)
@@ -179,7 +203,7 @@ impl Program
         self.gl.vertex_attrib_divisor(loc, divisor as u32);
         self.gl.unbind_buffer(consts::ARRAY_BUFFER);
         self.gl.unuse_program();
-        Ok(())
+        check_gl_errors(&self.gl, "using a vertex attribute")
     }
 
     pub fn use_attribute_vec2_float(&self, buffer: &buffer::VertexBuffer, attribute_name: &str) -> Result<(), Error>
@@ -197,7 +221,7 @@ impl Program
         self.gl.vertex_attrib_divisor(loc, divisor as u32);
         self.gl.unbind_buffer(consts::ARRAY_BUFFER);
         self.gl.unuse_program();
-        Ok(())
+        check_gl_errors(&self.gl, "using a vertex attribute")
     }
 
     pub fn use_attribute_vec3_float(&self, buffer: &buffer::VertexBuffer, attribute_name: &str) -> Result<(), Error>
@@ -215,16 +239,35 @@ impl Program
         self.gl.vertex_attrib_divisor(loc, divisor as u32);
         self.gl.unbind_buffer(consts::ARRAY_BUFFER);
         self.gl.unuse_program();
+        check_gl_errors(&self.gl, "using a vertex attribute")
+    }
+
+    pub fn use_attribute_mat4_float(&self, buffer: &buffer::VertexBuffer, attribute_name: &str) -> Result<(), Error>
+    {
+        self.use_attribute_mat4_float_divisor(buffer, attribute_name, 0)?;
         Ok(())
     }
 
+    /// Uses 16 floats of the buffer per element as a column major matrix, which occupies four consecutive attribute locations.
+    pub fn use_attribute_mat4_float_divisor(&self, buffer: &buffer::VertexBuffer, attribute_name: &str, divisor: usize) -> Result<(), Error>
+    {
+        buffer.bind();
+        let loc = self.location(&attribute_name)?;
+        for column in 0..4 {
+            self.gl.enable_vertex_attrib_array(loc + column);
+            self.gl.vertex_attrib_pointer(loc + column, 4, consts::FLOAT, false, 16, 4 * column);
+            self.gl.vertex_attrib_divisor(loc + column, divisor as u32);
+        }
+        self.gl.unbind_buffer(consts::ARRAY_BUFFER);
+        self.gl.unuse_program();
+        check_gl_errors(&self.gl, "using a vertex attribute")
+    }
+
     pub fn draw_arrays(&self, count: u32)
     {
         self.set_used();
         self.gl.draw_arrays(consts::TRIANGLES, 0, count);
-        for location in self.vertex_attributes.values() {
-            self.gl.disable_vertex_attrib_array(*location);
-        }
+        self.disable_vertex_attributes();
         self.gl.unuse_program();
     }
 
@@ -233,9 +276,7 @@ impl Program
         self.set_used();
         self.gl.draw_arrays_instanced(consts::TRIANGLES, 0, count, instance_count);
         self.gl.unbind_buffer(consts::ELEMENT_ARRAY_BUFFER);
-        for location in self.vertex_attributes.values() {
-            self.gl.disable_vertex_attrib_array(*location);
-        }
+        self.disable_vertex_attributes();
         self.gl.unuse_program();
     }
 
@@ -251,9 +292,7 @@ impl Program
         self.gl.draw_elements(consts::TRIANGLES, count, consts::UNSIGNED_INT, first);
         self.gl.unbind_buffer(consts::ELEMENT_ARRAY_BUFFER);
 
-        for location in self.vertex_attributes.values() {
-            self.gl.disable_vertex_attrib_array(*location);
-        }
+        self.disable_vertex_attributes();
         self.gl.unuse_program();
     }
 
@@ -263,10 +302,43 @@ impl Program
         element_buffer.bind();
         self.gl.draw_elements_instanced(consts::TRIANGLES, element_buffer.count() as u32, consts::UNSIGNED_INT, 0, count);
         self.gl.unbind_buffer(consts::ELEMENT_ARRAY_BUFFER);
-        for location in self.vertex_attributes.values() {
+        self.disable_vertex_attributes();
+        self.gl.unuse_program();
+    }
+
+    /// Draws with the command at the given index of the draw indirect buffer.
+    #[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+    pub fn draw_elements_indirect(&self, element_buffer: &buffer::ElementBuffer, indirect_buffer: &buffer::DrawIndirectBuffer, index: u32)
+    {
+        self.set_used();
+        element_buffer.bind();
+        indirect_buffer.bind();
+        self.gl.draw_elements_indirect(consts::TRIANGLES, consts::UNSIGNED_INT, index * std::mem::size_of::<buffer::DrawElementsIndirectCommand>() as u32);
+        self.gl.unbind_buffer(consts::DRAW_INDIRECT_BUFFER);
+        self.gl.unbind_buffer(consts::ELEMENT_ARRAY_BUFFER);
+        self.disable_vertex_attributes();
+        self.gl.unuse_program();
+    }
+
+    /// Draws with all of the commands of the draw indirect buffer in a single call.
+    #[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+    pub fn multi_draw_elements_indirect(&self, element_buffer: &buffer::ElementBuffer, indirect_buffer: &buffer::DrawIndirectBuffer)
+    {
+        self.set_used();
+        element_buffer.bind();
+        indirect_buffer.bind();
+        self.gl.multi_draw_elements_indirect(consts::TRIANGLES, consts::UNSIGNED_INT, 0, indirect_buffer.count() as u32, 0);
+        self.gl.unbind_buffer(consts::DRAW_INDIRECT_BUFFER);
+        self.gl.unbind_buffer(consts::ELEMENT_ARRAY_BUFFER);
+        self.disable_vertex_attributes();
+        self.gl.unuse_program();
+    }
+
+    fn disable_vertex_attributes(&self)
+    {
+        for location in self.vertex_attribute_locations.iter() {
             self.gl.disable_vertex_attrib_array(*location);
         }
-        self.gl.unuse_program();
     }
 
     fn location(&self, name: &str) -> Result<u32, Error>
@@ -280,10 +352,37 @@ impl Program
     fn set_used(&self) {
         self.gl.use_program(&self.id);
     }
+
+    /// Names this program in graphics debuggers such as RenderDoc (KHR_debug) and in the report of `resources`.
+    pub fn set_label(&self, label: &str)
+    {
+        self.gl.object_label(consts::PROGRAM, &self.id, label);
+        resources::set_label(&self.gl, self.resource, label);
+    }
 }
 
 impl Drop for Program {
     fn drop(&mut self) {
         self.gl.delete_program(&self.id);
+        resources::unregister(&self.gl, self.resource);
+    }
+}
+
+/// The sources of a program, which can be loaded or generated on a worker thread and compiled with `to_program` on the render thread, see `UploadQueue`.
+#[derive(Clone, Debug)]
+pub struct ProgramSource {
+    pub vertex_shader: String,
+    pub fragment_shader: String
+}
+
+impl ProgramSource {
+    pub fn new(vertex_shader: &str, fragment_shader: &str) -> ProgramSource
+    {
+        ProgramSource { vertex_shader: vertex_shader.to_string(), fragment_shader: fragment_shader.to_string() }
     }
-}
\ No newline at end of file
+
+    pub fn to_program(&self, gl: &Gl) -> Result<Program, Error>
+    {
+        Program::from_source(gl, &self.vertex_shader, &self.fragment_shader)
+    }
+}
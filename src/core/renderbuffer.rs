@@ -0,0 +1,69 @@
+use crate::core::Error;
+use crate::core::error_check::check_gl_errors;
+use crate::core::texture::Format;
+use crate::gl::Gl;
+use crate::gl::consts;
+
+/// A render target which can only be written to and not sampled, but which supports multisampling.
+/// Attach it to a `RenderTarget` and use `RenderTarget::resolve` to copy the content of a multisampled renderbuffer into a texture.
+pub struct Renderbuffer {
+    gl: Gl,
+    id: crate::gl::Renderbuffer,
+    pub width: usize,
+    pub height: usize,
+    pub samples: u32,
+    format: Format
+}
+
+impl Renderbuffer
+{
+    pub fn new(gl: &Gl, width: usize, height: usize, format: Format) -> Result<Renderbuffer, Error>
+    {
+        Self::new_multisample(gl, width, height, format, 0)
+    }
+
+    /// The number of samples is clamped to the maximum number of samples supported by the context.
+    pub fn new_multisample(gl: &Gl, width: usize, height: usize, format: Format, samples: u32) -> Result<Renderbuffer, Error>
+    {
+        let id = gl.create_renderbuffer()
+            .ok_or_else(|| Error::FailedToCreateFramebuffer {message: "Failed to create renderbuffer".to_string()})?;
+        let samples = samples.min(crate::core::capabilities(gl).max_samples);
+        gl.bind_renderbuffer(consts::RENDERBUFFER, Some(&id));
+        gl.renderbuffer_storage_multisample(consts::RENDERBUFFER, samples, format as u32, width as u32, height as u32);
+        gl.bind_renderbuffer(consts::RENDERBUFFER, None);
+        let renderbuffer = Self { gl: gl.clone(), id, width, height, samples, format };
+        check_gl_errors(gl, "creating a renderbuffer")?;
+        Ok(renderbuffer)
+    }
+
+    pub fn format(&self) -> Format
+    {
+        self.format
+    }
+
+    pub(crate) fn bind_as_color_target(&self, channel: usize)
+    {
+        self.gl.framebuffer_renderbuffer(consts::DRAW_FRAMEBUFFER,
+                       consts::COLOR_ATTACHMENT0 + channel as u32, consts::RENDERBUFFER, Some(&self.id));
+    }
+
+    pub(crate) fn bind_as_depth_target(&self)
+    {
+        self.gl.framebuffer_renderbuffer(consts::DRAW_FRAMEBUFFER,
+                       self.format.depth_attachment(), consts::RENDERBUFFER, Some(&self.id));
+    }
+
+    /// Names this renderbuffer in graphics debuggers such as RenderDoc (KHR_debug).
+    pub fn set_label(&self, label: &str)
+    {
+        self.gl.object_label(consts::RENDERBUFFER, &self.id, label);
+    }
+}
+
+impl Drop for Renderbuffer
+{
+    fn drop(&mut self)
+    {
+        self.gl.delete_renderbuffer(&self.id);
+    }
+}
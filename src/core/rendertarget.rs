@@ -6,35 +6,46 @@ impl Screen {
     pub fn write(gl: &Gl, x: i32, y: i32, width: usize, height: usize,
                           clear_color: Option<&Vec4>, clear_depth: Option<f32>, render: &dyn Fn()) -> Result<(), Error>
     {
-        gl.viewport(x, y, width, height);
-        gl.bind_framebuffer(consts::DRAW_FRAMEBUFFER, None);
-        RenderTarget::clear(gl, clear_color, clear_depth);
+        Self::write_with_stencil(gl, x, y, width, height, clear_color, clear_depth, None, render)
+    }
+
+    pub fn write_with_stencil(gl: &Gl, x: i32, y: i32, width: usize, height: usize,
+                          clear_color: Option<&Vec4>, clear_depth: Option<f32>, clear_stencil: Option<i32>, render: &dyn Fn()) -> Result<(), Error>
+    {
+        let viewport = Viewport {x, y, width, height};
+        push_viewport(gl, None, viewport, Some(viewport));
+        RenderTarget::clear(gl, clear_color, clear_depth, clear_stencil);
         render();
-        Ok(())
+        pop_viewport(gl);
+        check_gl_errors(gl, "writing to the screen")
     }
 
-    // TODO: Possible to change format
-    #[cfg(not(target_arch = "wasm32"))]
+    /// Reads the color of the given rectangle of the screen as RGB with 8 bits per channel, starting with the bottom row.
     pub fn read_color(gl: &Gl, x: i32, y: i32, width: usize, height: usize) -> Result<Vec<u8>, Error>
     {
-        gl.viewport(x, y, width, height);
-        let mut pixels = vec![0u8; width * height * 3];
-        gl.bind_framebuffer(consts::READ_FRAMEBUFFER, None);
-        gl.read_pixels_with_u8_data(x as u32, y as u32, width as u32, height as u32, consts::RGB,
-                            consts::UNSIGNED_BYTE, &mut pixels);
-        Ok(pixels)
+        let pixels = Self::read_color_rgba(gl, x, y, width, height)?;
+        Ok(pixels.chunks(4).flat_map(|pixel| pixel[0..3].iter().cloned()).collect())
+    }
+
+    /// Reads the color of the given rectangle of the screen as RGBA with 8 bits per channel, starting with the bottom row.
+    pub fn read_color_rgba(gl: &Gl, x: i32, y: i32, width: usize, height: usize) -> Result<Vec<u8>, Error>
+    {
+        read_color_u8(gl, None, Viewport {x, y, width, height})
+    }
+
+    /// Reads the color of the given rectangle of the screen as RGBA floats, starting with the bottom row.
+    /// The screen has 8 bits per channel, so it is read as bytes which is supported everywhere, including WebGL and OpenGL ES, and converted to floats.
+    pub fn read_color_f32(gl: &Gl, x: i32, y: i32, width: usize, height: usize) -> Result<Vec<f32>, Error>
+    {
+        let pixels = Self::read_color_rgba(gl, x, y, width, height)?;
+        Ok(pixels.iter().map(|value| *value as f32 / 255.0).collect())
     }
 
-    // TODO: Possible to change format
-    #[cfg(not(target_arch = "wasm32"))]
+    /// Reads the depth of the given rectangle of the screen, starting with the bottom row. Not available on web or OpenGL ES, since WebGL and OpenGL ES do not support reading depth.
+    #[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
     pub fn read_depth(gl: &Gl, x: i32, y: i32, width: usize, height: usize) -> Result<Vec<f32>, Error>
     {
-        gl.viewport(x, y, width, height);
-        let mut pixels = vec![0f32; width * height];
-        gl.bind_framebuffer(consts::READ_FRAMEBUFFER, None);
-        gl.read_pixels_with_f32_data(x as u32, y as u32, width as u32, height as u32,
-                        consts::DEPTH_COMPONENT, consts::FLOAT, &mut pixels);
-        Ok(pixels)
+        read_depth(gl, None, Viewport {x, y, width, height})
     }
 
     #[cfg(all(not(target_arch = "wasm32"), feature = "image-io"))]
@@ -54,211 +65,488 @@ impl Screen {
         image::save_buffer(&std::path::Path::new(path), &pixels_out, width as u32, height as u32, image::RGB(8))?;
         Ok(())
     }
+
+    /// Copies the `source` rectangle of the screen into the `destination` rectangle of the render target.
+    pub fn blit_to(gl: &Gl, source: Viewport, target: &RenderTarget, destination: Viewport,
+                   blit_type: BlitType, filter: Interpolation) -> Result<(), Error>
+    {
+        target.check_blit_type(blit_type)?;
+        blit(gl, None, None, source, Some(&target.id), destination, blit_type, filter)
+    }
 }
 
-pub struct RenderTarget {}
+/// Which buffers a blit copies.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BlitType {
+    Color,
+    Depth,
+    ColorAndDepth
+}
 
-impl RenderTarget
-{
-    pub fn write_to_color(gl: &Gl, x: i32, y: i32, width: usize, height: usize,
-                          clear_color: Option<&Vec4>, color_texture: Option<&Texture2D>, render: &dyn Fn()) -> Result<(), Error>
+/// A texture, a layer of a texture or a renderbuffer which is attached to a `RenderTarget`.
+pub enum Attachment {
+    Texture2D {texture: Texture2D, mip_level: usize},
+    /// When used as color attachment, each layer is written to the color output with the same index as the layer has in `layers`.
+    /// When used as depth attachment, only the first layer is used.
+    Texture2DArray {texture: Texture2DArray, layers: Vec<usize>},
+    TextureCubeMap {texture: TextureCubeMap, side: CubeMapSide, mip_level: usize},
+    Renderbuffer(Renderbuffer)
+}
+
+impl Attachment {
+    pub fn as_texture_2d(&self) -> Option<&Texture2D>
     {
-        Self::write(gl, x, y, width, height, clear_color, None, color_texture, None, render)
+        match self { Attachment::Texture2D {texture, ..} => Some(texture), _ => None }
     }
 
-    pub fn write_to_depth(gl: &Gl, x: i32, y: i32, width: usize, height: usize,
-                          clear_depth: Option<f32>, depth_texture: Option<&Texture2D>, render: &dyn Fn()) -> Result<(), Error>
+    pub fn as_texture_2d_array(&self) -> Option<&Texture2DArray>
     {
-        Self::write(gl, x, y, width, height, None, clear_depth, None, depth_texture, render)
+        match self { Attachment::Texture2DArray {texture, ..} => Some(texture), _ => None }
     }
 
-    pub fn write(gl: &Gl, x: i32, y: i32, width: usize, height: usize,
-                 clear_color: Option<&Vec4>, clear_depth: Option<f32>,
-                 color_texture: Option<&Texture2D>, depth_texture: Option<&Texture2D>,
-                 render: &dyn Fn()) -> Result<(), Error>
+    pub fn as_texture_cube_map(&self) -> Option<&TextureCubeMap>
     {
-        gl.viewport(x, y, width, height);
-        let id = RenderTarget::new_framebuffer(gl, if color_texture.is_some() {1} else {0})?;
+        match self { Attachment::TextureCubeMap {texture, ..} => Some(texture), _ => None }
+    }
+
+    pub fn as_renderbuffer(&self) -> Option<&Renderbuffer>
+    {
+        match self { Attachment::Renderbuffer(renderbuffer) => Some(renderbuffer), _ => None }
+    }
 
-        if let Some(color_texture) = color_texture {
-            color_texture.bind_as_color_target(0);
+    fn size(&self) -> (usize, usize)
+    {
+        let mip_size = |size: usize, mip_level: usize| (size >> mip_level).max(1);
+        match self {
+            Attachment::Texture2D {texture, mip_level} => (mip_size(texture.width, *mip_level), mip_size(texture.height, *mip_level)),
+            Attachment::Texture2DArray {texture, ..} => (texture.width, texture.height),
+            Attachment::TextureCubeMap {texture, mip_level, ..} => (mip_size(texture.width, *mip_level), mip_size(texture.height, *mip_level)),
+            Attachment::Renderbuffer(renderbuffer) => (renderbuffer.width, renderbuffer.height)
         }
+    }
+
+    fn color_channel_count(&self) -> usize
+    {
+        match self { Attachment::Texture2DArray {layers, ..} => layers.len(), _ => 1 }
+    }
 
-        if let Some(depth_texture) = depth_texture {
-            depth_texture.bind_as_depth_target();
+    fn bind_as_color_target(&self)
+    {
+        match self {
+            Attachment::Texture2D {texture, mip_level} => texture.bind_as_color_target(0, *mip_level),
+            Attachment::Texture2DArray {texture, layers} => {
+                for (channel, layer) in layers.iter().enumerate() {
+                    texture.bind_as_color_target(*layer, channel);
+                }
+            },
+            Attachment::TextureCubeMap {texture, side, mip_level} => texture.bind_as_color_target(*side, 0, *mip_level),
+            Attachment::Renderbuffer(renderbuffer) => renderbuffer.bind_as_color_target(0)
         }
+    }
 
-        #[cfg(feature = "debug")]
-        {
-            gl.check_framebuffer_status().or_else(|message| Err(Error::FailedToCreateFramebuffer {message}))?;
+    fn bind_as_depth_target(&self)
+    {
+        match self {
+            Attachment::Texture2D {texture, mip_level} => texture.bind_as_depth_target(*mip_level),
+            Attachment::Texture2DArray {texture, layers} => texture.bind_as_depth_target(layers.first().cloned().unwrap_or(0)),
+            Attachment::TextureCubeMap {texture, side, mip_level} => texture.bind_as_depth_target(*side, *mip_level),
+            Attachment::Renderbuffer(renderbuffer) => renderbuffer.bind_as_depth_target()
         }
-        RenderTarget::clear(gl, clear_color, clear_depth);
+    }
 
-        render();
+    fn generate_mip_maps(&self)
+    {
+        match self {
+            Attachment::Texture2D {texture, ..} => texture.generate_mip_maps(),
+            Attachment::Texture2DArray {texture, ..} => texture.generate_mip_maps(),
+            Attachment::TextureCubeMap {texture, ..} => texture.generate_mip_maps(),
+            Attachment::Renderbuffer(_) => {}
+        }
+    }
+}
 
-        gl.delete_framebuffer(Some(&id));
+/// A framebuffer together with the textures and renderbuffers which are attached to it.
+/// The framebuffer is created and validated once and can then be written to repeatedly.
+pub struct RenderTarget {
+    gl: Gl,
+    id: crate::gl::Framebuffer,
+    pub width: usize,
+    pub height: usize,
+    color: Option<Attachment>,
+    depth: Option<Attachment>
+}
 
-        if let Some(color_texture) = color_texture {
-            color_texture.generate_mip_maps();
+impl RenderTarget
+{
+    pub fn new(gl: &Gl, color: Option<Attachment>, depth: Option<Attachment>) -> Result<RenderTarget, Error>
+    {
+        let (width, height) = color.as_ref().or(depth.as_ref()).map(|a| a.size())
+            .ok_or_else(|| Error::FailedToCreateFramebuffer {message: "A render target needs at least one attachment".to_string()})?;
+        if let (Some(c), Some(d)) = (color.as_ref(), depth.as_ref()) {
+            if c.size() != d.size() {
+                Err(Error::FailedToCreateFramebuffer {message: format!("The color attachment has size {:?} but the depth attachment has size {:?}", c.size(), d.size())})?
+            }
         }
 
-        if let Some(depth_texture) = depth_texture {
-            depth_texture.generate_mip_maps();
+        let id = gl.create_framebuffer()
+            .ok_or_else(|| Error::FailedToCreateFramebuffer {message: "Failed to create framebuffer".to_string()} )?;
+        let render_target = RenderTarget { gl: gl.clone(), id, width, height, color, depth };
+        render_target.bind();
+        if let Some(ref color) = render_target.color {
+            render_target.set_draw_buffers(color.color_channel_count());
+            color.bind_as_color_target();
         }
-        Ok(())
+        if let Some(ref depth) = render_target.depth {
+            depth.bind_as_depth_target();
+        }
+        let status = gl.check_framebuffer_status().map_err(|message| Error::FailedToCreateFramebuffer {message});
+        restore_draw_framebuffer(gl);
+        status?;
+        check_gl_errors(gl, "creating a render target")?;
+        Ok(render_target)
     }
 
-    pub fn write_to_color_array(gl: &Gl, x: i32, y: i32, width: usize, height: usize,
-                                clear_color: Option<&Vec4>,
-                       color_texture_array: Option<&Texture2DArray>,
-                                color_channel_count: usize, color_channel_to_texture_layer: &dyn Fn(usize) -> usize,
-                                render: &dyn Fn()) -> Result<(), Error>
+    pub fn new_color(gl: &Gl, color_texture: Texture2D) -> Result<RenderTarget, Error>
     {
-        Self::write_array(gl, x, y, width, height, clear_color, None, color_texture_array, None, color_channel_count, color_channel_to_texture_layer, 0, render)
+        Self::new(gl, Some(Attachment::Texture2D {texture: color_texture, mip_level: 0}), None)
     }
 
-    pub fn write_to_depth_array(gl: &Gl, x: i32, y: i32, width: usize, height: usize, clear_depth: Option<f32>,
-                       depth_texture_array: Option<&Texture2DArray>, depth_layer: usize,
-                                render: &dyn Fn()) -> Result<(), Error>
+    pub fn new_depth(gl: &Gl, depth_texture: Texture2D) -> Result<RenderTarget, Error>
     {
-        Self::write_array(gl, x, y, width, height,None, clear_depth, None, depth_texture_array, 0, &|i| {i}, depth_layer, render)
+        Self::new(gl, None, Some(Attachment::Texture2D {texture: depth_texture, mip_level: 0}))
     }
 
-    pub fn write_array(gl: &Gl, x: i32, y: i32, width: usize, height: usize,
-                       clear_color: Option<&Vec4>, clear_depth: Option<f32>,
-                       color_texture_array: Option<&Texture2DArray>,
-                       depth_texture_array: Option<&Texture2DArray>,
-                       color_channel_count: usize, color_channel_to_texture_layer: &dyn Fn(usize) -> usize,
-                       depth_layer: usize, render: &dyn Fn()) -> Result<(), Error>
+    pub fn new_color_and_depth(gl: &Gl, color_texture: Texture2D, depth_texture: Texture2D) -> Result<RenderTarget, Error>
     {
-        gl.viewport(x, y, width, height);
-        let id = RenderTarget::new_framebuffer(gl, color_channel_count)?;
+        Self::new(gl, Some(Attachment::Texture2D {texture: color_texture, mip_level: 0}),
+                  Some(Attachment::Texture2D {texture: depth_texture, mip_level: 0}))
+    }
 
-        if let Some(color_texture) = color_texture_array {
-            for channel in 0..color_channel_count {
-                color_texture.bind_as_color_target(color_channel_to_texture_layer(channel), channel);
-            }
-        }
+    pub fn write(&self, clear_color: Option<&Vec4>, clear_depth: Option<f32>, render: &dyn Fn()) -> Result<(), Error>
+    {
+        self.write_with_stencil(clear_color, clear_depth, None, render)
+    }
+
+    /// Same as `write`, but also clears the stencil buffer if `clear_stencil` is specified, which requires a depth attachment with a stencil format.
+    pub fn write_with_stencil(&self, clear_color: Option<&Vec4>, clear_depth: Option<f32>, clear_stencil: Option<i32>, render: &dyn Fn()) -> Result<(), Error>
+    {
+        push_viewport(&self.gl, Some(&self.id), Viewport::new_at_origo(self.width, self.height), None);
+        RenderTarget::clear(&self.gl, clear_color, clear_depth, clear_stencil);
+        render();
+        pop_viewport(&self.gl);
+        check_gl_errors(&self.gl, "writing to a render target")
+    }
 
-        if let Some(depth_texture) = depth_texture_array {
-            depth_texture.bind_as_depth_target(depth_layer);
+    /// Copies the content of this (multisampled) render target into the target which must have the same size.
+    pub fn resolve(&self, target: &RenderTarget) -> Result<(), Error>
+    {
+        if self.width != target.width || self.height != target.height {
+            Err(Error::FailedToCreateFramebuffer {message: "Only render targets of the same size can be resolved".to_string()})?
         }
+        let blit_type = match (self.color.is_some() && target.color.is_some(), self.depth.is_some() && target.depth.is_some()) {
+            (true, true) => BlitType::ColorAndDepth,
+            (true, false) => BlitType::Color,
+            (false, true) => BlitType::Depth,
+            (false, false) => return Ok(())
+        };
+        self.blit_to(target, blit_type, Interpolation::Nearest)
+    }
+
+    /// Copies the content of this render target into the target, scaling it if the sizes differ.
+    /// Color is read from the first color attachment, which for a texture array is the first of the attached layers, and written to all color attachments of the target.
+    /// Depth can only be copied between attachments of the same format and with nearest filtering.
+    pub fn blit_to(&self, target: &RenderTarget, blit_type: BlitType, filter: Interpolation) -> Result<(), Error>
+    {
+        let source_layer = match self.color { Some(Attachment::Texture2DArray {ref layers, ..}) => layers.first().cloned().unwrap_or(0), _ => 0 };
+        self.blit_viewport_to(Viewport::new_at_origo(self.width, self.height), source_layer, target,
+                              Viewport::new_at_origo(target.width, target.height), blit_type, filter)
+    }
+
+    /// Copies the `source` rectangle of this render target into the `destination` rectangle of the target.
+    /// If the color attachment is a texture array, color is read from `source_layer` which must be one of the attached layers, otherwise `source_layer` must be 0.
+    pub fn blit_viewport_to(&self, source: Viewport, source_layer: usize, target: &RenderTarget, destination: Viewport,
+                            blit_type: BlitType, filter: Interpolation) -> Result<(), Error>
+    {
+        self.check_blit_type(blit_type)?;
+        target.check_blit_type(blit_type)?;
+        let read_buffer = self.read_buffer(blit_type, source_layer)?;
+        blit(&self.gl, Some(&self.id), read_buffer, source, Some(&target.id), destination, blit_type, filter)
+    }
 
-        #[cfg(feature = "debug")]
-        {
-            gl.check_framebuffer_status().or_else(|message| Err(Error::FailedToCreateFramebuffer {message}))?;
+    /// Copies the `source` rectangle of this render target into the `destination` rectangle of the screen, see `blit_viewport_to`.
+    pub fn blit_to_screen(&self, source: Viewport, source_layer: usize, destination: Viewport, blit_type: BlitType, filter: Interpolation) -> Result<(), Error>
+    {
+        self.check_blit_type(blit_type)?;
+        let read_buffer = self.read_buffer(blit_type, source_layer)?;
+        blit(&self.gl, Some(&self.id), read_buffer, source, None, destination, blit_type, filter)
+    }
+
+    /// Generates the mip maps of the attached textures from their base level, which is not done automatically after writing to the render target.
+    pub fn generate_mip_maps(&self)
+    {
+        if let Some(ref color) = self.color {
+            color.generate_mip_maps();
         }
-        RenderTarget::clear(gl, clear_color, clear_depth);
+        if let Some(ref depth) = self.depth {
+            depth.generate_mip_maps();
+        }
+    }
 
-        render();
+    /// Changes which layers of the texture array color attachment are written to.
+    pub fn set_color_layers(&mut self, color_layers: &[usize]) -> Result<(), Error>
+    {
+        let previous_count = self.color.as_ref().map(|c| c.color_channel_count()).unwrap_or(0);
+        match self.color {
+            Some(Attachment::Texture2DArray {ref mut layers, ..}) => *layers = color_layers.to_vec(),
+            _ => Err(Error::FailedToCreateFramebuffer {message: "The color attachment is not a texture array".to_string()})?
+        }
+        self.bind();
+        if previous_count != color_layers.len() {
+            self.set_draw_buffers(color_layers.len());
+        }
+        self.color.as_ref().unwrap().bind_as_color_target();
+        restore_draw_framebuffer(&self.gl);
+        check_gl_errors(&self.gl, "changing the attachments of a render target")
+    }
 
-        gl.delete_framebuffer(Some(&id));
-        if let Some(color_texture) = color_texture_array {
-            color_texture.generate_mip_maps();
+    /// Changes which layer of the texture array depth attachment is written to.
+    pub fn set_depth_layer(&mut self, depth_layer: usize) -> Result<(), Error>
+    {
+        match self.depth {
+            Some(Attachment::Texture2DArray {ref mut layers, ..}) => *layers = vec![depth_layer],
+            _ => Err(Error::FailedToCreateFramebuffer {message: "The depth attachment is not a texture array".to_string()})?
         }
+        self.bind();
+        self.depth.as_ref().unwrap().bind_as_depth_target();
+        restore_draw_framebuffer(&self.gl);
+        check_gl_errors(&self.gl, "changing the attachments of a render target")
+    }
 
-        if let Some(depth_texture) = depth_texture_array {
-            depth_texture.generate_mip_maps();
+    /// Changes which side and mip level of the cube map attachments are written to.
+    pub fn set_cube_map_side(&mut self, cube_map_side: CubeMapSide, cube_map_mip_level: usize) -> Result<(), Error>
+    {
+        let mut found = false;
+        for attachment in self.color.iter_mut().chain(self.depth.iter_mut()) {
+            if let Attachment::TextureCubeMap {ref mut side, ref mut mip_level, ..} = attachment {
+                *side = cube_map_side;
+                *mip_level = cube_map_mip_level;
+                found = true;
+            }
         }
-        Ok(())
+        if !found {
+            Err(Error::FailedToCreateFramebuffer {message: "None of the attachments are cube maps".to_string()})?
+        }
+        self.bind();
+        if let Some(ref color) = self.color {
+            color.bind_as_color_target();
+        }
+        if let Some(ref depth) = self.depth {
+            depth.bind_as_depth_target();
+        }
+        restore_draw_framebuffer(&self.gl);
+        let (width, height) = self.color.as_ref().or(self.depth.as_ref()).unwrap().size();
+        self.width = width;
+        self.height = height;
+        check_gl_errors(&self.gl, "changing the attachments of a render target")
     }
 
-    // TODO: Read color and depth from rendertarget to cpu
-    /*#[cfg(not(target_arch = "wasm32"))]
-    pub fn read_color(&self, x: i32, y: i32, width: usize, height: usize) -> Result<Vec<u8>, Error>
+    pub fn color_attachment(&self) -> Option<&Attachment>
     {
-        let color_texture = self.color_texture.as_ref().unwrap();
-        self.gl.viewport(x, y, width, height);
-        let mut pixels = vec![0u8; width * height * 3];
-        let id = RenderTarget::new_framebuffer(&self.gl, 1)?;
-        color_texture.bind_as_color_target(0);
-        self.gl.check_framebuffer_status().or_else(|message| Err(Error::FailedToCreateFramebuffer {message}))?;
+        self.color.as_ref()
+    }
 
-        self.gl.bind_framebuffer(consts::READ_FRAMEBUFFER, Some(&id));
-        self.gl.read_pixels(x as u32, y as u32, width as u32, height as u32, consts::RGB,
-                            consts::UNSIGNED_BYTE, &mut pixels);
-        self.gl.delete_framebuffer(Some(&id));
-        Ok(pixels)
-    }*/
+    pub fn depth_attachment(&self) -> Option<&Attachment>
+    {
+        self.depth.as_ref()
+    }
 
-    /*#[cfg(not(target_arch = "wasm32"))]
-    pub fn read_depth(&self, x: i32, y: i32, width: usize, height: usize) -> Result<Vec<f32>, Error>
+    /// Deletes the framebuffer and returns the color and depth attachments.
+    pub fn into_attachments(mut self) -> (Option<Attachment>, Option<Attachment>)
     {
-        let depth_texture = self.depth_texture.as_ref().unwrap();
-        self.gl.viewport(x, y, width, height);
-        let mut pixels = vec![0f32; width * height];
-        let id = RenderTarget::new_framebuffer(&self.gl, 0)?;
-        depth_texture.bind_as_depth_target();
-        self.gl.check_framebuffer_status().or_else(|message| Err(Error::FailedToCreateFramebuffer {message}))?;
+        (self.color.take(), self.depth.take())
+    }
 
-        self.gl.bind_framebuffer(consts::READ_FRAMEBUFFER, Some(&id));
-        self.gl.read_depths(x as u32, y as u32, width as u32, height as u32,
-                        consts::DEPTH_COMPONENT, consts::FLOAT, &mut pixels);
-        self.gl.delete_framebuffer(Some(&id));
-        Ok(pixels)
-    }*/
+    /// Reads the color of the given rectangle of the first color attachment as RGBA with 8 bits per channel, starting with the bottom row.
+    pub fn read_color_rgba(&self, viewport: Viewport) -> Result<Vec<u8>, Error>
+    {
+        self.check_blit_type(BlitType::Color)?;
+        read_color_u8(&self.gl, Some(&self.id), viewport)
+    }
 
-    fn new_framebuffer(gl: &Gl, no_color_channels: usize) -> Result<crate::gl::Framebuffer, Error>
+    /// Reads the color of the given rectangle of the first color attachment as RGBA floats, which for example preserves the range of RGBA16F and RGBA32F attachments.
+    /// On web, this requires the `EXT_color_buffer_float` extension.
+    pub fn read_color_f32(&self, viewport: Viewport) -> Result<Vec<f32>, Error>
     {
-        let id = gl.create_framebuffer()
-            .ok_or_else(|| Error::FailedToCreateFramebuffer {message: "Failed to create framebuffer".to_string()} )?;
-        gl.bind_framebuffer(consts::DRAW_FRAMEBUFFER, Some(&id));
+        self.check_blit_type(BlitType::Color)?;
+        read_color_f32(&self.gl, Some(&self.id), viewport)
+    }
 
-        if no_color_channels > 0 {
-            let mut draw_buffers = Vec::new();
-            for i in 0..no_color_channels {
-                draw_buffers.push(consts::COLOR_ATTACHMENT0 + i as u32);
-            }
-            gl.draw_buffers(&draw_buffers);
+    /// Reads the depth of the given rectangle of the depth attachment, starting with the bottom row. Not available on web or OpenGL ES.
+    #[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+    pub fn read_depth(&self, viewport: Viewport) -> Result<Vec<f32>, Error>
+    {
+        self.check_blit_type(BlitType::Depth)?;
+        read_depth(&self.gl, Some(&self.id), viewport)
+    }
+
+    fn bind(&self)
+    {
+        self.gl.bind_framebuffer(consts::DRAW_FRAMEBUFFER, Some(&self.id));
+    }
+
+    /// The color attachment point which `source_layer` of the color attachment is bound to.
+    fn read_buffer(&self, blit_type: BlitType, source_layer: usize) -> Result<Option<u32>, Error>
+    {
+        if blit_type == BlitType::Depth {
+            return Ok(None);
+        }
+        let channel = match self.color {
+            Some(Attachment::Texture2DArray {ref layers, ..}) => layers.iter().position(|layer| *layer == source_layer),
+            _ => if source_layer == 0 { Some(0) } else { None }
+        };
+        channel.map(|channel| Some(consts::COLOR_ATTACHMENT0 + channel as u32))
+            .ok_or_else(|| Error::FailedToCreateFramebuffer {message: format!("Layer {} is not attached to the render target", source_layer)})
+    }
+
+    fn check_blit_type(&self, blit_type: BlitType) -> Result<(), Error>
+    {
+        if blit_type != BlitType::Depth && self.color.is_none() {
+            Err(Error::FailedToCreateFramebuffer {message: "Cannot blit color to or from a render target without a color attachment".to_string()})?
+        }
+        if blit_type != BlitType::Color && self.depth.is_none() {
+            Err(Error::FailedToCreateFramebuffer {message: "Cannot blit depth to or from a render target without a depth attachment".to_string()})?
+        }
+        Ok(())
+    }
+
+    fn set_draw_buffers(&self, no_color_channels: usize)
+    {
+        let mut draw_buffers = Vec::new();
+        for i in 0..no_color_channels {
+            draw_buffers.push(consts::COLOR_ATTACHMENT0 + i as u32);
         }
-        Ok(id)
+        self.gl.draw_buffers(&draw_buffers);
     }
 
-    fn clear(gl: &Gl, clear_color: Option<&Vec4>, clear_depth: Option<f32>) {
+    fn clear(gl: &Gl, clear_color: Option<&Vec4>, clear_depth: Option<f32>, clear_stencil: Option<i32>) {
+        let mut mask = 0;
         if let Some(color) = clear_color {
-            if let Some(depth) = clear_depth {
-                gl.clear_color(color.x, color.y, color.z, color.w);
-                depth_write(gl,true);
-                gl.clear_depth(depth);
-                gl.clear(consts::COLOR_BUFFER_BIT | consts::DEPTH_BUFFER_BIT);
-            }
-            else {
-                gl.clear_color(color.x, color.y, color.z, color.w);
-                gl.clear(consts::COLOR_BUFFER_BIT);
-            }
-        } else if let Some(depth) = clear_depth {
-            gl.clear_depth(depth);
+            gl.clear_color(color.x, color.y, color.z, color.w);
+            mask |= consts::COLOR_BUFFER_BIT;
+        }
+        if let Some(depth) = clear_depth {
             depth_write(gl, true);
-            gl.clear(consts::DEPTH_BUFFER_BIT);
+            gl.clear_depth(depth);
+            mask |= consts::DEPTH_BUFFER_BIT;
+        }
+        if let Some(stencil) = clear_stencil {
+            stencil_write(gl, 0xFFFFFFFF);
+            gl.clear_stencil(stencil);
+            mask |= consts::STENCIL_BUFFER_BIT;
+        }
+        if mask != 0 {
+            gl.clear(mask);
         }
     }
 
-    /*pub fn blit_color_and_depth_to(&self, target: &RenderTarget, target_color_texture: &Texture2D, target_depth_texture: &Texture2D)
+    /// Names this framebuffer in graphics debuggers such as RenderDoc (KHR_debug).
+    pub fn set_label(&self, label: &str)
     {
-        self.read();
-        target.write_to_color_and_depth(target_color_texture, target_depth_texture).unwrap();
-        depth_write(&self.gl, true);
-        self.gl.blit_framebuffer(0, 0, target_color_texture.width as u32, target_color_texture.height as u32,
-                                 0, 0, target_color_texture.width as u32, target_color_texture.height as u32,
-                                 consts::COLOR_BUFFER_BIT | consts::DEPTH_BUFFER_BIT, consts::NEAREST);
+        self.gl.object_label(consts::FRAMEBUFFER, &self.id, label);
     }
+}
 
-    pub fn blit_color_to(&self, target: &RenderTarget, target_texture: &Texture2D)
-    {
-        self.read();
-        target.write_to_color(target_texture).unwrap();
-        self.gl.blit_framebuffer(0, 0, target_texture.width as u32, target_texture.height as u32,
-                                 0, 0, target_texture.width as u32, target_texture.height as u32,
-                                 consts::COLOR_BUFFER_BIT, consts::NEAREST);
+/// Rejects viewports which start at negative coordinates, since they would wrap around when passed to the context.
+fn check_viewport(viewport: Viewport) -> Result<(), Error>
+{
+    if viewport.x < 0 || viewport.y < 0 {
+        Err(Error::FailedToCreateFramebuffer {message: format!("The viewport {:?} starts outside of the framebuffer", viewport)})?
+    }
+    Ok(())
+}
+
+/// Binds the framebuffer for reading color, which is always done from the first color attachment of a render target since blits may have changed the read buffer.
+fn bind_read_framebuffer(gl: &Gl, id: Option<&crate::gl::Framebuffer>)
+{
+    gl.bind_framebuffer(consts::READ_FRAMEBUFFER, id);
+    if id.is_some() {
+        gl.read_buffer(consts::COLOR_ATTACHMENT0);
+    }
+}
+
+fn read_color_u8(gl: &Gl, id: Option<&crate::gl::Framebuffer>, viewport: Viewport) -> Result<Vec<u8>, Error>
+{
+    check_viewport(viewport)?;
+    let mut pixels = vec![0u8; viewport.width * viewport.height * 4];
+    bind_read_framebuffer(gl, id);
+    gl.pixel_storei(consts::PACK_ALIGNMENT, 1);
+    gl.read_pixels_with_u8_data(viewport.x as u32, viewport.y as u32, viewport.width as u32, viewport.height as u32,
+                                consts::RGBA, consts::UNSIGNED_BYTE, &mut pixels);
+    gl.bind_framebuffer(consts::READ_FRAMEBUFFER, None);
+    check_gl_errors(gl, "reading pixels")?;
+    Ok(pixels)
+}
+
+fn read_color_f32(gl: &Gl, id: Option<&crate::gl::Framebuffer>, viewport: Viewport) -> Result<Vec<f32>, Error>
+{
+    check_viewport(viewport)?;
+    let mut pixels = vec![0f32; viewport.width * viewport.height * 4];
+    bind_read_framebuffer(gl, id);
+    gl.read_pixels_with_f32_data(viewport.x as u32, viewport.y as u32, viewport.width as u32, viewport.height as u32,
+                                 consts::RGBA, consts::FLOAT, &mut pixels);
+    gl.bind_framebuffer(consts::READ_FRAMEBUFFER, None);
+    check_gl_errors(gl, "reading pixels")?;
+    Ok(pixels)
+}
+
+#[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+fn read_depth(gl: &Gl, id: Option<&crate::gl::Framebuffer>, viewport: Viewport) -> Result<Vec<f32>, Error>
+{
+    check_viewport(viewport)?;
+    let mut pixels = vec![0f32; viewport.width * viewport.height];
+    gl.bind_framebuffer(consts::READ_FRAMEBUFFER, id);
+    gl.read_pixels_with_f32_data(viewport.x as u32, viewport.y as u32, viewport.width as u32, viewport.height as u32,
+                                 consts::DEPTH_COMPONENT, consts::FLOAT, &mut pixels);
+    gl.bind_framebuffer(consts::READ_FRAMEBUFFER, None);
+    check_gl_errors(gl, "reading pixels")?;
+    Ok(pixels)
+}
+
+fn blit(gl: &Gl, source_id: Option<&crate::gl::Framebuffer>, read_buffer: Option<u32>, source: Viewport,
+        target_id: Option<&crate::gl::Framebuffer>, destination: Viewport,
+        blit_type: BlitType, filter: Interpolation) -> Result<(), Error>
+{
+    check_viewport(source)?;
+    check_viewport(destination)?;
+    if blit_type != BlitType::Color && filter != Interpolation::Nearest {
+        Err(Error::FailedToCreateFramebuffer {message: "Depth can only be blitted with nearest filtering".to_string()})?
+    }
+    let mask = match blit_type {
+        BlitType::Color => consts::COLOR_BUFFER_BIT,
+        BlitType::Depth => consts::DEPTH_BUFFER_BIT,
+        BlitType::ColorAndDepth => consts::COLOR_BUFFER_BIT | consts::DEPTH_BUFFER_BIT
+    };
+    if blit_type != BlitType::Color {
+        depth_write(gl, true);
     }
 
-    pub fn blit_depth_to(&self, target: &RenderTarget, target_texture: &Texture2D)
+    gl.bind_framebuffer(consts::READ_FRAMEBUFFER, source_id);
+    if let Some(read_buffer) = read_buffer {
+        gl.read_buffer(read_buffer);
+    }
+    gl.bind_framebuffer(consts::DRAW_FRAMEBUFFER, target_id);
+    gl.blit_framebuffer(source.x as u32, source.y as u32, source.x as u32 + source.width as u32, source.y as u32 + source.height as u32,
+                        destination.x as u32, destination.y as u32, destination.x as u32 + destination.width as u32, destination.y as u32 + destination.height as u32,
+                        mask, filter as u32);
+    gl.bind_framebuffer(consts::READ_FRAMEBUFFER, None);
+    restore_draw_framebuffer(gl);
+    check_gl_errors(gl, "blitting")
+}
+
+impl Drop for RenderTarget
+{
+    fn drop(&mut self)
     {
-        self.read();
-        target.write_to_depth(target_texture).unwrap();
-        depth_write(&self.gl, true);
-        self.gl.blit_framebuffer(0, 0, target_texture.width as u32, target_texture.height as u32,
-                                 0, 0, target_texture.width as u32, target_texture.height as u32,
-                                 consts::DEPTH_BUFFER_BIT, consts::NEAREST);
-    }*/
-}
\ No newline at end of file
+        self.gl.delete_framebuffer(Some(&self.id));
+    }
+}
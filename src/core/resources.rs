@@ -0,0 +1,189 @@
+use crate::core::*;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+/// The kinds of GPU resources which are accounted for in the report returned by `resources`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourceCategory {
+    VertexBuffer,
+    ElementBuffer,
+    UniformBuffer,
+    DrawIndirectBuffer,
+    Texture2D,
+    Texture2DArray,
+    TextureCubeMap,
+    Program
+}
+
+/// A live resource, where `bytes` is the estimated GPU memory used by the data of the resource (zero for programs).
+#[derive(Clone, Debug)]
+pub struct ResourceInfo {
+    pub category: ResourceCategory,
+    pub label: Option<String>,
+    pub bytes: usize
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    pub count: usize,
+    pub bytes: usize
+}
+
+/// The live resources of a context at the time `resources` was called.
+#[derive(Clone, Debug)]
+pub struct ResourceReport {
+    pub resources: Vec<ResourceInfo>
+}
+
+impl ResourceReport {
+    pub fn total(&self) -> ResourceUsage
+    {
+        self.resources.iter().fold(ResourceUsage::default(), add)
+    }
+
+    pub fn by_category(&self) -> BTreeMap<ResourceCategory, ResourceUsage>
+    {
+        let mut usage = BTreeMap::new();
+        for resource in self.resources.iter() {
+            let entry = usage.entry(resource.category).or_insert_with(ResourceUsage::default);
+            *entry = add(*entry, resource);
+        }
+        usage
+    }
+
+    /// The usage of the resources with each label given by `set_label`, where the resources without a label are grouped under `None`.
+    pub fn by_label(&self) -> BTreeMap<Option<String>, ResourceUsage>
+    {
+        let mut usage = BTreeMap::new();
+        for resource in self.resources.iter() {
+            let entry = usage.entry(resource.label.clone()).or_insert_with(ResourceUsage::default);
+            *entry = add(*entry, resource);
+        }
+        usage
+    }
+}
+
+impl std::fmt::Display for ResourceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        let total = self.total();
+        writeln!(f, "{} resources using {}", total.count, format_bytes(total.bytes))?;
+        for (category, usage) in self.by_category() {
+            writeln!(f, "  {:?}: {} using {}", category, usage.count, format_bytes(usage.bytes))?;
+        }
+        for (label, usage) in self.by_label() {
+            let label = label.map(|label| format!("\"{}\"", label)).unwrap_or_else(|| "Unlabeled".to_string());
+            writeln!(f, "  {}: {} using {}", label, usage.count, format_bytes(usage.bytes))?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the buffers, textures and programs which are currently alive in the given context.
+pub fn resources(gl: &Gl) -> ResourceReport
+{
+    let registry = gl.resources().borrow();
+    let mut resources: Vec<(usize, ResourceInfo)> = registry.resources.iter().map(|(id, info)| (*id, info.clone())).collect();
+    resources.sort_by_key(|(id, _)| *id);
+    ResourceReport { resources: resources.into_iter().map(|(_, info)| info).collect() }
+}
+
+type RestoreCallback = Rc<dyn Fn(&Gl)>;
+
+/// The live resources of each context, which the resource types register with when they are created and unregister from when they are dropped.
+#[derive(Clone, Default)]
+pub(crate) struct ResourceRegistry {
+    next_id: usize,
+    resources: HashMap<usize, ResourceInfo>,
+    restore_callbacks: Vec<(usize, RestoreCallback)>
+}
+
+pub(crate) fn register(gl: &Gl, category: ResourceCategory, bytes: usize) -> usize
+{
+    let mut registry = gl.resources().borrow_mut();
+    let id = registry.next_id;
+    registry.next_id += 1;
+    registry.resources.insert(id, ResourceInfo { category, label: None, bytes });
+    id
+}
+
+pub(crate) fn set_bytes(gl: &Gl, id: usize, bytes: usize)
+{
+    if let Some(info) = gl.resources().borrow_mut().resources.get_mut(&id) {
+        info.bytes = bytes;
+    }
+}
+
+pub(crate) fn set_label(gl: &Gl, id: usize, label: &str)
+{
+    if let Some(info) = gl.resources().borrow_mut().resources.get_mut(&id) {
+        info.label = Some(label.to_string());
+    }
+}
+
+pub(crate) fn unregister(gl: &Gl, id: usize)
+{
+    gl.resources().borrow_mut().resources.remove(&id);
+}
+
+/// Registers a callback which recreates resources from data retained on the CPU, for example a `CPUMesh` or the bytes of an image,
+/// when a lost context is restored. On the web, the browser may drop the WebGL context at any time, after which all resources are invalid.
+/// The returned id can be given to `remove_context_restored` when the resources are no longer needed.
+pub fn on_context_restored<F: 'static + Fn(&Gl)>(gl: &Gl, callback: F) -> usize
+{
+    let mut registry = gl.resources().borrow_mut();
+    let id = registry.next_id;
+    registry.next_id += 1;
+    registry.restore_callbacks.push((id, Rc::new(callback)));
+    id
+}
+
+pub fn remove_context_restored(gl: &Gl, id: usize)
+{
+    gl.resources().borrow_mut().restore_callbacks.retain(|(callback_id, _)| *callback_id != id);
+}
+
+/// Calls the callbacks given to `on_context_restored` in the order they were registered.
+/// This is done by the canvas window when the WebGL context is restored, other applications call it themselves after recreating their context.
+pub fn restore_resources(gl: &Gl)
+{
+    // The callbacks create resources which register with the registry, so it cannot be borrowed while they are called
+    let callbacks: Vec<RestoreCallback> = gl.resources().borrow().restore_callbacks.iter().map(|(_, callback)| callback.clone()).collect();
+    for callback in callbacks {
+        callback(gl);
+    }
+}
+
+/// Prints the resources which are still alive in debug builds, called when the window or context which owns the given context is dropped.
+pub(crate) fn report_live_resources(gl: &Gl)
+{
+    if cfg!(debug_assertions) {
+        let report = resources(gl);
+        if !report.resources.is_empty() {
+            eprintln!("Live GPU resources when the context was dropped: {}", report);
+        }
+    }
+}
+
+/// The estimated size of a texture with the given number of layers and mip maps.
+pub(crate) fn texture_bytes(format: Format, width: usize, height: usize, layers: usize, number_of_mip_maps: u32) -> usize
+{
+    (0..number_of_mip_maps).map(|level| (width >> level).max(1) * (height >> level).max(1)).sum::<usize>()
+        * layers * format.bytes_per_pixel()
+}
+
+fn add(usage: ResourceUsage, resource: &ResourceInfo) -> ResourceUsage
+{
+    ResourceUsage { count: usage.count + 1, bytes: usage.bytes + resource.bytes }
+}
+
+fn format_bytes(bytes: usize) -> String
+{
+    if bytes >= 1 << 20 {
+        format!("{:.1} MB", bytes as f64 / (1 << 20) as f64)
+    } else if bytes >= 1 << 10 {
+        format!("{:.1} KB", bytes as f64 / (1 << 10) as f64)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
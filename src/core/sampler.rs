@@ -0,0 +1,98 @@
+use crate::core::*;
+
+// Defined by the EXT_texture_filter_anisotropic extension, the value is the same on desktop and web.
+const TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84FE;
+
+/// Describes how a texture is sampled. A sampler overrides the filtering and wrapping parameters
+/// which are set on the texture itself when it is used together with the texture in `Program::use_texture_with_sampler`.
+pub struct Sampler {
+    gl: Gl,
+    id: crate::gl::Sampler
+}
+
+impl Sampler
+{
+    pub fn new(gl: &Gl, min_filter: Interpolation, mag_filter: Interpolation, mip_map_filter: Option<Interpolation>,
+               wrap_s: Wrapping, wrap_t: Wrapping, wrap_r: Wrapping) -> Result<Sampler, Error>
+    {
+        let id = gl.create_sampler()
+            .ok_or_else(|| Error::FailedToCreateSampler {message: "Failed to create sampler".to_string()})?;
+        gl.sampler_parameteri(&id, consts::TEXTURE_MIN_FILTER, texture::min_filter_parameter(min_filter, mip_map_filter));
+        gl.sampler_parameteri(&id, consts::TEXTURE_MAG_FILTER, mag_filter as i32);
+        gl.sampler_parameteri(&id, consts::TEXTURE_WRAP_S, wrap_s as i32);
+        gl.sampler_parameteri(&id, consts::TEXTURE_WRAP_T, wrap_t as i32);
+        gl.sampler_parameteri(&id, consts::TEXTURE_WRAP_R, wrap_r as i32);
+        Ok(Sampler { gl: gl.clone(), id })
+    }
+
+    /// Creates a sampler which compares the sampled depth with a reference value, ie. it can be used with a `sampler2DShadow`.
+    /// Linear filtering results in hardware percentage-closer filtering.
+    pub fn new_depth_compare(gl: &Gl, filter: Interpolation, compare: DepthTestType) -> Result<Sampler, Error>
+    {
+        let sampler = Self::new(gl, filter, filter, None, Wrapping::ClampToEdge, Wrapping::ClampToEdge, Wrapping::ClampToEdge)?;
+        sampler.set_depth_compare(compare);
+        Ok(sampler)
+    }
+
+    /// Enables anisotropic filtering if the `EXT_texture_filter_anisotropic` extension is supported.
+    /// The anisotropy is clamped to the maximum supported value which is returned.
+    pub fn set_anisotropy(&self, max_anisotropy: f32) -> Result<f32, Error>
+    {
+        let supported = capabilities(&self.gl).max_anisotropy.ok_or_else(|| Error::FailedToCreateSampler {
+            message: "Anisotropic filtering is not supported, the EXT_texture_filter_anisotropic extension is missing".to_string()})?;
+        let anisotropy = max_anisotropy.max(1.0).min(supported);
+        self.gl.sampler_parameterf(&self.id, TEXTURE_MAX_ANISOTROPY_EXT, anisotropy);
+        Ok(anisotropy)
+    }
+
+    pub fn set_lod_range(&self, min_lod: f32, max_lod: f32)
+    {
+        self.gl.sampler_parameterf(&self.id, consts::TEXTURE_MIN_LOD, min_lod);
+        self.gl.sampler_parameterf(&self.id, consts::TEXTURE_MAX_LOD, max_lod);
+    }
+
+    #[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+    pub fn set_lod_bias(&self, bias: f32)
+    {
+        self.gl.sampler_parameterf(&self.id, consts::TEXTURE_LOD_BIAS, bias);
+    }
+
+    /// Sets the color used outside the texture when wrapping is `Wrapping::ClampToBorder`.
+    #[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+    pub fn set_border_color(&self, color: &Vec4)
+    {
+        self.gl.sampler_parameterfv(&self.id, consts::TEXTURE_BORDER_COLOR, &color.to_slice());
+    }
+
+    /// Sets the function used to compare a reference value with the sampled depth.
+    /// `DepthTestType::None` disables the comparison.
+    pub fn set_depth_compare(&self, compare: DepthTestType)
+    {
+        if compare == DepthTestType::None {
+            self.gl.sampler_parameteri(&self.id, consts::TEXTURE_COMPARE_MODE, consts::NONE as i32);
+        }
+        else {
+            self.gl.sampler_parameteri(&self.id, consts::TEXTURE_COMPARE_MODE, consts::COMPARE_REF_TO_TEXTURE as i32);
+            self.gl.sampler_parameteri(&self.id, consts::TEXTURE_COMPARE_FUNC, state::depth_function(compare) as i32);
+        }
+    }
+
+    pub(crate) fn bind(&self, location: u32)
+    {
+        self.gl.bind_sampler(location, Some(&self.id));
+    }
+
+    /// Names this sampler in graphics debuggers such as RenderDoc (KHR_debug).
+    pub fn set_label(&self, label: &str)
+    {
+        self.gl.object_label(consts::SAMPLER, &self.id, label);
+    }
+}
+
+impl Drop for Sampler
+{
+    fn drop(&mut self)
+    {
+        self.gl.delete_sampler(&self.id);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::core::*;
+
+// Encoding of pixels read from the screen or a render target into image files.
+// The pixels are expected to start with the bottom row, as returned by the read functions, and are flipped to start with the top row in the image.
+// The encode functions return the bytes of the image file, which on web for example can be wrapped in a Blob and downloaded.
+// High dynamic range images are saved in the Radiance HDR format, since OpenEXR is not supported by the image crate.
+
+/// Encodes RGBA pixels with 8 bits per channel as a PNG image with alpha.
+pub fn encode_png_rgba8(pixels: &[u8], width: usize, height: usize) -> Result<Vec<u8>, Error>
+{
+    let mut bytes = Vec::new();
+    image::png::PNGEncoder::new(&mut bytes).encode(&flip_rows(pixels, width * 4, height), width as u32, height as u32, image::RGBA(8))?;
+    Ok(bytes)
+}
+
+/// Encodes RGBA float pixels as a PNG image with 16 bits per channel, values are clamped to the range [0, 1].
+pub fn encode_png_rgba16(pixels: &[f32], width: usize, height: usize) -> Result<Vec<u8>, Error>
+{
+    let data: Vec<u8> = flip_rows(pixels, width * 4, height).iter()
+        .flat_map(|value| to_u16(*value).to_be_bytes().to_vec())
+        .collect();
+    let mut bytes = Vec::new();
+    image::png::PNGEncoder::new(&mut bytes).encode(&data, width as u32, height as u32, image::RGBA(16))?;
+    Ok(bytes)
+}
+
+/// Encodes RGBA float pixels as a Radiance HDR image, which preserves values outside the range [0, 1]. The alpha channel is discarded.
+pub fn encode_hdr(pixels: &[f32], width: usize, height: usize) -> Result<Vec<u8>, Error>
+{
+    let data: Vec<image::Rgb<f32>> = flip_rows(pixels, width * 4, height).chunks(4)
+        .map(|pixel| image::Rgb([pixel[0], pixel[1], pixel[2]]))
+        .collect();
+    let mut bytes = Vec::new();
+    image::hdr::HDREncoder::new(&mut bytes).encode(&data, width, height)?;
+    Ok(bytes)
+}
+
+/// Encodes depth values as a grayscale PNG image with 16 bits per pixel,
+/// where the range of depth values in the image, excluding the far plane, is stretched to black (near) to white (far) to make the depth visible.
+pub fn encode_png_depth(depth: &[f32], width: usize, height: usize) -> Result<Vec<u8>, Error>
+{
+    let (min, max) = depth.iter().filter(|d| **d < 1.0)
+        .fold((f32::MAX, f32::MIN), |(min, max), d| (min.min(*d), max.max(*d)));
+    let range = if max > min { max - min } else { 1.0 };
+    let data: Vec<u8> = flip_rows(depth, width, height).iter()
+        .flat_map(|d| to_u16(if *d < 1.0 { (d - min) / range } else { 1.0 }).to_be_bytes().to_vec())
+        .collect();
+    let mut bytes = Vec::new();
+    image::png::PNGEncoder::new(&mut bytes).encode(&data, width as u32, height as u32, image::Gray(16))?;
+    Ok(bytes)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_png_rgba8(path: &str, pixels: &[u8], width: usize, height: usize) -> Result<(), Error>
+{
+    std::fs::write(path, encode_png_rgba8(pixels, width, height)?)?;
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_png_rgba16(path: &str, pixels: &[f32], width: usize, height: usize) -> Result<(), Error>
+{
+    std::fs::write(path, encode_png_rgba16(pixels, width, height)?)?;
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_hdr(path: &str, pixels: &[f32], width: usize, height: usize) -> Result<(), Error>
+{
+    std::fs::write(path, encode_hdr(pixels, width, height)?)?;
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_png_depth(path: &str, depth: &[f32], width: usize, height: usize) -> Result<(), Error>
+{
+    std::fs::write(path, encode_png_depth(depth, width, height)?)?;
+    Ok(())
+}
+
+fn to_u16(value: f32) -> u16
+{
+    (value.clamp(0.0, 1.0) * 65535.0).round() as u16
+}
+
+fn flip_rows<T: Copy>(pixels: &[T], row_length: usize, height: usize) -> Vec<T>
+{
+    let mut flipped = Vec::with_capacity(pixels.len());
+    for row in (0..height).rev() {
+        flipped.extend_from_slice(&pixels[row * row_length..(row + 1) * row_length]);
+    }
+    flipped
+}
@@ -1,43 +1,295 @@
 use crate::gl::consts;
 use crate::gl::Gl;
+use crate::core::types::Vec4;
 
-#[derive(PartialEq)]
+/// A complete description of the blend, depth, stencil and rasterizer state used when drawing, which can be applied at once using `apply`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RenderState {
+    pub blend: BlendType,
+    pub blend_color: Vec4,
+    pub color_mask: ColorMask,
+    pub cull: CullType,
+    pub depth_test: DepthTestType,
+    pub depth_write: bool,
+    pub stencil_test: (DepthTestType, i32, u32),
+    pub stencil_op: (StencilOp, StencilOp, StencilOp),
+    pub stencil_write: u32,
+    pub polygon_offset: Option<PolygonOffset>,
+    pub line_width: f32,
+    #[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+    pub polygon_mode: PolygonMode,
+    pub scissor: Option<Viewport>
+}
+
+impl Default for RenderState {
+    /// The default state of a newly created context.
+    fn default() -> Self
+    {
+        RenderState {
+            blend: BlendType::None,
+            blend_color: Vec4::new(0.0, 0.0, 0.0, 0.0),
+            color_mask: ColorMask::default(),
+            cull: CullType::None,
+            depth_test: DepthTestType::None,
+            depth_write: true,
+            stencil_test: (DepthTestType::None, 0, 0xFFFFFFFF),
+            stencil_op: (StencilOp::Keep, StencilOp::Keep, StencilOp::Keep),
+            stencil_write: 0xFFFFFFFF,
+            polygon_offset: None,
+            line_width: 1.0,
+            #[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+            polygon_mode: PolygonMode::Fill,
+            scissor: None
+        }
+    }
+}
+
+impl RenderState {
+    /// Sets all of the state, only the parts which differ from the current state of the context result in GL calls.
+    pub fn apply(&self, gl: &Gl)
+    {
+        blend(gl, self.blend);
+        blend_color(gl, &self.blend_color);
+        color_mask(gl, self.color_mask);
+        cull(gl, self.cull);
+        depth_test(gl, self.depth_test);
+        depth_write(gl, self.depth_write);
+        let (stencil_test_type, reference, mask) = self.stencil_test;
+        stencil_test(gl, stencil_test_type, reference, mask);
+        let (stencil_fail, depth_fail, pass) = self.stencil_op;
+        stencil_op(gl, stencil_fail, depth_fail, pass);
+        stencil_write(gl, self.stencil_write);
+        polygon_offset(gl, self.polygon_offset);
+        line_width(gl, self.line_width);
+        #[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+        polygon_mode(gl, self.polygon_mode);
+        scissor(gl, self.scissor);
+    }
+}
+
+/// The render state of a context as last set through the functions in this module.
+/// A value of `None` means that the state is unknown, for example after `invalidate_state`, and is always applied the next time it is set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateCache {
+    pub blend: Option<BlendType>,
+    pub blend_color: Option<Vec4>,
+    pub color_mask: Option<ColorMask>,
+    pub cull: Option<CullType>,
+    pub depth_test: Option<DepthTestType>,
+    pub depth_write: Option<bool>,
+    pub stencil_test: Option<(DepthTestType, i32, u32)>,
+    pub stencil_op: Option<(StencilOp, StencilOp, StencilOp)>,
+    pub stencil_write: Option<u32>,
+    pub polygon_offset: Option<Option<PolygonOffset>>,
+    pub line_width: Option<f32>,
+    #[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+    pub polygon_mode: Option<PolygonMode>,
+    pub scissor: Option<Option<Viewport>>,
+    pub viewport: Option<Viewport>,
+    /// The framebuffer which is written to, `None` meaning the screen, see `push_viewport`.
+    draw_framebuffer: Option<crate::gl::Framebuffer>,
+    viewport_stack: Vec<PushedViewport>
+}
+
+/// The state which is restored by `pop_viewport`.
+#[derive(Clone, Debug, PartialEq)]
+struct PushedViewport {
+    viewport: Option<Viewport>,
+    scissor: Option<Option<Viewport>>,
+    draw_framebuffer: Option<crate::gl::Framebuffer>
+}
+
+impl StateCache {
+    /// Returns the full state if all of it is known.
+    pub fn known_state(&self) -> Option<RenderState>
+    {
+        Some(RenderState {
+            blend: self.blend?,
+            blend_color: self.blend_color?,
+            color_mask: self.color_mask?,
+            cull: self.cull?,
+            depth_test: self.depth_test?,
+            depth_write: self.depth_write?,
+            stencil_test: self.stencil_test?,
+            stencil_op: self.stencil_op?,
+            stencil_write: self.stencil_write?,
+            polygon_offset: self.polygon_offset?,
+            line_width: self.line_width?,
+            #[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+            polygon_mode: self.polygon_mode?,
+            scissor: self.scissor?
+        })
+    }
+
+    fn unknown() -> Self
+    {
+        StateCache { blend: None, blend_color: None, color_mask: None, cull: None, depth_test: None, depth_write: None,
+            stencil_test: None, stencil_op: None, stencil_write: None, polygon_offset: None, line_width: None,
+            #[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+            polygon_mode: None,
+            scissor: None, viewport: None, draw_framebuffer: None, viewport_stack: Vec::new() }
+    }
+}
+
+impl Default for StateCache {
+    /// The state of a newly created context.
+    fn default() -> Self
+    {
+        let state = RenderState::default();
+        StateCache {
+            blend: Some(state.blend),
+            blend_color: Some(state.blend_color),
+            color_mask: Some(state.color_mask),
+            cull: Some(state.cull),
+            depth_test: Some(state.depth_test),
+            depth_write: Some(state.depth_write),
+            stencil_test: Some(state.stencil_test),
+            stencil_op: Some(state.stencil_op),
+            stencil_write: Some(state.stencil_write),
+            polygon_offset: Some(state.polygon_offset),
+            line_width: Some(state.line_width),
+            #[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+            polygon_mode: Some(state.polygon_mode),
+            scissor: Some(state.scissor),
+            viewport: None,
+            draw_framebuffer: None,
+            viewport_stack: Vec::new()
+        }
+    }
+}
+
+/// Returns the render state of the context as last set through the functions in this module.
+pub fn current_state(gl: &Gl) -> StateCache
+{
+    gl.state().borrow().clone()
+}
+
+/// Marks the entire render state as unknown, so that it is applied again the next time it is set.
+/// Call this after external code has changed the state of the context.
+pub fn invalidate_state(gl: &Gl)
+{
+    let mut state = gl.state().borrow_mut();
+    let viewport_stack = std::mem::take(&mut state.viewport_stack);
+    let draw_framebuffer = state.draw_framebuffer.take();
+    *state = StateCache::unknown();
+    state.viewport_stack = viewport_stack;
+    state.draw_framebuffer = draw_framebuffer;
+}
+
+/// Sets the entire render state of the context to the default state of a newly created context.
+pub fn reset_state(gl: &Gl)
+{
+    invalidate_state(gl);
+    RenderState::default().apply(gl);
+}
+
+/// Predefined blend modes and `Custom` for full control of the blend equations and factors.
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum BlendType {
     None,
     SrcAlphaOneMinusSrcAlpha,
     DstAlphaOneMinusDstAlpha,
-    OneOne
+    OneOne,
+    Custom(BlendParameters)
+}
+
+impl BlendType {
+    /// The blend parameters of this blend type, `None` if blending is disabled.
+    pub fn parameters(&self) -> Option<BlendParameters>
+    {
+        match self {
+            BlendType::None => None,
+            BlendType::SrcAlphaOneMinusSrcAlpha => Some(BlendParameters::new(BlendMultiplierType::SrcAlpha, BlendMultiplierType::OneMinusSrcAlpha)),
+            BlendType::DstAlphaOneMinusDstAlpha => Some(BlendParameters::new(BlendMultiplierType::DstAlpha, BlendMultiplierType::OneMinusDstAlpha)),
+            BlendType::OneOne => Some(BlendParameters::new(BlendMultiplierType::One, BlendMultiplierType::One)),
+            BlendType::Custom(parameters) => Some(*parameters)
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BlendParameters {
+    pub rgb_equation: BlendEquationType,
+    pub alpha_equation: BlendEquationType,
+    pub source_rgb_multiplier: BlendMultiplierType,
+    pub source_alpha_multiplier: BlendMultiplierType,
+    pub destination_rgb_multiplier: BlendMultiplierType,
+    pub destination_alpha_multiplier: BlendMultiplierType
+}
+
+impl BlendParameters {
+    /// Adds the source and destination multiplied by the given factors, using the same factors for color and alpha.
+    pub fn new(source_multiplier: BlendMultiplierType, destination_multiplier: BlendMultiplierType) -> Self
+    {
+        BlendParameters {
+            rgb_equation: BlendEquationType::Add,
+            alpha_equation: BlendEquationType::Add,
+            source_rgb_multiplier: source_multiplier,
+            source_alpha_multiplier: source_multiplier,
+            destination_rgb_multiplier: destination_multiplier,
+            destination_alpha_multiplier: destination_multiplier
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum BlendEquationType {
+    Add = consts::FUNC_ADD as isize,
+    Subtract = consts::FUNC_SUBTRACT as isize,
+    ReverseSubtract = consts::FUNC_REVERSE_SUBTRACT as isize,
+    Min = consts::MIN as isize,
+    Max = consts::MAX as isize
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum BlendMultiplierType {
+    Zero = consts::ZERO as isize,
+    One = consts::ONE as isize,
+    SrcColor = consts::SRC_COLOR as isize,
+    OneMinusSrcColor = consts::ONE_MINUS_SRC_COLOR as isize,
+    DstColor = consts::DST_COLOR as isize,
+    OneMinusDstColor = consts::ONE_MINUS_DST_COLOR as isize,
+    SrcAlpha = consts::SRC_ALPHA as isize,
+    OneMinusSrcAlpha = consts::ONE_MINUS_SRC_ALPHA as isize,
+    DstAlpha = consts::DST_ALPHA as isize,
+    OneMinusDstAlpha = consts::ONE_MINUS_DST_ALPHA as isize,
+    ConstantColor = consts::CONSTANT_COLOR as isize,
+    OneMinusConstantColor = consts::ONE_MINUS_CONSTANT_COLOR as isize,
+    ConstantAlpha = consts::CONSTANT_ALPHA as isize,
+    OneMinusConstantAlpha = consts::ONE_MINUS_CONSTANT_ALPHA as isize,
+    SrcAlphaSaturate = consts::SRC_ALPHA_SATURATE as isize
 }
 
 pub fn blend(gl: &Gl, blend_type: BlendType)
 {
-    unsafe {
-        static mut CURRENT: BlendType = BlendType::None;
-        if blend_type != CURRENT
-        {
-            match blend_type {
-                BlendType::None => {
-                    gl.disable(consts::BLEND);
-                },
-                BlendType::SrcAlphaOneMinusSrcAlpha => {
-                    gl.enable(consts::BLEND);
-                    gl.blend_func(consts::SRC_ALPHA, consts::ONE_MINUS_SRC_ALPHA);
-                },
-                BlendType::DstAlphaOneMinusDstAlpha => {
-                    gl.enable(consts::BLEND);
-                    gl.blend_func(consts::DST_ALPHA, consts::ONE_MINUS_DST_ALPHA);
-                },
-                BlendType::OneOne => {
-                    gl.enable(consts::BLEND);
-                    gl.blend_func(consts::ONE, consts::ONE);
-                }
-            }
-            CURRENT = blend_type;
+    let mut state = gl.state().borrow_mut();
+    if state.blend != Some(blend_type)
+    {
+        if let Some(parameters) = blend_type.parameters() {
+            gl.enable(consts::BLEND);
+            gl.blend_func_separate(parameters.source_rgb_multiplier as u32, parameters.destination_rgb_multiplier as u32,
+                                   parameters.source_alpha_multiplier as u32, parameters.destination_alpha_multiplier as u32);
+            gl.blend_equation_separate(parameters.rgb_equation as u32, parameters.alpha_equation as u32);
+        }
+        else {
+            gl.disable(consts::BLEND);
         }
+        state.blend = Some(blend_type);
+    }
+}
+
+/// The color used by the constant blend multipliers.
+pub fn blend_color(gl: &Gl, color: &Vec4)
+{
+    let mut state = gl.state().borrow_mut();
+    if state.blend_color != Some(*color)
+    {
+        gl.blend_color(color.x, color.y, color.z, color.w);
+        state.blend_color = Some(*color);
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum CullType {
     None,
     Back,
@@ -47,33 +299,31 @@ pub enum CullType {
 
 pub fn cull(gl: &Gl, cull_type: CullType)
 {
-    unsafe {
-        static mut CURRENT: CullType = CullType::None;
-        if cull_type != CURRENT
-        {
-            match cull_type {
-                CullType::None => {
-                    gl.disable(consts::CULL_FACE);
-                },
-                CullType::Back => {
-                    gl.enable(consts::CULL_FACE);
-                    gl.cull_face(consts::BACK);
-                },
-                CullType::Front => {
-                    gl.enable(consts::CULL_FACE);
-                    gl.cull_face(consts::FRONT);
-                },
-                CullType::FrontAndBack => {
-                    gl.enable(consts::CULL_FACE);
-                    gl.cull_face(consts::FRONT_AND_BACK);
-                }
+    let mut state = gl.state().borrow_mut();
+    if state.cull != Some(cull_type)
+    {
+        match cull_type {
+            CullType::None => {
+                gl.disable(consts::CULL_FACE);
+            },
+            CullType::Back => {
+                gl.enable(consts::CULL_FACE);
+                gl.cull_face(consts::BACK);
+            },
+            CullType::Front => {
+                gl.enable(consts::CULL_FACE);
+                gl.cull_face(consts::FRONT);
+            },
+            CullType::FrontAndBack => {
+                gl.enable(consts::CULL_FACE);
+                gl.cull_face(consts::FRONT_AND_BACK);
             }
-            CURRENT = cull_type;
         }
+        state.cull = Some(cull_type);
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum DepthTestType {
     None,
     Never,
@@ -88,57 +338,260 @@ pub enum DepthTestType {
 
 pub fn depth_test(gl: &Gl, depth_test_type: DepthTestType)
 {
-    unsafe {
-        static mut CURRENT: DepthTestType = DepthTestType::None;
-        if depth_test_type != CURRENT
-        {
-            if depth_test_type == DepthTestType::None {
-                gl.disable(consts::DEPTH_TEST);
-            }
-            else {
-                gl.enable(consts::DEPTH_TEST);
-            }
-
-            match depth_test_type {
-                DepthTestType::Never => {
-                    gl.depth_func(consts::NEVER);
-                },
-                DepthTestType::Less => {
-                    gl.depth_func(consts::LESS);
-                },
-                DepthTestType::Equal => {
-                    gl.depth_func(consts::EQUAL);
-                },
-                DepthTestType::LessOrEqual => {
-                    gl.depth_func(consts::LEQUAL);
-                },
-                DepthTestType::Greater => {
-                    gl.depth_func(consts::GREATER);
-                },
-                DepthTestType::NotEqual => {
-                    gl.depth_func(consts::NOTEQUAL);
-                },
-                DepthTestType::GreaterOrEqual => {
-                    gl.depth_func(consts::GEQUAL);
-                },
-                DepthTestType::Always => {
-                    gl.depth_func(consts::ALWAYS);
-                },
-                DepthTestType::None => {}
-            }
-            CURRENT = depth_test_type;
+    let mut state = gl.state().borrow_mut();
+    if state.depth_test != Some(depth_test_type)
+    {
+        if depth_test_type == DepthTestType::None {
+            gl.disable(consts::DEPTH_TEST);
         }
+        else {
+            gl.enable(consts::DEPTH_TEST);
+            gl.depth_func(depth_function(depth_test_type));
+        }
+        state.depth_test = Some(depth_test_type);
+    }
+}
+
+pub(crate) fn depth_function(depth_test_type: DepthTestType) -> u32
+{
+    match depth_test_type {
+        DepthTestType::Never => consts::NEVER,
+        DepthTestType::Less => consts::LESS,
+        DepthTestType::Equal => consts::EQUAL,
+        DepthTestType::LessOrEqual => consts::LEQUAL,
+        DepthTestType::Greater => consts::GREATER,
+        DepthTestType::NotEqual => consts::NOTEQUAL,
+        DepthTestType::GreaterOrEqual => consts::GEQUAL,
+        DepthTestType::Always | DepthTestType::None => consts::ALWAYS
     }
 }
 
 pub fn depth_write(gl: &Gl, enable: bool)
 {
-    unsafe {
-        static mut CURRENTLY_ENABLED: bool = true;
-        if enable != CURRENTLY_ENABLED
-        {
-            gl.depth_mask(enable);
-            CURRENTLY_ENABLED = enable;
+    let mut state = gl.state().borrow_mut();
+    if state.depth_write != Some(enable)
+    {
+        gl.depth_mask(enable);
+        state.depth_write = Some(enable);
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ColorMask {
+    pub red: bool,
+    pub green: bool,
+    pub blue: bool,
+    pub alpha: bool
+}
+
+impl Default for ColorMask {
+    fn default() -> Self
+    {
+        ColorMask {red: true, green: true, blue: true, alpha: true}
+    }
+}
+
+/// Specifies which color channels are written.
+pub fn color_mask(gl: &Gl, mask: ColorMask)
+{
+    let mut state = gl.state().borrow_mut();
+    if state.color_mask != Some(mask)
+    {
+        gl.color_mask(mask.red, mask.green, mask.blue, mask.alpha);
+        state.color_mask = Some(mask);
+    }
+}
+
+/// Enables or disables writing to all color channels.
+pub fn color_write(gl: &Gl, enable: bool)
+{
+    color_mask(gl, ColorMask {red: enable, green: enable, blue: enable, alpha: enable});
+}
+
+/// Enables the stencil test using the same comparison functions as the depth test, `DepthTestType::None` disables it.
+/// A fragment passes if `reference & mask` compares to `stencil value & mask` with the given function.
+pub fn stencil_test(gl: &Gl, stencil_test_type: DepthTestType, reference: i32, mask: u32)
+{
+    let mut state = gl.state().borrow_mut();
+    if state.stencil_test != Some((stencil_test_type, reference, mask))
+    {
+        if stencil_test_type == DepthTestType::None {
+            gl.disable(consts::STENCIL_TEST);
+        }
+        else {
+            gl.enable(consts::STENCIL_TEST);
+            gl.stencil_func(depth_function(stencil_test_type), reference, mask);
+        }
+        state.stencil_test = Some((stencil_test_type, reference, mask));
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum StencilOp {
+    Keep = consts::KEEP as isize,
+    Zero = consts::ZERO as isize,
+    Replace = consts::REPLACE as isize,
+    Increment = consts::INCR as isize,
+    IncrementWrap = consts::INCR_WRAP as isize,
+    Decrement = consts::DECR as isize,
+    DecrementWrap = consts::DECR_WRAP as isize,
+    Invert = consts::INVERT as isize
+}
+
+/// Specifies how the stencil value is updated when the stencil test fails, when the stencil test passes but the depth test fails and when both tests pass.
+pub fn stencil_op(gl: &Gl, stencil_fail: StencilOp, depth_fail: StencilOp, pass: StencilOp)
+{
+    let mut state = gl.state().borrow_mut();
+    if state.stencil_op != Some((stencil_fail, depth_fail, pass))
+    {
+        gl.stencil_op(stencil_fail as u32, depth_fail as u32, pass as u32);
+        state.stencil_op = Some((stencil_fail, depth_fail, pass));
+    }
+}
+
+/// Specifies which bits of the stencil value can be written, zero disables stencil writes.
+pub fn stencil_write(gl: &Gl, mask: u32)
+{
+    let mut state = gl.state().borrow_mut();
+    if state.stencil_write != Some(mask)
+    {
+        gl.stencil_mask(mask);
+        state.stencil_write = Some(mask);
+    }
+}
+
+/// Offsets the depth of polygons by `factor` times the depth slope of the polygon plus `units` times the smallest resolvable depth difference,
+/// for example to avoid shadow acne when rendering shadow maps.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PolygonOffset {
+    pub factor: f32,
+    pub units: f32
+}
+
+pub fn polygon_offset(gl: &Gl, offset: Option<PolygonOffset>)
+{
+    let mut state = gl.state().borrow_mut();
+    if state.polygon_offset != Some(offset)
+    {
+        if let Some(offset) = offset {
+            gl.enable(consts::POLYGON_OFFSET_FILL);
+            gl.polygon_offset(offset.factor, offset.units);
+        }
+        else {
+            gl.disable(consts::POLYGON_OFFSET_FILL);
         }
+        state.polygon_offset = Some(offset);
+    }
+}
+
+/// The width of rasterized lines, most platforms only support a width of 1.
+pub fn line_width(gl: &Gl, width: f32)
+{
+    let mut state = gl.state().borrow_mut();
+    if state.line_width != Some(width)
+    {
+        gl.line_width(width);
+        state.line_width = Some(width);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PolygonMode {
+    Point = consts::POINT as isize,
+    Line = consts::LINE as isize,
+    Fill = consts::FILL as isize
+}
+
+/// How polygons are rasterized, only available on desktop OpenGL.
+#[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+pub fn polygon_mode(gl: &Gl, mode: PolygonMode)
+{
+    let mut state = gl.state().borrow_mut();
+    if state.polygon_mode != Some(mode)
+    {
+        gl.polygon_mode(consts::FRONT_AND_BACK, mode as u32);
+        state.polygon_mode = Some(mode);
+    }
+}
+
+/// A rectangle in pixels with origo in the lower left corner.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport {
+    pub x: i32,
+    pub y: i32,
+    pub width: usize,
+    pub height: usize
+}
+
+impl Viewport {
+    pub fn new_at_origo(width: usize, height: usize) -> Self
+    {
+        Viewport {x: 0, y: 0, width, height}
+    }
+}
+
+pub fn viewport(gl: &Gl, viewport: Viewport)
+{
+    let mut state = gl.state().borrow_mut();
+    if state.viewport != Some(viewport)
+    {
+        gl.viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+        state.viewport = Some(viewport);
+    }
+}
+
+/// Restricts all drawing and clearing to the given rectangle, `None` disables the scissor test.
+pub fn scissor(gl: &Gl, scissor: Option<Viewport>)
+{
+    let mut state = gl.state().borrow_mut();
+    if state.scissor != Some(scissor)
+    {
+        if let Some(rectangle) = scissor {
+            gl.enable(consts::SCISSOR_TEST);
+            gl.scissor(rectangle.x, rectangle.y, rectangle.width, rectangle.height);
+        }
+        else {
+            gl.disable(consts::SCISSOR_TEST);
+        }
+        state.scissor = Some(scissor);
+    }
+}
+
+/// Binds the framebuffer (`None` meaning the screen) and sets the viewport and scissor rectangle,
+/// and remembers the previous ones, which are restored by the matching call to `pop_viewport`.
+pub fn push_viewport(gl: &Gl, framebuffer: Option<&crate::gl::Framebuffer>, new_viewport: Viewport, new_scissor: Option<Viewport>)
+{
+    {
+        let mut state = gl.state().borrow_mut();
+        let previous = PushedViewport {viewport: state.viewport, scissor: state.scissor, draw_framebuffer: state.draw_framebuffer.clone()};
+        state.viewport_stack.push(previous);
+        state.draw_framebuffer = framebuffer.cloned();
+    }
+    gl.bind_framebuffer(consts::DRAW_FRAMEBUFFER, framebuffer);
+    viewport(gl, new_viewport);
+    scissor(gl, new_scissor);
+}
+
+/// Restores the framebuffer, viewport and scissor rectangle which were current before the last call to `push_viewport`.
+pub fn pop_viewport(gl: &Gl)
+{
+    let previous = gl.state().borrow_mut().viewport_stack.pop();
+    if let Some(previous) = previous {
+        gl.state().borrow_mut().draw_framebuffer = previous.draw_framebuffer.clone();
+        gl.bind_framebuffer(consts::DRAW_FRAMEBUFFER, previous.draw_framebuffer.as_ref());
+        if let Some(previous_viewport) = previous.viewport {
+            viewport(gl, previous_viewport);
+        }
+        if let Some(previous_scissor) = previous.scissor {
+            scissor(gl, previous_scissor);
+        }
+    }
+}
+
+/// Binds the framebuffer which is currently written to, after another framebuffer has been bound temporarily, for example to change its attachments.
+pub(crate) fn restore_draw_framebuffer(gl: &Gl)
+{
+    let framebuffer = gl.state().borrow().draw_framebuffer.clone();
+    gl.bind_framebuffer(consts::DRAW_FRAMEBUFFER, framebuffer.as_ref());
+}
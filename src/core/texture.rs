@@ -1,6 +1,4 @@
-use crate::core::Error;
-use crate::gl::Gl;
-use crate::gl::consts;
+use crate::core::*;
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum Interpolation {
@@ -12,21 +10,97 @@ pub enum Interpolation {
 pub enum Wrapping {
     Repeat = consts::REPEAT as isize,
     MirroredRepeat = consts::MIRRORED_REPEAT as isize,
-    ClampToEdge = consts::CLAMP_TO_EDGE as isize
+    ClampToEdge = consts::CLAMP_TO_EDGE as isize,
+    #[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+    ClampToBorder = consts::CLAMP_TO_BORDER as isize
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum Format {
     R8 = consts::R8 as isize,
+    R16F = consts::R16F as isize,
     R32F = consts::R32F as isize,
     RGB8 = consts::RGB8 as isize,
+    RGB16F = consts::RGB16F as isize,
     RGB32F = consts::RGB32F as isize,
     RGBA4 = consts::RGBA4 as isize,
     RGBA8 = consts::RGBA8 as isize,
+    RGBA16F = consts::RGBA16F as isize,
     RGBA32F = consts::RGBA32F as isize,
     Depth16 = consts::DEPTH_COMPONENT16 as isize,
     Depth24 = consts::DEPTH_COMPONENT24 as isize,
-    Depth32F = consts::DEPTH_COMPONENT32F as isize
+    Depth32F = consts::DEPTH_COMPONENT32F as isize,
+    Depth24Stencil8 = consts::DEPTH24_STENCIL8 as isize,
+    Depth32FStencil8 = consts::DEPTH32F_STENCIL8 as isize
+}
+
+impl Format {
+    pub fn has_stencil(&self) -> bool
+    {
+        *self == Format::Depth24Stencil8 || *self == Format::Depth32FStencil8
+    }
+
+    /// The size of a pixel with this format, which is used to estimate the memory used by a texture.
+    pub fn bytes_per_pixel(&self) -> usize
+    {
+        match self {
+            Format::R8 => 1,
+            Format::R16F | Format::RGBA4 | Format::Depth16 => 2,
+            Format::RGB8 | Format::Depth24 => 3,
+            Format::R32F | Format::RGBA8 | Format::Depth32F | Format::Depth24Stencil8 => 4,
+            Format::RGB16F => 6,
+            Format::RGBA16F | Format::Depth32FStencil8 => 8,
+            Format::RGB32F => 12,
+            Format::RGBA32F => 16
+        }
+    }
+
+    /// The framebuffer attachment point to use when a texture or renderbuffer with this format is used as depth target.
+    pub(crate) fn depth_attachment(&self) -> u32
+    {
+        if self.has_stencil() { consts::DEPTH_STENCIL_ATTACHMENT } else { consts::DEPTH_ATTACHMENT }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum CubeMapSide {
+    Right = 0,
+    Left = 1,
+    Top = 2,
+    Bottom = 3,
+    Front = 4,
+    Back = 5
+}
+
+impl CubeMapSide {
+    pub fn all() -> [CubeMapSide; 6] {
+        [CubeMapSide::Right, CubeMapSide::Left, CubeMapSide::Top, CubeMapSide::Bottom, CubeMapSide::Front, CubeMapSide::Back]
+    }
+
+    /// The direction from the center of the cube towards this side.
+    pub fn direction(&self) -> Vec3 {
+        match self {
+            CubeMapSide::Right => vec3(1.0, 0.0, 0.0),
+            CubeMapSide::Left => vec3(-1.0, 0.0, 0.0),
+            CubeMapSide::Top => vec3(0.0, 1.0, 0.0),
+            CubeMapSide::Bottom => vec3(0.0, -1.0, 0.0),
+            CubeMapSide::Front => vec3(0.0, 0.0, 1.0),
+            CubeMapSide::Back => vec3(0.0, 0.0, -1.0)
+        }
+    }
+
+    /// The up direction used when rendering this side, as defined by the OpenGL cube map conventions.
+    pub fn up(&self) -> Vec3 {
+        match self {
+            CubeMapSide::Top => vec3(0.0, 0.0, 1.0),
+            CubeMapSide::Bottom => vec3(0.0, 0.0, -1.0),
+            _ => vec3(0.0, -1.0, 0.0)
+        }
+    }
+
+    fn target(&self) -> u32 {
+        consts::TEXTURE_CUBE_MAP_POSITIVE_X + *self as u32
+    }
 }
 
 pub trait Texture {
@@ -39,7 +113,8 @@ pub struct Texture2D {
     pub width: usize,
     pub height: usize,
     format: Format,
-    number_of_mip_maps: u32
+    number_of_mip_maps: u32,
+    resource: usize
 }
 
 impl Texture2D
@@ -55,27 +130,24 @@ impl Texture2D
                         format as u32,
                         width as u32,
                         height as u32);
-        Ok(Self { gl: gl.clone(), id, width, height, format, number_of_mip_maps })
+        let resource = resources::register(gl, ResourceCategory::Texture2D, resources::texture_bytes(format, width, height, 1, number_of_mip_maps));
+        let texture = Self { gl: gl.clone(), id, width, height, format, number_of_mip_maps, resource };
+        check_gl_errors(gl, "creating a 2D texture")?;
+        Ok(texture)
     }
 
     #[cfg(feature = "image-io")]
     pub fn new_from_bytes(gl: &Gl, min_filter: Interpolation, mag_filter: Interpolation, mip_map_filter: Option<Interpolation>,
            wrap_s: Wrapping, wrap_t: Wrapping, bytes: &[u8]) -> Result<Texture2D, Error>
     {
-        use image::GenericImageView;
-        let img = image::load_from_memory(bytes)?;
-        let (width, height) = img.dimensions();
-        Self::new_with_u8(gl, min_filter, mag_filter, mip_map_filter, wrap_s, wrap_t, width, height, &img.raw_pixels())
+        CPUTexture::from_bytes(min_filter, mag_filter, mip_map_filter, wrap_s, wrap_t, bytes)?.to_texture(gl)
     }
 
     #[cfg(all(not(target_arch = "wasm32"), feature = "image-io"))]
     pub fn new_from_file(gl: &Gl, min_filter: Interpolation, mag_filter: Interpolation, mip_map_filter: Option<Interpolation>,
            wrap_s: Wrapping, wrap_t: Wrapping, path: &str) -> Result<Texture2D, Error>
     {
-        use image::GenericImageView;
-        let img = image::open(path)?;
-        let (width, height) = img.dimensions();
-        Self::new_with_u8(gl, min_filter, mag_filter, mip_map_filter, wrap_s, wrap_t, width, height, &img.raw_pixels())
+        Self::new_from_bytes(gl, min_filter, mag_filter, mip_map_filter, wrap_s, wrap_t, &std::fs::read(path)?)
     }
 
     pub fn new_with_u8(gl: &Gl, min_filter: Interpolation, mag_filter: Interpolation, mip_map_filter: Option<Interpolation>,
@@ -117,16 +189,16 @@ impl Texture2D
                                               self.width as u32, self.height as u32,
                                               format, consts::UNSIGNED_BYTE, data);
         self.generate_mip_maps();
-        Ok(())
+        check_gl_errors(&self.gl, "filling a texture")
     }
 
     pub fn fill_with_f32(&mut self, data: &[f32]) -> Result<(), Error>
     {
         let format =
             match self.format {
-                Format::R32F => Ok(consts::RED),
-                Format::RGB32F => Ok(consts::RGB),
-                Format::RGBA32F => Ok(consts::RGBA),
+                Format::R16F | Format::R32F => Ok(consts::RED),
+                Format::RGB16F | Format::RGB32F => Ok(consts::RGB),
+                Format::RGBA16F | Format::RGBA32F => Ok(consts::RGBA),
                 _ => Err(Error::FailedToCreateTexture {message: "Wrong texture format".to_string()})
             }?;
 
@@ -142,26 +214,33 @@ impl Texture2D
                                            self.width as u32,self.height as u32,
                                                format,consts::FLOAT,data);
         self.generate_mip_maps();
-        Ok(())
+        check_gl_errors(&self.gl, "filling a texture")
     }
 
-    pub(crate) fn generate_mip_maps(&self) {
+    pub fn generate_mip_maps(&self) {
         if self.number_of_mip_maps > 1 {
             self.gl.bind_texture(consts::TEXTURE_2D, &self.id);
             self.gl.generate_mipmap(consts::TEXTURE_2D);
         }
     }
 
-    pub(crate) fn bind_as_color_target(&self, channel: usize)
+    pub(crate) fn bind_as_color_target(&self, channel: usize, level: usize)
     {
-        self.gl.framebuffer_texture_2d(consts::FRAMEBUFFER,
-                       consts::COLOR_ATTACHMENT0 + channel as u32, consts::TEXTURE_2D, &self.id, 0);
+        self.gl.framebuffer_texture_2d(consts::DRAW_FRAMEBUFFER,
+                       consts::COLOR_ATTACHMENT0 + channel as u32, consts::TEXTURE_2D, &self.id, level as u32);
     }
 
-    pub(crate) fn bind_as_depth_target(&self)
+    pub(crate) fn bind_as_depth_target(&self, level: usize)
     {
-        self.gl.framebuffer_texture_2d(consts::FRAMEBUFFER,
-                       consts::DEPTH_ATTACHMENT, consts::TEXTURE_2D, &self.id, 0);
+        self.gl.framebuffer_texture_2d(consts::DRAW_FRAMEBUFFER,
+                       self.format.depth_attachment(), consts::TEXTURE_2D, &self.id, level as u32);
+    }
+
+    /// Names this texture in graphics debuggers such as RenderDoc (KHR_debug) and in the report of `resources`.
+    pub fn set_label(&self, label: &str)
+    {
+        self.gl.object_label(consts::TEXTURE, &self.id, label);
+        resources::set_label(&self.gl, self.resource, label);
     }
 }
 
@@ -178,6 +257,7 @@ impl Drop for Texture2D
     fn drop(&mut self)
     {
         self.gl.delete_texture(&self.id);
+        resources::unregister(&self.gl, self.resource);
     }
 }
 
@@ -187,7 +267,8 @@ pub struct TextureCubeMap {
     pub width: usize,
     pub height: usize,
     format: Format,
-    number_of_mip_maps: u32
+    number_of_mip_maps: u32,
+    resource: usize
 }
 
 impl TextureCubeMap
@@ -205,7 +286,10 @@ impl TextureCubeMap
                     format as u32,
                     width as u32,
                     height as u32);
-        Ok(Self { gl: gl.clone(), id, width, height, format, number_of_mip_maps })
+        let resource = resources::register(gl, ResourceCategory::TextureCubeMap, resources::texture_bytes(format, width, height, 6, number_of_mip_maps));
+        let texture = Self { gl: gl.clone(), id, width, height, format, number_of_mip_maps, resource };
+        check_gl_errors(gl, "creating a cube map texture")?;
+        Ok(texture)
     }
 
     #[cfg(feature = "image-io")]
@@ -280,15 +364,162 @@ impl TextureCubeMap
                                                   format, consts::UNSIGNED_BYTE, data[i]);
         }
         self.generate_mip_maps();
-        Ok(())
+        check_gl_errors(&self.gl, "filling a texture")
     }
 
-    pub(crate) fn generate_mip_maps(&self) {
+    pub fn fill_with_f32(&mut self, data: [&[f32]; 6]) -> Result<(), Error>
+    {
+        let format =
+            match self.format {
+                Format::R16F | Format::R32F => Ok(consts::RED),
+                Format::RGB16F | Format::RGB32F => Ok(consts::RGB),
+                Format::RGBA16F | Format::RGBA32F => Ok(consts::RGBA),
+                _ => Err(Error::FailedToCreateTexture {message: "Wrong texture format".to_string()})
+            }?;
+
+        let mut desired_length = self.width * self.height;
+        if format == consts::RGB { desired_length *= 3 };
+        if format == consts::RGBA { desired_length *= 4 };
+
+        if let Some(side) = data.iter().find(|side| side.len() != desired_length) {
+            Err(Error::FailedToCreateTexture {message: format!("Wrong size of data for the texture ({} != {})", side.len(), desired_length)})?
+        }
+        self.gl.bind_texture(consts::TEXTURE_CUBE_MAP, &self.id);
+        for i in 0..6 {
+            self.gl.tex_sub_image_2d_with_f32_data(consts::TEXTURE_CUBE_MAP_POSITIVE_X + i as u32, 0, 0, 0,
+                                                  self.width as u32, self.height as u32,
+                                                  format, consts::FLOAT, data[i]);
+        }
+        self.generate_mip_maps();
+        check_gl_errors(&self.gl, "filling a texture")
+    }
+
+    /// Creates a cube map from a single image where the six sides are laid out in a horizontal (4x3) or vertical (3x4) cross.
+    /// The layout is deduced from the aspect ratio of the image. High dynamic range images (.hdr) results in a float texture.
+    #[cfg(feature = "image-io")]
+    pub fn new_from_cross_bytes(gl: &Gl, min_filter: Interpolation, mag_filter: Interpolation, mip_map_filter: Option<Interpolation>,
+                                bytes: &[u8]) -> Result<TextureCubeMap, Error>
+    {
+        let (width, height, pixels) = decode_image(bytes)?;
+        let (size, offsets) =
+            if 3 * width == 4 * height {
+                (width / 4, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)])
+            } else if 4 * width == 3 * height {
+                (width / 3, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (1, 3)])
+            } else {
+                Err(Error::FailedToCreateTexture {message: format!("The image size {}x{} does not match a cube map cross layout", width, height)})?
+            };
+        // The back side is upside down in the vertical cross layout
+        let rotated = |side: usize| width < height && side == CubeMapSide::Back as usize;
+
+        let wrapping = Wrapping::ClampToEdge;
+        match pixels {
+            TextureData::U8(data) => {
+                let channels = data.len() / (width * height) as usize;
+                let sides: Vec<Vec<u8>> = (0..6).map(|i| crop_square(&data, width, channels, offsets[i], size, rotated(i))).collect();
+                Self::new_with_u8(gl, min_filter, mag_filter, mip_map_filter, wrapping, wrapping, wrapping, size, size,
+                                  [&sides[0], &sides[1], &sides[2], &sides[3], &sides[4], &sides[5]])
+            },
+            TextureData::F32(data) => {
+                let sides: Vec<Vec<f32>> = (0..6).map(|i| crop_square(&data, width, 3, offsets[i], size, rotated(i))).collect();
+                let format = float_format(gl, false, min_filter, mag_filter, mip_map_filter);
+                let mut texture = Self::new(gl, size as usize, size as usize, min_filter, mag_filter, mip_map_filter,
+                                            wrapping, wrapping, wrapping, format)?;
+                texture.fill_with_f32([&sides[0], &sides[1], &sides[2], &sides[3], &sides[4], &sides[5]])?;
+                Ok(texture)
+            }
+        }
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "image-io"))]
+    pub fn new_from_cross_file(gl: &Gl, min_filter: Interpolation, mag_filter: Interpolation, mip_map_filter: Option<Interpolation>,
+                               path: &str) -> Result<TextureCubeMap, Error>
+    {
+        Self::new_from_cross_bytes(gl, min_filter, mag_filter, mip_map_filter, &std::fs::read(path)?)
+    }
+
+    /// Creates a cube map from an image in the equirectangular (latitude/longitude) projection, for example a high dynamic range (.hdr) environment map.
+    #[cfg(feature = "image-io")]
+    pub fn new_from_equirectangular_bytes(gl: &Gl, min_filter: Interpolation, mag_filter: Interpolation, mip_map_filter: Option<Interpolation>,
+                                          texture_size: usize, bytes: &[u8]) -> Result<TextureCubeMap, Error>
+    {
+        let equirectangular = Texture2D::new_from_bytes(gl, Interpolation::Linear, Interpolation::Linear, None,
+                                                         Wrapping::Repeat, Wrapping::ClampToEdge, bytes)?;
+        let format = if capabilities(gl).float_render_targets { float_format(gl, true, min_filter, mag_filter, mip_map_filter) } else { Format::RGBA8 };
+        Self::new_from_equirectangular(gl, min_filter, mag_filter, mip_map_filter, format, texture_size, &equirectangular)
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "image-io"))]
+    pub fn new_from_equirectangular_file(gl: &Gl, min_filter: Interpolation, mag_filter: Interpolation, mip_map_filter: Option<Interpolation>,
+                                         texture_size: usize, path: &str) -> Result<TextureCubeMap, Error>
+    {
+        Self::new_from_equirectangular_bytes(gl, min_filter, mag_filter, mip_map_filter, texture_size, &std::fs::read(path)?)
+    }
+
+    /// Projects the equirectangular texture onto the sides of a new cube map on the GPU. The format must be color renderable.
+    pub fn new_from_equirectangular(gl: &Gl, min_filter: Interpolation, mag_filter: Interpolation, mip_map_filter: Option<Interpolation>,
+                                    format: Format, texture_size: usize, equirectangular: &Texture2D) -> Result<TextureCubeMap, Error>
+    {
+        let texture = Self::new(gl, texture_size, texture_size, min_filter, mag_filter, mip_map_filter,
+                                Wrapping::ClampToEdge, Wrapping::ClampToEdge, Wrapping::ClampToEdge, format)?;
+        let effect = ImageEffect::new(gl, "
+            uniform sampler2D equirectangularMap;
+            uniform vec3 direction;
+            uniform vec3 right;
+            uniform vec3 up;
+            in vec2 uv;
+            layout (location = 0) out vec4 color;
+            void main()
+            {
+                vec3 dir = normalize(direction + (2.0 * uv.x - 1.0) * right + (2.0 * uv.y - 1.0) * up);
+                vec2 coords = vec2(atan(dir.z, dir.x) * 0.1591549 + 0.5, 0.5 - asin(clamp(dir.y, -1.0, 1.0)) * 0.3183099);
+                color = vec4(texture(equirectangularMap, coords).rgb, 1.0);
+            }")?;
+
+        state::depth_write(gl, false);
+        state::depth_test(gl, state::DepthTestType::None);
+        state::blend(gl, state::BlendType::None);
+        let mut render_target = RenderTarget::new(gl, Some(Attachment::TextureCubeMap {texture, side: CubeMapSide::Right, mip_level: 0}), None)?;
+        for side in CubeMapSide::all().iter() {
+            effect.program().use_texture(equirectangular, "equirectangularMap")?;
+            effect.program().add_uniform_vec3("direction", &side.direction())?;
+            effect.program().add_uniform_vec3("right", &side.direction().cross(side.up()))?;
+            effect.program().add_uniform_vec3("up", &side.up())?;
+            render_target.set_cube_map_side(*side, 0)?;
+            render_target.write(None, None, &|| effect.apply())?;
+        }
+        render_target.generate_mip_maps();
+        match render_target.into_attachments().0 {
+            Some(Attachment::TextureCubeMap {texture, ..}) => Ok(texture),
+            _ => unreachable!()
+        }
+    }
+
+    pub fn generate_mip_maps(&self) {
         if self.number_of_mip_maps > 1 {
             self.gl.bind_texture(consts::TEXTURE_CUBE_MAP, &self.id);
             self.gl.generate_mipmap(consts::TEXTURE_CUBE_MAP);
         }
     }
+
+    pub(crate) fn bind_as_color_target(&self, side: CubeMapSide, channel: usize, level: usize)
+    {
+        self.gl.framebuffer_texture_2d(consts::DRAW_FRAMEBUFFER,
+                       consts::COLOR_ATTACHMENT0 + channel as u32, side.target(), &self.id, level as u32);
+    }
+
+    pub(crate) fn bind_as_depth_target(&self, side: CubeMapSide, level: usize)
+    {
+        self.gl.framebuffer_texture_2d(consts::DRAW_FRAMEBUFFER,
+                       self.format.depth_attachment(), side.target(), &self.id, level as u32);
+    }
+
+    /// Names this texture in graphics debuggers such as RenderDoc (KHR_debug) and in the report of `resources`.
+    pub fn set_label(&self, label: &str)
+    {
+        self.gl.object_label(consts::TEXTURE, &self.id, label);
+        resources::set_label(&self.gl, self.resource, label);
+    }
 }
 
 impl Texture for TextureCubeMap
@@ -304,6 +535,7 @@ impl Drop for TextureCubeMap
     fn drop(&mut self)
     {
         self.gl.delete_texture(&self.id);
+        resources::unregister(&self.gl, self.resource);
     }
 }
 
@@ -313,7 +545,9 @@ pub struct Texture2DArray {
     pub width: usize,
     pub height: usize,
     pub depth: usize,
-    number_of_mip_maps: u32
+    format: Format,
+    number_of_mip_maps: u32,
+    resource: usize
 }
 
 impl Texture2DArray
@@ -331,10 +565,13 @@ impl Texture2DArray
                         width as u32,
                         height as u32,
                         depth as u32);
-        Ok(Self { gl: gl.clone(), id, width, height, depth, number_of_mip_maps })
+        let resource = resources::register(gl, ResourceCategory::Texture2DArray, resources::texture_bytes(format, width, height, depth, number_of_mip_maps));
+        let texture = Self { gl: gl.clone(), id, width, height, depth, format, number_of_mip_maps, resource };
+        check_gl_errors(gl, "creating a 2D texture array")?;
+        Ok(texture)
     }
 
-    pub(crate) fn generate_mip_maps(&self) {
+    pub fn generate_mip_maps(&self) {
         if self.number_of_mip_maps > 1 {
             self.gl.bind_texture(consts::TEXTURE_2D_ARRAY, &self.id);
             self.gl.generate_mipmap(consts::TEXTURE_2D_ARRAY);
@@ -350,7 +587,14 @@ impl Texture2DArray
     pub(crate) fn bind_as_depth_target(&self, layer: usize)
     {
         self.gl.framebuffer_texture_layer(consts::DRAW_FRAMEBUFFER,
-                       consts::DEPTH_ATTACHMENT, &self.id, 0, layer as u32);
+                       self.format.depth_attachment(), &self.id, 0, layer as u32);
+    }
+
+    /// Names this texture in graphics debuggers such as RenderDoc (KHR_debug) and in the report of `resources`.
+    pub fn set_label(&self, label: &str)
+    {
+        self.gl.object_label(consts::TEXTURE, &self.id, label);
+        resources::set_label(&self.gl, self.resource, label);
     }
 }
 
@@ -367,11 +611,64 @@ impl Drop for Texture2DArray
     fn drop(&mut self)
     {
         self.gl.delete_texture(&self.id);
+        resources::unregister(&self.gl, self.resource);
     }
 }
 
 
 // COMMON FUNCTIONS
+/// The format used for float image data, which is 32 bit unless the texture is linearly filtered and that is not supported for 32 bit float textures,
+/// see `Capabilities::float_linear_filtering`.
+pub(crate) fn float_format(gl: &Gl, alpha: bool, min_filter: Interpolation, mag_filter: Interpolation, mip_map_filter: Option<Interpolation>) -> Format
+{
+    let linear = min_filter == Interpolation::Linear || mag_filter == Interpolation::Linear || mip_map_filter == Some(Interpolation::Linear);
+    let full_precision = !linear || capabilities(gl).float_linear_filtering;
+    match (alpha, full_precision) {
+        (false, true) => Format::RGB32F,
+        (false, false) => Format::RGB16F,
+        (true, true) => Format::RGBA32F,
+        (true, false) => Format::RGBA16F
+    }
+}
+
+#[cfg(feature = "image-io")]
+pub(crate) fn decode_image(bytes: &[u8]) -> Result<(u32, u32, TextureData), Error>
+{
+    if let Ok(image::ImageFormat::HDR) = image::guess_format(bytes) {
+        let decoder = image::hdr::HDRDecoder::new(std::io::BufReader::new(bytes))?;
+        let metadata = decoder.metadata();
+        let mut data = Vec::with_capacity(3 * (metadata.width * metadata.height) as usize);
+        for pixel in decoder.read_image_native()? {
+            data.extend_from_slice(&pixel.to_hdr().0);
+        }
+        Ok((metadata.width, metadata.height, TextureData::F32(data)))
+    }
+    else {
+        use image::GenericImageView;
+        let img = image::load_from_memory(bytes)?;
+        let (width, height) = img.dimensions();
+        Ok((width, height, TextureData::U8(img.raw_pixels())))
+    }
+}
+
+#[cfg(feature = "image-io")]
+fn crop_square<T: Copy>(data: &[T], width: u32, channels: usize, offset: (u32, u32), size: u32, rotate: bool) -> Vec<T>
+{
+    let mut result = Vec::with_capacity((size * size) as usize * channels);
+    for row in 0..size {
+        let start = (((offset.1 * size + row) * width + offset.0 * size) as usize) * channels;
+        result.extend_from_slice(&data[start..start + size as usize * channels]);
+    }
+    if rotate {
+        let mut rotated = Vec::with_capacity(result.len());
+        for pixel in result.chunks(channels).rev() {
+            rotated.extend_from_slice(pixel);
+        }
+        result = rotated;
+    }
+    result
+}
+
 fn generate(gl: &Gl) -> Result<crate::gl::Texture, Error>
 {
     gl.create_texture().ok_or_else(|| Error::FailedToCreateTexture {message: "Failed to create texture".to_string()} )
@@ -386,21 +683,7 @@ fn bind_at(gl: &Gl, id: &crate::gl::Texture, target: u32, location: u32)
 fn set_parameters(gl: &Gl, id: &crate::gl::Texture, target: u32, min_filter: Interpolation, mag_filter: Interpolation, mip_map_filter: Option<Interpolation>, wrap_s: Wrapping, wrap_t: Wrapping, wrap_r: Option<Wrapping>)
 {
     gl.bind_texture(target, id);
-    match mip_map_filter {
-        None => gl.tex_parameteri(target, consts::TEXTURE_MIN_FILTER, min_filter as i32),
-        Some(Interpolation::Nearest) =>
-            if min_filter == Interpolation::Nearest {
-                gl.tex_parameteri(target, consts::TEXTURE_MIN_FILTER, consts::NEAREST_MIPMAP_NEAREST as i32);
-            } else {
-                gl.tex_parameteri(target, consts::TEXTURE_MIN_FILTER, consts::LINEAR_MIPMAP_NEAREST as i32)
-            },
-        Some(Interpolation::Linear) =>
-            if min_filter == Interpolation::Nearest {
-                gl.tex_parameteri(target, consts::TEXTURE_MIN_FILTER, consts::NEAREST_MIPMAP_LINEAR as i32);
-            } else {
-                gl.tex_parameteri(target, consts::TEXTURE_MIN_FILTER, consts::LINEAR_MIPMAP_LINEAR as i32)
-            }
-    }
+    gl.tex_parameteri(target, consts::TEXTURE_MIN_FILTER, min_filter_parameter(min_filter, mip_map_filter));
     gl.tex_parameteri(target, consts::TEXTURE_MAG_FILTER, mag_filter as i32);
     gl.tex_parameteri(target, consts::TEXTURE_WRAP_S, wrap_s as i32);
     gl.tex_parameteri(target, consts::TEXTURE_WRAP_T, wrap_t as i32);
@@ -409,6 +692,18 @@ fn set_parameters(gl: &Gl, id: &crate::gl::Texture, target: u32, min_filter: Int
     }
 }
 
+pub(crate) fn min_filter_parameter(min_filter: Interpolation, mip_map_filter: Option<Interpolation>) -> i32
+{
+    let parameter = match mip_map_filter {
+        None => min_filter as u32,
+        Some(Interpolation::Nearest) =>
+            if min_filter == Interpolation::Nearest {consts::NEAREST_MIPMAP_NEAREST} else {consts::LINEAR_MIPMAP_NEAREST},
+        Some(Interpolation::Linear) =>
+            if min_filter == Interpolation::Nearest {consts::NEAREST_MIPMAP_LINEAR} else {consts::LINEAR_MIPMAP_LINEAR}
+    };
+    parameter as i32
+}
+
 fn calculate_number_of_mip_maps(mip_map_filter: Option<Interpolation>, width: usize, height: usize, depth: usize) -> u32 {
     if mip_map_filter.is_some() {
             let w = (width as f64).log2().ceil();
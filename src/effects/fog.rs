@@ -17,6 +17,7 @@ impl FogEffect {
 
     pub fn apply(&self, time: f32, camera: &camera::Camera, depth_texture: &Texture2DArray) -> Result<(), Error>
     {
+        let _group = DebugGroup::new(&self.gl, "Fog effect");
         state::depth_write(&self.gl,false);
         state::depth_test(&self.gl, state::DepthTestType::None);
         state::blend(&self.gl, state::BlendType::SrcAlphaOneMinusSrcAlpha);
@@ -6,6 +6,24 @@ pub mod ogl;
 #[cfg(not(target_arch = "wasm32"))]
 pub use ogl::*;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod backend;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use backend::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod recording;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use recording::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cpu;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use cpu::*;
+
 // WEBGL
 #[cfg(target_arch = "wasm32")]
 pub mod wgl2;
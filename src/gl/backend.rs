@@ -0,0 +1,143 @@
+use crate::gl::*;
+
+/// The OpenGL calls made by this crate on desktop, which are implemented by `OpenGL` calling the driver
+/// and by `RecordingBackend` recording the calls without a GPU.
+pub trait Backend {
+    fn get_error(&self) -> u32;
+    /// Enables or disables capturing the messages of the driver (KHR_debug).
+    /// Errors and undefined behavior are returned by `take_debug_messages`, the other messages by `take_debug_output`.
+    fn set_debug_messages(&self, enable: bool);
+    fn take_debug_messages(&self) -> Vec<String>;
+    /// The messages of the driver which are not errors, for example about performance or portability.
+    fn take_debug_output(&self) -> Vec<String>;
+    /// Names an object in graphics debuggers (KHR_debug), `identifier` is for example `consts::BUFFER` or `consts::TEXTURE`.
+    /// Does nothing if the extension is not supported.
+    fn object_label(&self, identifier: u32, name: &u32, label: &str);
+    /// Starts a named group of calls in graphics debuggers (KHR_debug), which is ended by `pop_debug_group`.
+    /// Does nothing if the extension is not supported.
+    fn push_debug_group(&self, message: &str);
+    fn pop_debug_group(&self);
+    fn finish(&self);
+    fn create_shader(&self, type_: u32) -> Option<Shader>;
+    fn compile_shader(&self, source: &str, shader: &Shader);
+    fn get_shader_info_log(&self, shader: &Shader) -> Option<String>;
+    fn delete_shader(&self, shader: Option<&Shader>);
+    fn attach_shader(&self, program: &Program, shader: &Shader);
+    fn detach_shader(&self, program: &Program, shader: &Shader);
+    fn get_program_parameter(&self, program: &Program, pname: u32) -> u32;
+    fn get_active_attrib(&self, program: &Program, index: u32) -> ActiveInfo;
+    fn get_active_uniform(&self, program: &Program, index: u32) -> ActiveInfo;
+    fn create_buffer(&self) -> Option<Buffer>;
+    fn delete_buffer(&self, buffer: &Buffer);
+    fn bind_buffer_base(&self, target: u32, index: u32, buffer: &Buffer);
+    fn bind_buffer(&self, target: u32, buffer: &Buffer);
+    fn unbind_buffer(&self, target: u32);
+    fn get_uniform_block_index(&self, program: &Program, name: &str) -> u32;
+    fn uniform_block_binding(&self, program: &Program, location: u32, index: u32);
+    fn buffer_data(&self, target: u32, size_in_bytes: u32, usage: u32);
+    fn buffer_data_u8(&self, target: u32, data: &[u8], usage: u32);
+    fn buffer_data_u32(&self, target: u32, data: &[u32], usage: u32);
+    fn buffer_data_f32(&self, target: u32, data: &[f32], usage: u32);
+    fn create_vertex_array(&self) -> Option<VertexArrayObject>;
+    fn bind_vertex_array(&self, array: &VertexArrayObject);
+    fn create_program(&self) -> Program;
+    fn link_program(&self, program: &Program) -> bool;
+    fn get_program_info_log(&self, program: &Program) -> Option<String>;
+    fn use_program(&self, program: &Program);
+    fn unuse_program(&self);
+    fn delete_program(&self, program: &Program);
+    fn get_attrib_location(&self, program: &Program, name: &str) -> Option<AttributeLocation>;
+    fn enable_vertex_attrib_array(&self, location: AttributeLocation);
+    fn disable_vertex_attrib_array(&self, location: AttributeLocation);
+    fn vertex_attrib_pointer(&self, location: AttributeLocation, size: u32, data_type: u32, normalized: bool, stride: u32, offset: u32);
+    fn vertex_attrib_divisor(&self, location: AttributeLocation, divisor: u32);
+    fn get_uniform_location(&self, program: &Program, name: &str) -> Option<UniformLocation>;
+    fn uniform1i(&self, location: &UniformLocation, data: i32);
+    fn uniform1f(&self, location: &UniformLocation, data: f32);
+    fn uniform2fv(&self, location: &UniformLocation, data: &[f32]);
+    fn uniform3fv(&self, location: &UniformLocation, data: &[f32]);
+    fn uniform4fv(&self, location: &UniformLocation, data: &[f32]);
+    fn uniform_matrix2fv(&self, location: &UniformLocation, data: &[f32]);
+    fn uniform_matrix3fv(&self, location: &UniformLocation, data: &[f32]);
+    fn uniform_matrix4fv(&self, location: &UniformLocation, data: &[f32]);
+    fn draw_buffers(&self, draw_buffers: &[u32]);
+    fn read_buffer(&self, source: u32);
+    fn create_framebuffer(&self) -> Option<Framebuffer>;
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<&Framebuffer>);
+    fn delete_framebuffer(&self, framebuffer: Option<&Framebuffer>);
+    fn create_renderbuffer(&self) -> Option<Renderbuffer>;
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<&Renderbuffer>);
+    fn renderbuffer_storage_multisample(&self, target: u32, samples: u32, internalformat: u32, width: u32, height: u32);
+    fn framebuffer_renderbuffer(&self, target: u32, attachment: u32, renderbuffertarget: u32, renderbuffer: Option<&Renderbuffer>);
+    fn delete_renderbuffer(&self, renderbuffer: &Renderbuffer);
+    fn check_framebuffer_status(&self) -> Result<(), String>;
+    fn blit_framebuffer(&self, src_x0: u32, src_y0: u32, src_x1: u32, src_y1: u32, dst_x0: u32, dst_y0: u32, dst_x1: u32, dst_y1: u32, mask: u32, filter: u32);
+    fn pixel_storei(&self, pname: u32, param: i32);
+    fn scissor(&self, x: i32, y: i32, width: usize, height: usize);
+    fn viewport(&self, x: i32, y: i32, width: usize, height: usize);
+    fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32);
+    fn clear_depth(&self, depth: f32);
+    fn clear_stencil(&self, stencil: i32);
+    fn clear(&self, mask: u32);
+    fn enable(&self, cap: u32);
+    fn disable(&self, cap: u32);
+    fn blend_func(&self, sfactor: u32, dfactor: u32);
+    fn blend_func_separate(&self, src_rgb: u32, dst_rgb: u32, src_alpha: u32, dst_alpha: u32);
+    fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32);
+    fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32);
+    fn polygon_offset(&self, factor: f32, units: f32);
+    fn line_width(&self, width: f32);
+    fn polygon_mode(&self, face: u32, mode: u32);
+    fn cull_face(&self, mode: u32);
+    fn depth_func(&self, func: u32);
+    fn depth_mask(&self, flag: bool);
+    fn stencil_func(&self, func: u32, reference: i32, mask: u32);
+    fn stencil_op(&self, stencil_fail: u32, depth_fail: u32, pass: u32);
+    fn stencil_mask(&self, mask: u32);
+    fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool);
+    fn create_texture(&self) -> Option<Texture>;
+    fn active_texture(&self, texture: u32);
+    fn bind_texture(&self, target: u32, texture: &Texture);
+    fn generate_mipmap(&self, target: u32);
+    fn tex_storage_2d(&self, target: u32, levels: u32, internalformat: u32, width: u32, height: u32);
+    fn tex_storage_3d(&self, target: u32, levels: u32, internalformat: u32, width: u32, height: u32, depth: u32);
+    fn tex_image_2d(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, border: u32, format: u32, data_type: u32);
+    fn tex_image_2d_with_u8_data(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, border: u32, format: u32, data_type: u32, pixels: &[u8]);
+    fn tex_sub_image_2d_with_u8_data(&self, target: u32, level: u32, x_offset: u32, y_offset: u32, width: u32, height: u32, format: u32, data_type: u32, pixels: &[u8]);
+    fn tex_image_2d_with_f32_data(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, border: u32, format: u32, data_type: u32, pixels: &[f32]);
+    fn tex_sub_image_2d_with_f32_data(&self, target: u32, level: u32, x_offset: u32, y_offset: u32, width: u32, height: u32, format: u32, data_type: u32, pixels: &[f32]);
+    fn tex_image_3d(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, depth: u32, format: u32, data_type: u32);
+    fn tex_parameteri(&self, target: u32, pname: u32, param: i32);
+    fn delete_texture(&self, texture: &Texture);
+    fn create_sampler(&self) -> Option<Sampler>;
+    fn bind_sampler(&self, unit: u32, sampler: Option<&Sampler>);
+    fn sampler_parameteri(&self, sampler: &Sampler, pname: u32, param: i32);
+    fn sampler_parameterf(&self, sampler: &Sampler, pname: u32, param: f32);
+    fn sampler_parameterfv(&self, sampler: &Sampler, pname: u32, params: &[f32]);
+    fn delete_sampler(&self, sampler: &Sampler);
+    fn framebuffer_texture_2d(&self, target: u32, attachment: u32, textarget: u32, texture: &Texture, level: u32);
+    fn framebuffer_texture_layer(&self, target: u32, attachment: u32, texture: &Texture, level: u32, layer: u32);
+    fn draw_arrays(&self, mode: u32, first: u32, count: u32);
+    fn draw_arrays_instanced(&self, mode: u32, first: u32, count: u32, instance_count: u32);
+    fn draw_elements(&self, mode: u32, count: u32, data_type: u32, offset: u32);
+    fn draw_elements_instanced(&self, mode: u32, count: u32, data_type: u32, offset: u32, instance_count: u32);
+    fn draw_elements_indirect(&self, mode: u32, data_type: u32, offset: u32);
+    fn multi_draw_elements_indirect(&self, mode: u32, data_type: u32, offset: u32, draw_count: u32, stride: u32);
+    fn read_pixels(&self, x: u32, y: u32, width: u32, height: u32, format: u32, data_type: u32);
+    fn read_pixels_with_u8_data(&self, x: u32, y: u32, width: u32, height: u32, format: u32, data_type: u32, dst_data: &mut [u8]);
+    fn read_pixels_with_f32_data(&self, x: u32, y: u32, width: u32, height: u32, format: u32, data_type: u32, dst_data: &mut [f32]);
+    fn get_parameter_f32(&self, pname: u32) -> f32;
+    fn get_parameter_f32v(&self, pname: u32, values: &mut [f32]);
+    fn get_parameter_i32v(&self, pname: u32, values: &mut [i32]);
+    /// Returns the value of an indexed parameter, for example the buffer bound to a uniform buffer binding point.
+    fn get_parameter_indexed_i32(&self, pname: u32, index: u32) -> i32;
+    fn is_enabled(&self, cap: u32) -> bool;
+    /// Returns a description of the context, for example `consts::VERSION` or `consts::RENDERER`.
+    fn get_string(&self, pname: u32) -> String;
+    fn get_extensions(&self) -> Vec<String>;
+    fn supports_extension(&self, name: &str) -> bool;
+    fn flush(&self);
+    fn fence_sync(&self) -> Sync;
+    fn client_wait_sync(&self, sync: &Sync, flags: u32, timeout: u32) -> u32;
+    fn delete_sync(&self, sync: &Sync);
+}
@@ -0,0 +1,1520 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use crate::gl::*;
+use crate::core::types::*;
+
+mod rasterizer;
+mod shaders;
+
+/// A vertex shader implemented in Rust, which writes the outputs of the vertex to `varyings` and returns the clip space position (`gl_Position`).
+pub type VertexShader = Rc<dyn Fn(&ShaderContext, &VertexInput, &mut Vec<f32>) -> Vec4>;
+
+/// A fragment shader implemented in Rust, which writes the outputs to `output` and returns false to discard the fragment.
+pub type FragmentShader = Rc<dyn Fn(&ShaderContext, &FragmentInput, &mut FragmentOutput) -> bool>;
+
+/// Gives shaders access to the uniforms, uniform blocks and textures of the program being drawn.
+pub struct ShaderContext<'a> {
+    program: &'a CpuProgram,
+    blocks: HashMap<String, Vec<f32>>,
+    images: &'a HashMap<u32, CpuImage>,
+    bound_textures: &'a HashMap<(u32, u32), u32>,
+    sampler_units: &'a HashMap<u32, u32>,
+    samplers: &'a HashMap<u32, SamplerParameters>
+}
+
+impl<'a> ShaderContext<'a> {
+    /// The value of the uniform, which is empty if the uniform is not set. Integers are stored as floats.
+    pub fn uniform(&self, name: &str) -> &[f32]
+    {
+        self.program.uniforms.iter().position(|uniform| uniform == name)
+            .and_then(|location| self.program.uniform_values.get(&(location as u32)))
+            .map(|value| value.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn uniform_f32(&self, name: &str) -> f32
+    {
+        self.uniform(name).first().cloned().unwrap_or(0.0)
+    }
+
+    pub fn uniform_i32(&self, name: &str) -> i32
+    {
+        self.uniform_f32(name) as i32
+    }
+
+    pub fn uniform_vec3(&self, name: &str) -> Vec3
+    {
+        let value = self.uniform(name);
+        if value.len() < 3 { vec3(0.0, 0.0, 0.0) } else { vec3(value[0], value[1], value[2]) }
+    }
+
+    pub fn uniform_mat4(&self, name: &str) -> Mat4
+    {
+        mat4_from(self.uniform(name))
+    }
+
+    /// The content of the uniform block with the given name, in the std140 layout.
+    pub fn block(&self, name: &str) -> &[f32]
+    {
+        self.blocks.get(name).map(|block| block.as_slice()).unwrap_or(&[])
+    }
+
+    /// Samples the texture bound to the sampler uniform with the given name.
+    /// The coordinates are (u, v, unused) for 2D textures, (u, v, layer) for 2D array textures and a direction for cube maps.
+    pub fn texture(&self, name: &str, coordinates: Vec3) -> Vec4
+    {
+        match self.sampler(name) {
+            Some((image, parameters)) => image.sample(&parameters, coordinates),
+            None => vec4(0.0, 0.0, 0.0, 1.0)
+        }
+    }
+
+    /// Samples the depth texture bound to the sampler uniform with the given name and compares the depth with `reference` (a shadow sampler).
+    pub fn texture_compare(&self, name: &str, uv: Vec2, reference: f32) -> f32
+    {
+        match self.sampler(name) {
+            Some((image, parameters)) => image.sample_compare(&parameters, uv, reference),
+            None => 1.0
+        }
+    }
+
+    fn sampler(&self, name: &str) -> Option<(&CpuImage, SamplerParameters)>
+    {
+        let unit = self.uniform_i32(name) as u32;
+        let target = self.program.sampler_targets.get(name).cloned().unwrap_or(consts::TEXTURE_2D);
+        let image = self.bound_textures.get(&(unit, target)).and_then(|id| self.images.get(id))?;
+        let parameters = self.sampler_units.get(&unit).and_then(|id| self.samplers.get(id)).cloned().unwrap_or(image.parameters);
+        Some((image, parameters))
+    }
+}
+
+/// The attributes of a vertex.
+pub struct VertexInput<'a> {
+    names: &'a [String],
+    values: &'a [[f32; 4]],
+    pub vertex_id: u32,
+    pub instance_id: u32
+}
+
+impl<'a> VertexInput<'a> {
+    /// The value of the attribute, where components which are not in the buffer are 0, except the fourth which is 1.
+    pub fn attribute(&self, name: &str) -> Vec4
+    {
+        self.names.iter().position(|n| n == name)
+            .map(|i| vec4(self.values[i][0], self.values[i][1], self.values[i][2], self.values[i][3]))
+            .unwrap_or(vec4(0.0, 0.0, 0.0, 1.0))
+    }
+}
+
+/// The input of a fragment, where the varyings are interpolated perspective correctly from the vertex shader outputs.
+pub struct FragmentInput<'a> {
+    pub varyings: &'a [f32],
+    pub front_facing: bool,
+    /// The window coordinates of the fragment center, the depth and 1/w (`gl_FragCoord`).
+    pub frag_coord: Vec4
+}
+
+/// The output of a fragment, where `colors[i]` is written to draw buffer `i`.
+/// If `depth` is set, it is written instead of the interpolated depth (`gl_FragDepth`).
+pub struct FragmentOutput {
+    pub colors: [Vec4; 8],
+    pub depth: Option<f32>
+}
+
+/// A software rasterizer implementing the subset of OpenGL used by this crate on the CPU, so that images can be rendered without a graphics driver.
+/// The screen is an offscreen buffer of the size given when the backend is created, which is read back with the `Screen` read functions as usual.
+///
+/// GLSL is not interpreted. Instead, each shader source is mapped to a shader implemented in Rust, see `add_vertex_shader` and `add_fragment_shader`.
+/// The shaders used by `ImageEffect`, `Mesh` and the lights of `DeferredPipeline` are added when the backend is created.
+/// Drawing with a program where a shader is missing reports an `INVALID_OPERATION` error (see `core::set_error_check`).
+///
+/// Only triangles are rasterized and stencil testing, multisampling and texture mip maps (the base level is always sampled) are not supported.
+///
+/// The backend is cloned before it is given to the context, since the clones share the state:
+/// ```ignore
+/// let backend = CpuBackend::new(width, height);
+/// let gl = Glstruct::new(Box::new(backend.clone()));
+/// ```
+#[derive(Clone)]
+pub struct CpuBackend {
+    state: Rc<RefCell<CpuState>>,
+    vertex_shaders: Rc<RefCell<HashMap<String, VertexShader>>>,
+    fragment_shaders: Rc<RefCell<HashMap<String, FragmentShader>>>
+}
+
+impl CpuBackend {
+    pub fn new(width: usize, height: usize) -> CpuBackend
+    {
+        let backend = CpuBackend {
+            state: Rc::new(RefCell::new(CpuState::new(width, height))),
+            vertex_shaders: Rc::new(RefCell::new(HashMap::new())),
+            fragment_shaders: Rc::new(RefCell::new(HashMap::new()))
+        };
+        shaders::add_built_in_shaders(&backend);
+        backend
+    }
+
+    /// Uses `shader` for vertex shaders with the given source. Sources are compared ignoring differences in whitespace.
+    pub fn add_vertex_shader(&self, source: &str, shader: VertexShader)
+    {
+        self.vertex_shaders.borrow_mut().insert(normalize_source(source), shader);
+    }
+
+    /// Uses `shader` for fragment shaders with the given source. Sources are compared ignoring differences in whitespace.
+    pub fn add_fragment_shader(&self, source: &str, shader: FragmentShader)
+    {
+        self.fragment_shaders.borrow_mut().insert(normalize_source(source), shader);
+    }
+
+    /// The size of the screen.
+    pub fn size(&self) -> (usize, usize)
+    {
+        let state = self.state.borrow();
+        let screen = &state.images[&SCREEN_COLOR];
+        (screen.width, screen.height)
+    }
+
+    fn error(&self, message: String)
+    {
+        let mut state = self.state.borrow_mut();
+        state.errors.push(consts::INVALID_OPERATION);
+        state.messages.push(message);
+    }
+
+    fn create(&self) -> u32
+    {
+        let mut state = self.state.borrow_mut();
+        state.next_id += 1;
+        state.next_id
+    }
+
+    fn image_at(&self, target: u32) -> Option<u32>
+    {
+        let state = self.state.borrow();
+        let unit = state.active_texture;
+        state.bound_textures.get(&(unit, texture_target(target))).cloned()
+    }
+
+    fn with_image(&self, target: u32, operation: &dyn Fn(&mut CpuImage, usize))
+    {
+        let layer = cube_map_face(target).unwrap_or(0);
+        match self.image_at(target) {
+            Some(id) => {
+                let mut state = self.state.borrow_mut();
+                if let Some(image) = state.images.get_mut(&id) {
+                    operation(image, layer);
+                }
+            },
+            None => self.error(format!("No texture is bound to target {:#x}", target))
+        }
+    }
+
+    fn set_uniform(&self, location: &UniformLocation, data: &[f32])
+    {
+        let mut state = self.state.borrow_mut();
+        let program = state.current_program;
+        match program.and_then(|id| state.programs.get_mut(&id)) {
+            Some(program) => { program.uniform_values.insert(*location, data.to_vec()); },
+            None => {
+                state.errors.push(consts::INVALID_OPERATION);
+                state.messages.push("Setting a uniform without a program".to_string());
+            }
+        }
+    }
+}
+
+const SCREEN: u32 = 0;
+const SCREEN_COLOR: u32 = u32::MAX;
+const SCREEN_DEPTH: u32 = u32::MAX - 1;
+
+struct CpuState {
+    next_id: u32,
+    errors: Vec<u32>,
+    messages: Vec<String>,
+    shaders: HashMap<u32, CpuShader>,
+    programs: HashMap<u32, CpuProgram>,
+    buffers: HashMap<u32, Vec<u8>>,
+    images: HashMap<u32, CpuImage>,
+    framebuffers: HashMap<u32, CpuFramebuffer>,
+    samplers: HashMap<u32, SamplerParameters>,
+    bound_buffers: HashMap<u32, u32>,
+    uniform_buffer_bindings: HashMap<u32, u32>,
+    draw_framebuffer: u32,
+    read_framebuffer: u32,
+    bound_renderbuffer: Option<u32>,
+    active_texture: u32,
+    bound_textures: HashMap<(u32, u32), u32>,
+    sampler_units: HashMap<u32, u32>,
+    attributes: HashMap<u32, AttributeBinding>,
+    current_program: Option<u32>,
+    raster: RasterState
+}
+
+impl CpuState {
+    fn new(width: usize, height: usize) -> CpuState
+    {
+        let mut images = HashMap::new();
+        images.insert(SCREEN_COLOR, CpuImage::new(consts::TEXTURE_2D, consts::RGBA8, width, height, 1, 1));
+        images.insert(SCREEN_DEPTH, CpuImage::new(consts::TEXTURE_2D, consts::DEPTH_COMPONENT24, width, height, 1, 1));
+        let mut screen = CpuFramebuffer::default();
+        screen.attachments.insert(consts::COLOR_ATTACHMENT0, (SCREEN_COLOR, 0, 0));
+        screen.attachments.insert(consts::DEPTH_ATTACHMENT, (SCREEN_DEPTH, 0, 0));
+        let mut framebuffers = HashMap::new();
+        framebuffers.insert(SCREEN, screen);
+
+        CpuState { next_id: 0, errors: Vec::new(), messages: Vec::new(), shaders: HashMap::new(), programs: HashMap::new(),
+            buffers: HashMap::new(), images, framebuffers, samplers: HashMap::new(), bound_buffers: HashMap::new(),
+            uniform_buffer_bindings: HashMap::new(), draw_framebuffer: SCREEN, read_framebuffer: SCREEN, bound_renderbuffer: None,
+            active_texture: 0, bound_textures: HashMap::new(), sampler_units: HashMap::new(),
+            attributes: HashMap::new(), current_program: None, raster: RasterState::new(width, height) }
+    }
+}
+
+struct CpuShader {
+    type_: u32,
+    source: String,
+    vertex: Option<VertexShader>,
+    fragment: Option<FragmentShader>
+}
+
+struct CpuProgram {
+    shaders: Vec<u32>,
+    vertex: Option<VertexShader>,
+    fragment: Option<FragmentShader>,
+    sources: Vec<String>,
+    attributes: Vec<String>,
+    uniforms: Vec<String>,
+    uniform_blocks: Vec<String>,
+    /// The texture target of each sampler uniform, since textures with different targets can be bound to the same unit.
+    sampler_targets: HashMap<String, u32>,
+    uniform_values: HashMap<u32, Vec<f32>>,
+    block_bindings: HashMap<u32, u32>
+}
+
+#[derive(Copy, Clone)]
+struct AttributeBinding {
+    buffer: u32,
+    size: u32,
+    stride: u32,
+    offset: u32,
+    divisor: u32,
+    enabled: bool
+}
+
+#[derive(Default)]
+struct CpuFramebuffer {
+    /// The attached image, mip level and layer for each attachment point.
+    attachments: HashMap<u32, (u32, usize, usize)>,
+    draw_buffers: Option<Vec<u32>>,
+    read_buffer: Option<u32>
+}
+
+impl CpuFramebuffer {
+    fn draw_buffers(&self) -> Vec<u32>
+    {
+        self.draw_buffers.clone().unwrap_or_else(|| vec![consts::COLOR_ATTACHMENT0])
+    }
+
+    fn read_attachment(&self) -> Option<(u32, usize, usize)>
+    {
+        let read_buffer = match self.read_buffer { Some(consts::BACK) | None => consts::COLOR_ATTACHMENT0, Some(read_buffer) => read_buffer };
+        self.attachments.get(&read_buffer).cloned()
+    }
+
+    fn depth_attachment(&self) -> Option<(u32, usize, usize)>
+    {
+        self.attachments.get(&consts::DEPTH_ATTACHMENT).or_else(|| self.attachments.get(&consts::DEPTH_STENCIL_ATTACHMENT)).cloned()
+    }
+}
+
+#[derive(Copy, Clone)]
+struct SamplerParameters {
+    min_filter: u32,
+    mag_filter: u32,
+    wrap_s: u32,
+    wrap_t: u32,
+    compare_mode: u32,
+    compare_func: u32
+}
+
+impl Default for SamplerParameters {
+    fn default() -> Self
+    {
+        SamplerParameters { min_filter: consts::NEAREST_MIPMAP_LINEAR, mag_filter: consts::LINEAR, wrap_s: consts::REPEAT, wrap_t: consts::REPEAT,
+            compare_mode: consts::NONE, compare_func: consts::LEQUAL }
+    }
+}
+
+impl SamplerParameters {
+    fn set(&mut self, pname: u32, param: u32)
+    {
+        match pname {
+            consts::TEXTURE_MIN_FILTER => self.min_filter = param,
+            consts::TEXTURE_MAG_FILTER => self.mag_filter = param,
+            consts::TEXTURE_WRAP_S => self.wrap_s = param,
+            consts::TEXTURE_WRAP_T => self.wrap_t = param,
+            consts::TEXTURE_COMPARE_MODE => self.compare_mode = param,
+            consts::TEXTURE_COMPARE_FUNC => self.compare_func = param,
+            _ => {}
+        }
+    }
+}
+
+/// The storage of a texture or renderbuffer, with the pixels of each mip level stored as RGBA floats, layer after layer.
+struct CpuImage {
+    target: u32,
+    format: u32,
+    width: usize,
+    height: usize,
+    layers: usize,
+    levels: Vec<Vec<[f32; 4]>>,
+    parameters: SamplerParameters
+}
+
+impl CpuImage {
+    fn new(target: u32, format: u32, width: usize, height: usize, layers: usize, levels: usize) -> CpuImage
+    {
+        let default = if is_depth_format(format) { [1.0, 0.0, 0.0, 1.0] } else { [0.0, 0.0, 0.0, 1.0] };
+        let levels = (0..levels.max(1)).map(|level| vec![default; level_size(width, level) * level_size(height, level) * layers]).collect();
+        CpuImage { target, format, width, height, layers, levels, parameters: SamplerParameters::default() }
+    }
+
+    fn level_width(&self, level: usize) -> usize
+    {
+        level_size(self.width, level)
+    }
+
+    fn level_height(&self, level: usize) -> usize
+    {
+        level_size(self.height, level)
+    }
+
+    fn index(&self, level: usize, layer: usize, x: usize, y: usize) -> usize
+    {
+        (layer * self.level_height(level) + y) * self.level_width(level) + x
+    }
+
+    fn get(&self, level: usize, layer: usize, x: usize, y: usize) -> [f32; 4]
+    {
+        self.levels[level][self.index(level, layer, x, y)]
+    }
+
+    /// Stores the value, quantized to the precision of the format.
+    fn set(&mut self, level: usize, layer: usize, x: usize, y: usize, value: [f32; 4])
+    {
+        let value = quantize(self.format, value);
+        let i = self.index(level, layer, x, y);
+        self.levels[level][i] = value;
+    }
+
+    fn generate_mip_maps(&mut self)
+    {
+        for level in 1..self.levels.len() {
+            for layer in 0..self.layers {
+                for y in 0..self.level_height(level) {
+                    for x in 0..self.level_width(level) {
+                        let mut sum = [0.0; 4];
+                        let (w, h) = (self.level_width(level - 1), self.level_height(level - 1));
+                        for (dx, dy) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+                            let value = self.get(level - 1, layer, (2 * x + dx).min(w - 1), (2 * y + dy).min(h - 1));
+                            for c in 0..4 { sum[c] += 0.25 * value[c]; }
+                        }
+                        self.set(level, layer, x, y, sum);
+                    }
+                }
+            }
+        }
+    }
+
+    fn sample(&self, parameters: &SamplerParameters, coordinates: Vec3) -> Vec4
+    {
+        let (uv, layer) = match self.target {
+            consts::TEXTURE_CUBE_MAP => cube_map_uv(coordinates),
+            consts::TEXTURE_2D_ARRAY => (vec2(coordinates.x, coordinates.y), (coordinates.z.round().max(0.0) as usize).min(self.layers - 1)),
+            _ => (vec2(coordinates.x, coordinates.y), 0)
+        };
+        let v = self.filter(parameters, uv, layer, &|value| value);
+        vec4(v[0], v[1], v[2], v[3])
+    }
+
+    fn sample_compare(&self, parameters: &SamplerParameters, uv: Vec2, reference: f32) -> f32
+    {
+        let compare_func = parameters.compare_func;
+        self.filter(parameters, uv, 0, &|value| {
+            let passed = compare(compare_func, reference, value[0]);
+            let result = if passed { 1.0 } else { 0.0 };
+            [result, result, result, 1.0]
+        })[0]
+    }
+
+    fn filter(&self, parameters: &SamplerParameters, uv: Vec2, layer: usize, map: &dyn Fn([f32; 4]) -> [f32; 4]) -> [f32; 4]
+    {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let texel = |x: i32, y: i32| {
+            let x = wrap(parameters.wrap_s, x, self.width);
+            let y = wrap(parameters.wrap_t, y, self.height);
+            map(self.get(0, layer, x, y))
+        };
+        if parameters.mag_filter == consts::NEAREST {
+            texel((uv.x * width).floor() as i32, (uv.y * height).floor() as i32)
+        }
+        else {
+            let x = uv.x * width - 0.5;
+            let y = uv.y * height - 0.5;
+            let (x0, y0) = (x.floor(), y.floor());
+            let (fx, fy) = (x - x0, y - y0);
+            let (x0, y0) = (x0 as i32, y0 as i32);
+            let (a, b, c, d) = (texel(x0, y0), texel(x0 + 1, y0), texel(x0, y0 + 1), texel(x0 + 1, y0 + 1));
+            let mut result = [0.0; 4];
+            for i in 0..4 {
+                result[i] = (a[i] * (1.0 - fx) + b[i] * fx) * (1.0 - fy) + (c[i] * (1.0 - fx) + d[i] * fx) * fy;
+            }
+            result
+        }
+    }
+}
+
+struct RasterState {
+    enabled: HashSet<u32>,
+    viewport: (i32, i32, usize, usize),
+    scissor: (i32, i32, usize, usize),
+    clear_color: [f32; 4],
+    clear_depth: f32,
+    blend_functions: [u32; 4],
+    blend_equations: [u32; 2],
+    blend_color: [f32; 4],
+    color_mask: [bool; 4],
+    depth_func: u32,
+    depth_mask: bool,
+    cull_face: u32,
+    polygon_offset: (f32, f32)
+}
+
+impl RasterState {
+    fn new(width: usize, height: usize) -> RasterState
+    {
+        RasterState { enabled: HashSet::new(), viewport: (0, 0, width, height), scissor: (0, 0, width, height),
+            clear_color: [0.0; 4], clear_depth: 1.0, blend_functions: [consts::ONE, consts::ZERO, consts::ONE, consts::ZERO],
+            blend_equations: [consts::FUNC_ADD, consts::FUNC_ADD], blend_color: [0.0; 4], color_mask: [true; 4],
+            depth_func: consts::LESS, depth_mask: true, cull_face: consts::BACK, polygon_offset: (0.0, 0.0) }
+    }
+}
+
+impl Backend for CpuBackend {
+    fn get_error(&self) -> u32
+    {
+        let mut state = self.state.borrow_mut();
+        if state.errors.is_empty() { consts::NO_ERROR } else { state.errors.remove(0) }
+    }
+
+    fn set_debug_messages(&self, _enable: bool)
+    {
+    }
+
+    fn take_debug_messages(&self) -> Vec<String>
+    {
+        self.state.borrow_mut().messages.drain(..).collect()
+    }
+
+    fn take_debug_output(&self) -> Vec<String>
+    {
+        Vec::new()
+    }
+
+    fn object_label(&self, _identifier: u32, _name: &u32, _label: &str)
+    {
+    }
+
+    fn push_debug_group(&self, _message: &str)
+    {
+    }
+
+    fn pop_debug_group(&self)
+    {
+    }
+
+    fn finish(&self)
+    {
+    }
+
+    fn create_shader(&self, type_: u32) -> Option<Shader>
+    {
+        let id = self.create();
+        self.state.borrow_mut().shaders.insert(id, CpuShader {type_, source: String::new(), vertex: None, fragment: None});
+        Some(id)
+    }
+
+    fn compile_shader(&self, source: &str, shader: &Shader)
+    {
+        let normalized = normalize_source(source);
+        let vertex = self.vertex_shaders.borrow().get(&normalized).cloned();
+        let fragment = self.fragment_shaders.borrow().get(&normalized).cloned();
+        if let Some(shader) = self.state.borrow_mut().shaders.get_mut(shader) {
+            shader.source = source.to_string();
+            if shader.type_ == consts::VERTEX_SHADER { shader.vertex = vertex; } else { shader.fragment = fragment; }
+        }
+    }
+
+    fn get_shader_info_log(&self, _shader: &Shader) -> Option<String>
+    {
+        None
+    }
+
+    fn delete_shader(&self, shader: Option<&Shader>)
+    {
+        if let Some(shader) = shader {
+            self.state.borrow_mut().shaders.remove(shader);
+        }
+    }
+
+    fn attach_shader(&self, program: &Program, shader: &Shader)
+    {
+        if let Some(program) = self.state.borrow_mut().programs.get_mut(program) {
+            program.shaders.push(*shader);
+        }
+    }
+
+    fn detach_shader(&self, program: &Program, shader: &Shader)
+    {
+        if let Some(program) = self.state.borrow_mut().programs.get_mut(program) {
+            program.shaders.retain(|s| s != shader);
+        }
+    }
+
+    fn get_program_parameter(&self, program: &Program, pname: u32) -> u32
+    {
+        let state = self.state.borrow();
+        let program = match state.programs.get(program) { Some(program) => program, None => return 0 };
+        match pname {
+            consts::ACTIVE_ATTRIBUTES => program.attributes.len() as u32,
+            consts::ACTIVE_UNIFORMS => program.uniforms.len() as u32,
+            consts::LINK_STATUS => 1,
+            _ => 0
+        }
+    }
+
+    fn get_active_attrib(&self, program: &Program, index: u32) -> ActiveInfo
+    {
+        let name = self.state.borrow().programs.get(program).and_then(|p| p.attributes.get(index as usize).cloned()).unwrap_or_default();
+        ActiveInfo::new(1, 0, name)
+    }
+
+    fn get_active_uniform(&self, program: &Program, index: u32) -> ActiveInfo
+    {
+        let name = self.state.borrow().programs.get(program).and_then(|p| p.uniforms.get(index as usize).cloned()).unwrap_or_default();
+        ActiveInfo::new(1, 0, name)
+    }
+
+    fn create_buffer(&self) -> Option<Buffer>
+    {
+        let id = self.create();
+        self.state.borrow_mut().buffers.insert(id, Vec::new());
+        Some(id)
+    }
+
+    fn delete_buffer(&self, buffer: &Buffer)
+    {
+        let mut state = self.state.borrow_mut();
+        state.buffers.remove(buffer);
+        state.bound_buffers.retain(|_, id| id != buffer);
+    }
+
+    fn bind_buffer_base(&self, target: u32, index: u32, buffer: &Buffer)
+    {
+        let mut state = self.state.borrow_mut();
+        state.bound_buffers.insert(target, *buffer);
+        if target == consts::UNIFORM_BUFFER {
+            state.uniform_buffer_bindings.insert(index, *buffer);
+        }
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: &Buffer)
+    {
+        self.state.borrow_mut().bound_buffers.insert(target, *buffer);
+    }
+
+    fn unbind_buffer(&self, target: u32)
+    {
+        self.state.borrow_mut().bound_buffers.remove(&target);
+    }
+
+    fn get_uniform_block_index(&self, program: &Program, name: &str) -> u32
+    {
+        self.state.borrow().programs.get(program)
+            .and_then(|program| program.uniform_blocks.iter().position(|block| block == name))
+            .map(|index| index as u32).unwrap_or(consts::INVALID_INDEX)
+    }
+
+    fn uniform_block_binding(&self, program: &Program, location: u32, index: u32)
+    {
+        if let Some(program) = self.state.borrow_mut().programs.get_mut(program) {
+            program.block_bindings.insert(location, index);
+        }
+    }
+
+    fn buffer_data(&self, target: u32, size_in_bytes: u32, _usage: u32)
+    {
+        self.buffer_data_u8(target, &vec![0; size_in_bytes as usize], _usage);
+    }
+
+    fn buffer_data_u8(&self, target: u32, data: &[u8], _usage: u32)
+    {
+        let mut state = self.state.borrow_mut();
+        match state.bound_buffers.get(&target).cloned() {
+            Some(id) => { state.buffers.insert(id, data.to_vec()); },
+            None => {
+                state.errors.push(consts::INVALID_OPERATION);
+                state.messages.push(format!("No buffer is bound to target {:#x}", target));
+            }
+        }
+    }
+
+    fn buffer_data_u32(&self, target: u32, data: &[u32], usage: u32)
+    {
+        let bytes: Vec<u8> = data.iter().flat_map(|value| value.to_ne_bytes().to_vec()).collect();
+        self.buffer_data_u8(target, &bytes, usage);
+    }
+
+    fn buffer_data_f32(&self, target: u32, data: &[f32], usage: u32)
+    {
+        let bytes: Vec<u8> = data.iter().flat_map(|value| value.to_ne_bytes().to_vec()).collect();
+        self.buffer_data_u8(target, &bytes, usage);
+    }
+
+    fn create_vertex_array(&self) -> Option<VertexArrayObject>
+    {
+        Some(self.create())
+    }
+
+    fn bind_vertex_array(&self, _array: &VertexArrayObject)
+    {
+    }
+
+    fn create_program(&self) -> Program
+    {
+        let id = self.create();
+        self.state.borrow_mut().programs.insert(id, CpuProgram { shaders: Vec::new(), vertex: None, fragment: None, sources: Vec::new(),
+            attributes: Vec::new(), uniforms: Vec::new(), uniform_blocks: Vec::new(), sampler_targets: HashMap::new(), uniform_values: HashMap::new(), block_bindings: HashMap::new() });
+        id
+    }
+
+    fn link_program(&self, program: &Program) -> bool
+    {
+        let mut state = self.state.borrow_mut();
+        let shader_ids = match state.programs.get(program) { Some(program) => program.shaders.clone(), None => return false };
+        let mut attributes = Vec::new();
+        let mut uniforms = Vec::new();
+        let mut uniform_blocks = Vec::new();
+        let mut vertex = None;
+        let mut fragment = None;
+        let mut sources = Vec::new();
+        let mut sampler_targets = HashMap::new();
+        for id in shader_ids {
+            if let Some(shader) = state.shaders.get(&id) {
+                crate::gl::recording::parse_declarations(&shader.source, shader.type_ == consts::VERTEX_SHADER, &mut attributes, &mut uniforms, &mut uniform_blocks);
+                vertex = vertex.or_else(|| shader.vertex.clone());
+                fragment = fragment.or_else(|| shader.fragment.clone());
+                sampler_targets.extend(parse_sampler_targets(&shader.source));
+                sources.push(shader.source.clone());
+            }
+        }
+        let program = state.programs.get_mut(program).unwrap();
+        program.attributes = attributes;
+        program.uniforms = uniforms;
+        program.uniform_blocks = uniform_blocks;
+        program.vertex = vertex;
+        program.fragment = fragment;
+        program.sources = sources;
+        program.sampler_targets = sampler_targets;
+        true
+    }
+
+    fn get_program_info_log(&self, _program: &Program) -> Option<String>
+    {
+        None
+    }
+
+    fn use_program(&self, program: &Program)
+    {
+        self.state.borrow_mut().current_program = Some(*program);
+    }
+
+    fn unuse_program(&self)
+    {
+        self.state.borrow_mut().current_program = None;
+    }
+
+    fn delete_program(&self, program: &Program)
+    {
+        let mut state = self.state.borrow_mut();
+        state.programs.remove(program);
+        if state.current_program == Some(*program) {
+            state.current_program = None;
+        }
+    }
+
+    fn get_attrib_location(&self, program: &Program, name: &str) -> Option<AttributeLocation>
+    {
+        self.state.borrow().programs.get(program)
+            .and_then(|program| program.attributes.iter().position(|attribute| attribute == name))
+            .map(|location| location as u32)
+    }
+
+    fn enable_vertex_attrib_array(&self, location: AttributeLocation)
+    {
+        if let Some(binding) = self.state.borrow_mut().attributes.get_mut(&location) {
+            binding.enabled = true;
+        }
+    }
+
+    fn disable_vertex_attrib_array(&self, location: AttributeLocation)
+    {
+        if let Some(binding) = self.state.borrow_mut().attributes.get_mut(&location) {
+            binding.enabled = false;
+        }
+    }
+
+    fn vertex_attrib_pointer(&self, location: AttributeLocation, size: u32, _data_type: u32, _normalized: bool, stride: u32, offset: u32)
+    {
+        let mut state = self.state.borrow_mut();
+        let buffer = state.bound_buffers.get(&consts::ARRAY_BUFFER).cloned().unwrap_or(0);
+        let divisor = state.attributes.get(&location).map(|binding| binding.divisor).unwrap_or(0);
+        state.attributes.insert(location, AttributeBinding {buffer, size, stride, offset, divisor, enabled: true});
+    }
+
+    fn vertex_attrib_divisor(&self, location: AttributeLocation, divisor: u32)
+    {
+        if let Some(binding) = self.state.borrow_mut().attributes.get_mut(&location) {
+            binding.divisor = divisor;
+        }
+    }
+
+    fn get_uniform_location(&self, program: &Program, name: &str) -> Option<UniformLocation>
+    {
+        self.state.borrow().programs.get(program)
+            .and_then(|program| program.uniforms.iter().position(|uniform| uniform == name))
+            .map(|location| location as u32)
+    }
+
+    fn uniform1i(&self, location: &UniformLocation, data: i32)
+    {
+        self.set_uniform(location, &[data as f32]);
+    }
+
+    fn uniform1f(&self, location: &UniformLocation, data: f32)
+    {
+        self.set_uniform(location, &[data]);
+    }
+
+    fn uniform2fv(&self, location: &UniformLocation, data: &[f32])
+    {
+        self.set_uniform(location, data);
+    }
+
+    fn uniform3fv(&self, location: &UniformLocation, data: &[f32])
+    {
+        self.set_uniform(location, data);
+    }
+
+    fn uniform4fv(&self, location: &UniformLocation, data: &[f32])
+    {
+        self.set_uniform(location, data);
+    }
+
+    fn uniform_matrix2fv(&self, location: &UniformLocation, data: &[f32])
+    {
+        self.set_uniform(location, data);
+    }
+
+    fn uniform_matrix3fv(&self, location: &UniformLocation, data: &[f32])
+    {
+        self.set_uniform(location, data);
+    }
+
+    fn uniform_matrix4fv(&self, location: &UniformLocation, data: &[f32])
+    {
+        self.set_uniform(location, data);
+    }
+
+    fn draw_buffers(&self, draw_buffers: &[u32])
+    {
+        let mut state = self.state.borrow_mut();
+        let id = state.draw_framebuffer;
+        if let Some(framebuffer) = state.framebuffers.get_mut(&id) {
+            framebuffer.draw_buffers = Some(draw_buffers.to_vec());
+        }
+    }
+
+    fn read_buffer(&self, source: u32)
+    {
+        let mut state = self.state.borrow_mut();
+        let id = state.read_framebuffer;
+        if let Some(framebuffer) = state.framebuffers.get_mut(&id) {
+            framebuffer.read_buffer = Some(source);
+        }
+    }
+
+    fn create_framebuffer(&self) -> Option<Framebuffer>
+    {
+        let id = self.create();
+        self.state.borrow_mut().framebuffers.insert(id, CpuFramebuffer::default());
+        Some(id)
+    }
+
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<&Framebuffer>)
+    {
+        let mut state = self.state.borrow_mut();
+        let id = framebuffer.cloned().unwrap_or(SCREEN);
+        if target == consts::FRAMEBUFFER || target == consts::DRAW_FRAMEBUFFER {
+            state.draw_framebuffer = id;
+        }
+        if target == consts::FRAMEBUFFER || target == consts::READ_FRAMEBUFFER {
+            state.read_framebuffer = id;
+        }
+    }
+
+    fn delete_framebuffer(&self, framebuffer: Option<&Framebuffer>)
+    {
+        if let Some(framebuffer) = framebuffer {
+            let mut state = self.state.borrow_mut();
+            state.framebuffers.remove(framebuffer);
+            if state.draw_framebuffer == *framebuffer { state.draw_framebuffer = SCREEN; }
+            if state.read_framebuffer == *framebuffer { state.read_framebuffer = SCREEN; }
+        }
+    }
+
+    fn create_renderbuffer(&self) -> Option<Renderbuffer>
+    {
+        Some(self.create())
+    }
+
+    fn bind_renderbuffer(&self, _target: u32, renderbuffer: Option<&Renderbuffer>)
+    {
+        self.state.borrow_mut().bound_renderbuffer = renderbuffer.cloned();
+    }
+
+    fn renderbuffer_storage_multisample(&self, _target: u32, _samples: u32, internalformat: u32, width: u32, height: u32)
+    {
+        let mut state = self.state.borrow_mut();
+        if let Some(id) = state.bound_renderbuffer {
+            state.images.insert(id, CpuImage::new(consts::RENDERBUFFER, internalformat, width as usize, height as usize, 1, 1));
+        }
+    }
+
+    fn framebuffer_renderbuffer(&self, target: u32, attachment: u32, _renderbuffertarget: u32, renderbuffer: Option<&Renderbuffer>)
+    {
+        self.attach(target, attachment, renderbuffer.map(|id| (*id, 0, 0)));
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer: &Renderbuffer)
+    {
+        self.state.borrow_mut().images.remove(renderbuffer);
+    }
+
+    fn check_framebuffer_status(&self) -> Result<(), String>
+    {
+        let state = self.state.borrow();
+        let framebuffer = &state.framebuffers[&state.draw_framebuffer];
+        if framebuffer.attachments.is_empty() {
+            return Err("FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT".to_string());
+        }
+        if framebuffer.attachments.values().any(|(id, _, _)| !state.images.contains_key(id)) {
+            return Err("FRAMEBUFFER_INCOMPLETE_ATTACHMENT".to_string());
+        }
+        Ok(())
+    }
+
+    fn blit_framebuffer(&self, src_x0: u32, src_y0: u32, src_x1: u32, src_y1: u32, dst_x0: u32, dst_y0: u32, dst_x1: u32, dst_y1: u32, mask: u32, filter: u32)
+    {
+        rasterizer::blit(&mut self.state.borrow_mut(), (src_x0, src_y0, src_x1, src_y1), (dst_x0, dst_y0, dst_x1, dst_y1), mask, filter);
+    }
+
+    fn pixel_storei(&self, _pname: u32, _param: i32)
+    {
+    }
+
+    fn scissor(&self, x: i32, y: i32, width: usize, height: usize)
+    {
+        self.state.borrow_mut().raster.scissor = (x, y, width, height);
+    }
+
+    fn viewport(&self, x: i32, y: i32, width: usize, height: usize)
+    {
+        self.state.borrow_mut().raster.viewport = (x, y, width, height);
+    }
+
+    fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32)
+    {
+        self.state.borrow_mut().raster.clear_color = [red, green, blue, alpha];
+    }
+
+    fn clear_depth(&self, depth: f32)
+    {
+        self.state.borrow_mut().raster.clear_depth = depth;
+    }
+
+    fn clear_stencil(&self, _stencil: i32)
+    {
+    }
+
+    fn clear(&self, mask: u32)
+    {
+        rasterizer::clear(&mut self.state.borrow_mut(), mask);
+    }
+
+    fn enable(&self, cap: u32)
+    {
+        self.state.borrow_mut().raster.enabled.insert(cap);
+    }
+
+    fn disable(&self, cap: u32)
+    {
+        self.state.borrow_mut().raster.enabled.remove(&cap);
+    }
+
+    fn blend_func(&self, sfactor: u32, dfactor: u32)
+    {
+        self.blend_func_separate(sfactor, dfactor, sfactor, dfactor);
+    }
+
+    fn blend_func_separate(&self, src_rgb: u32, dst_rgb: u32, src_alpha: u32, dst_alpha: u32)
+    {
+        self.state.borrow_mut().raster.blend_functions = [src_rgb, dst_rgb, src_alpha, dst_alpha];
+    }
+
+    fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32)
+    {
+        self.state.borrow_mut().raster.blend_equations = [mode_rgb, mode_alpha];
+    }
+
+    fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32)
+    {
+        self.state.borrow_mut().raster.blend_color = [red, green, blue, alpha];
+    }
+
+    fn polygon_offset(&self, factor: f32, units: f32)
+    {
+        self.state.borrow_mut().raster.polygon_offset = (factor, units);
+    }
+
+    fn line_width(&self, _width: f32)
+    {
+    }
+
+    fn polygon_mode(&self, _face: u32, _mode: u32)
+    {
+    }
+
+    fn cull_face(&self, mode: u32)
+    {
+        self.state.borrow_mut().raster.cull_face = mode;
+    }
+
+    fn depth_func(&self, func: u32)
+    {
+        self.state.borrow_mut().raster.depth_func = func;
+    }
+
+    fn depth_mask(&self, flag: bool)
+    {
+        self.state.borrow_mut().raster.depth_mask = flag;
+    }
+
+    fn stencil_func(&self, _func: u32, _reference: i32, _mask: u32)
+    {
+    }
+
+    fn stencil_op(&self, _stencil_fail: u32, _depth_fail: u32, _pass: u32)
+    {
+    }
+
+    fn stencil_mask(&self, _mask: u32)
+    {
+    }
+
+    fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool)
+    {
+        self.state.borrow_mut().raster.color_mask = [red, green, blue, alpha];
+    }
+
+    fn create_texture(&self) -> Option<Texture>
+    {
+        Some(self.create())
+    }
+
+    fn active_texture(&self, texture: u32)
+    {
+        self.state.borrow_mut().active_texture = texture - consts::TEXTURE0;
+    }
+
+    fn bind_texture(&self, target: u32, texture: &Texture)
+    {
+        let mut state = self.state.borrow_mut();
+        let unit = state.active_texture;
+        state.bound_textures.insert((unit, target), *texture);
+    }
+
+    fn generate_mipmap(&self, target: u32)
+    {
+        self.with_image(target, &|image, _| image.generate_mip_maps());
+    }
+
+    fn tex_storage_2d(&self, target: u32, levels: u32, internalformat: u32, width: u32, height: u32)
+    {
+        let layers = if target == consts::TEXTURE_CUBE_MAP { 6 } else { 1 };
+        self.tex_storage(target, CpuImage::new(target, internalformat, width as usize, height as usize, layers, levels as usize));
+    }
+
+    fn tex_storage_3d(&self, target: u32, levels: u32, internalformat: u32, width: u32, height: u32, depth: u32)
+    {
+        self.tex_storage(target, CpuImage::new(target, internalformat, width as usize, height as usize, depth as usize, levels as usize));
+    }
+
+    fn tex_image_2d(&self, target: u32, _level: u32, internalformat: u32, width: u32, height: u32, _border: u32, _format: u32, _data_type: u32)
+    {
+        self.tex_storage_2d(target, 1, internalformat, width, height);
+    }
+
+    fn tex_image_2d_with_u8_data(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, border: u32, format: u32, data_type: u32, pixels: &[u8])
+    {
+        self.tex_image_2d(target, level, internalformat, width, height, border, format, data_type);
+        self.tex_sub_image_2d_with_u8_data(target, level, 0, 0, width, height, format, data_type, pixels);
+    }
+
+    fn tex_sub_image_2d_with_u8_data(&self, target: u32, level: u32, x_offset: u32, y_offset: u32, width: u32, height: u32, format: u32, _data_type: u32, pixels: &[u8])
+    {
+        let values: Vec<f32> = pixels.iter().map(|value| *value as f32 / 255.0).collect();
+        self.tex_sub_image(target, level, x_offset, y_offset, width, height, format, &values);
+    }
+
+    fn tex_image_2d_with_f32_data(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, border: u32, format: u32, data_type: u32, pixels: &[f32])
+    {
+        self.tex_image_2d(target, level, internalformat, width, height, border, format, data_type);
+        self.tex_sub_image_2d_with_f32_data(target, level, 0, 0, width, height, format, data_type, pixels);
+    }
+
+    fn tex_sub_image_2d_with_f32_data(&self, target: u32, level: u32, x_offset: u32, y_offset: u32, width: u32, height: u32, format: u32, _data_type: u32, pixels: &[f32])
+    {
+        self.tex_sub_image(target, level, x_offset, y_offset, width, height, format, pixels);
+    }
+
+    fn tex_image_3d(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, depth: u32, _format: u32, _data_type: u32)
+    {
+        self.tex_storage_3d(target, level + 1, internalformat, width, height, depth);
+    }
+
+    fn tex_parameteri(&self, target: u32, pname: u32, param: i32)
+    {
+        self.with_image(target, &|image, _| image.parameters.set(pname, param as u32));
+    }
+
+    fn delete_texture(&self, texture: &Texture)
+    {
+        let mut state = self.state.borrow_mut();
+        state.images.remove(texture);
+        state.bound_textures.retain(|_, id| id != texture);
+    }
+
+    fn create_sampler(&self) -> Option<Sampler>
+    {
+        let id = self.create();
+        self.state.borrow_mut().samplers.insert(id, SamplerParameters::default());
+        Some(id)
+    }
+
+    fn bind_sampler(&self, unit: u32, sampler: Option<&Sampler>)
+    {
+        let mut state = self.state.borrow_mut();
+        match sampler {
+            Some(id) => { state.sampler_units.insert(unit, *id); },
+            None => { state.sampler_units.remove(&unit); }
+        }
+    }
+
+    fn sampler_parameteri(&self, sampler: &Sampler, pname: u32, param: i32)
+    {
+        if let Some(parameters) = self.state.borrow_mut().samplers.get_mut(sampler) {
+            parameters.set(pname, param as u32);
+        }
+    }
+
+    fn sampler_parameterf(&self, _sampler: &Sampler, _pname: u32, _param: f32)
+    {
+    }
+
+    fn sampler_parameterfv(&self, _sampler: &Sampler, _pname: u32, _params: &[f32])
+    {
+    }
+
+    fn delete_sampler(&self, sampler: &Sampler)
+    {
+        let mut state = self.state.borrow_mut();
+        state.samplers.remove(sampler);
+        state.sampler_units.retain(|_, id| id != sampler);
+    }
+
+    fn framebuffer_texture_2d(&self, target: u32, attachment: u32, textarget: u32, texture: &Texture, level: u32)
+    {
+        self.attach(target, attachment, Some((*texture, level as usize, cube_map_face(textarget).unwrap_or(0))));
+    }
+
+    fn framebuffer_texture_layer(&self, target: u32, attachment: u32, texture: &Texture, level: u32, layer: u32)
+    {
+        self.attach(target, attachment, Some((*texture, level as usize, layer as usize)));
+    }
+
+    fn draw_arrays(&self, mode: u32, first: u32, count: u32)
+    {
+        self.draw_arrays_instanced(mode, first, count, 1);
+    }
+
+    fn draw_arrays_instanced(&self, mode: u32, first: u32, count: u32, instance_count: u32)
+    {
+        let indices: Vec<u32> = (first..first + count).collect();
+        self.draw(mode, &indices, instance_count);
+    }
+
+    fn draw_elements(&self, mode: u32, count: u32, data_type: u32, offset: u32)
+    {
+        self.draw_elements_instanced(mode, count, data_type, offset, 1);
+    }
+
+    fn draw_elements_instanced(&self, mode: u32, count: u32, _data_type: u32, offset: u32, instance_count: u32)
+    {
+        let indices = {
+            let state = self.state.borrow();
+            let buffer = state.bound_buffers.get(&consts::ELEMENT_ARRAY_BUFFER).and_then(|id| state.buffers.get(id));
+            match buffer {
+                Some(bytes) => (0..count as usize).map(|i| read_u32(bytes, (offset as usize + i) * 4)).collect::<Vec<u32>>(),
+                None => Vec::new()
+            }
+        };
+        if indices.is_empty() && count > 0 {
+            self.error("Drawing elements without an element buffer".to_string());
+            return;
+        }
+        self.draw(mode, &indices, instance_count);
+    }
+
+    // The CPU backend reports version 3.3, so indirect draws are not used by this crate
+    fn draw_elements_indirect(&self, _mode: u32, _data_type: u32, _offset: u32)
+    {
+        self.error("Indirect draws are not supported by the CPU backend".to_string());
+    }
+
+    fn multi_draw_elements_indirect(&self, _mode: u32, _data_type: u32, _offset: u32, _draw_count: u32, _stride: u32)
+    {
+        self.error("Indirect draws are not supported by the CPU backend".to_string());
+    }
+
+    fn read_pixels(&self, _x: u32, _y: u32, _width: u32, _height: u32, _format: u32, _data_type: u32)
+    {
+    }
+
+    fn read_pixels_with_u8_data(&self, x: u32, y: u32, width: u32, height: u32, format: u32, _data_type: u32, dst_data: &mut [u8])
+    {
+        let values = rasterizer::read_pixels(&self.state.borrow(), x as usize, y as usize, width as usize, height as usize, format);
+        for (dst, value) in dst_data.iter_mut().zip(values.iter()) {
+            *dst = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+    }
+
+    fn read_pixels_with_f32_data(&self, x: u32, y: u32, width: u32, height: u32, format: u32, _data_type: u32, dst_data: &mut [f32])
+    {
+        let values = rasterizer::read_pixels(&self.state.borrow(), x as usize, y as usize, width as usize, height as usize, format);
+        for (dst, value) in dst_data.iter_mut().zip(values.iter()) {
+            *dst = *value;
+        }
+    }
+
+    fn get_parameter_f32(&self, pname: u32) -> f32
+    {
+        match pname {
+            consts::MAX_SAMPLES => 1.0,
+            consts::MAX_TEXTURE_SIZE | consts::MAX_CUBE_MAP_TEXTURE_SIZE => 8192.0,
+            consts::MAX_ARRAY_TEXTURE_LAYERS => 2048.0,
+            consts::MAX_DRAW_BUFFERS | consts::MAX_COLOR_ATTACHMENTS => 8.0,
+            _ => 0.0
+        }
+    }
+
+    fn get_parameter_f32v(&self, _pname: u32, _values: &mut [f32])
+    {
+    }
+
+    fn get_parameter_i32v(&self, _pname: u32, _values: &mut [i32])
+    {
+    }
+
+    fn get_parameter_indexed_i32(&self, _pname: u32, _index: u32) -> i32
+    {
+        0
+    }
+
+    fn is_enabled(&self, cap: u32) -> bool
+    {
+        self.state.borrow().raster.enabled.contains(&cap)
+    }
+
+    fn get_string(&self, pname: u32) -> String
+    {
+        match pname {
+            consts::VERSION => "3.3 CpuBackend".to_string(),
+            consts::SHADING_LANGUAGE_VERSION => "3.30".to_string(),
+            consts::RENDERER => "CpuBackend".to_string(),
+            _ => String::new()
+        }
+    }
+
+    fn get_extensions(&self) -> Vec<String>
+    {
+        Vec::new()
+    }
+
+    fn supports_extension(&self, _name: &str) -> bool
+    {
+        false
+    }
+
+    fn flush(&self)
+    {
+    }
+
+    fn fence_sync(&self) -> Sync
+    {
+        self.create() as usize as Sync
+    }
+
+    fn client_wait_sync(&self, _sync: &Sync, _flags: u32, _timeout: u32) -> u32
+    {
+        consts::ALREADY_SIGNALED
+    }
+
+    fn delete_sync(&self, _sync: &Sync)
+    {
+    }
+}
+
+impl CpuBackend {
+    fn tex_storage(&self, target: u32, mut image: CpuImage)
+    {
+        if let Some(id) = self.image_at(target) {
+            let mut state = self.state.borrow_mut();
+            if let Some(previous) = state.images.get(&id) {
+                image.parameters = previous.parameters;
+            }
+            state.images.insert(id, image);
+        }
+        else {
+            self.error(format!("No texture is bound to target {:#x}", target));
+        }
+    }
+
+    fn tex_sub_image(&self, target: u32, level: u32, x_offset: u32, y_offset: u32, width: u32, height: u32, format: u32, values: &[f32])
+    {
+        let channels = channel_count(format);
+        self.with_image(target, &|image, layer| {
+            for y in 0..height as usize {
+                for x in 0..width as usize {
+                    let i = (y * width as usize + x) * channels;
+                    let mut value = [0.0, 0.0, 0.0, 1.0];
+                    value[..channels].copy_from_slice(&values[i..i + channels]);
+                    image.set(level as usize, layer, x_offset as usize + x, y_offset as usize + y, value);
+                }
+            }
+        });
+    }
+
+    fn attach(&self, target: u32, attachment: u32, image: Option<(u32, usize, usize)>)
+    {
+        let mut state = self.state.borrow_mut();
+        let id = if target == consts::READ_FRAMEBUFFER { state.read_framebuffer } else { state.draw_framebuffer };
+        if let Some(framebuffer) = state.framebuffers.get_mut(&id) {
+            match image {
+                Some(image) => { framebuffer.attachments.insert(attachment, image); },
+                None => { framebuffer.attachments.remove(&attachment); }
+            }
+        }
+    }
+
+    fn draw(&self, mode: u32, indices: &[u32], instance_count: u32)
+    {
+        if mode != consts::TRIANGLES {
+            self.error(format!("Only triangles can be drawn, not mode {:#x}", mode));
+            return;
+        }
+        let result = rasterizer::draw(&mut self.state.borrow_mut(), indices, instance_count);
+        if let Err(message) = result {
+            self.error(message);
+        }
+    }
+}
+
+fn parse_sampler_targets(source: &str) -> HashMap<String, u32>
+{
+    let source = crate::gl::recording::strip_comments(source);
+    let tokens: Vec<&str> = source.split(|c: char| c.is_whitespace() || c == ';').filter(|token| !token.is_empty()).collect();
+    tokens.windows(3).filter(|words| words[0] == "uniform").filter_map(|words| {
+        let target = match words[1] {
+            "sampler2D" | "sampler2DShadow" => consts::TEXTURE_2D,
+            "sampler2DArray" | "sampler2DArrayShadow" => consts::TEXTURE_2D_ARRAY,
+            "samplerCube" | "samplerCubeShadow" => consts::TEXTURE_CUBE_MAP,
+            "sampler3D" => consts::TEXTURE_3D,
+            _ => return None
+        };
+        Some((words[2].to_string(), target))
+    }).collect()
+}
+
+fn normalize_source(source: &str) -> String
+{
+    source.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn level_size(size: usize, level: usize) -> usize
+{
+    (size >> level).max(1)
+}
+
+fn texture_target(target: u32) -> u32
+{
+    if cube_map_face(target).is_some() { consts::TEXTURE_CUBE_MAP } else { target }
+}
+
+fn cube_map_face(target: u32) -> Option<usize>
+{
+    if (consts::TEXTURE_CUBE_MAP_POSITIVE_X..=consts::TEXTURE_CUBE_MAP_NEGATIVE_Z).contains(&target) {
+        Some((target - consts::TEXTURE_CUBE_MAP_POSITIVE_X) as usize)
+    }
+    else {
+        None
+    }
+}
+
+/// The face and the texture coordinates on the face in the direction, as defined by the OpenGL specification.
+fn cube_map_uv(direction: Vec3) -> (Vec2, usize)
+{
+    let (x, y, z) = (direction.x, direction.y, direction.z);
+    let (face, sc, tc, ma) = if x.abs() >= y.abs() && x.abs() >= z.abs() {
+        if x > 0.0 { (0, -z, -y, x) } else { (1, z, -y, -x) }
+    }
+    else if y.abs() >= z.abs() {
+        if y > 0.0 { (2, x, z, y) } else { (3, x, -z, -y) }
+    }
+    else {
+        if z > 0.0 { (4, x, -y, z) } else { (5, -x, -y, -z) }
+    };
+    (vec2(0.5 * (sc / ma + 1.0), 0.5 * (tc / ma + 1.0)), face)
+}
+
+fn wrap(mode: u32, coordinate: i32, size: usize) -> usize
+{
+    let size = size as i32;
+    let wrapped = match mode {
+        consts::REPEAT => coordinate.rem_euclid(size),
+        consts::MIRRORED_REPEAT => {
+            let period = coordinate.rem_euclid(2 * size);
+            if period < size { period } else { 2 * size - 1 - period }
+        },
+        _ => coordinate.max(0).min(size - 1)
+    };
+    wrapped as usize
+}
+
+fn compare(func: u32, value: f32, reference: f32) -> bool
+{
+    match func {
+        consts::NEVER => false,
+        consts::LESS => value < reference,
+        consts::EQUAL => value == reference,
+        consts::LEQUAL => value <= reference,
+        consts::GREATER => value > reference,
+        consts::NOTEQUAL => value != reference,
+        consts::GEQUAL => value >= reference,
+        _ => true
+    }
+}
+
+fn is_depth_format(format: u32) -> bool
+{
+    matches!(format, consts::DEPTH_COMPONENT16 | consts::DEPTH_COMPONENT24 | consts::DEPTH_COMPONENT32F
+        | consts::DEPTH24_STENCIL8 | consts::DEPTH32F_STENCIL8)
+}
+
+fn channel_count(format: u32) -> usize
+{
+    match format {
+        consts::RED | consts::DEPTH_COMPONENT => 1,
+        consts::RG => 2,
+        consts::RGB => 3,
+        _ => 4
+    }
+}
+
+fn quantize(format: u32, value: [f32; 4]) -> [f32; 4]
+{
+    let round = |v: f32, levels: f32| (v.clamp(0.0, 1.0) * levels).round() / levels;
+    match format {
+        consts::R8 => [round(value[0], 255.0), 0.0, 0.0, 1.0],
+        consts::RGB8 => [round(value[0], 255.0), round(value[1], 255.0), round(value[2], 255.0), 1.0],
+        consts::RGBA8 => [round(value[0], 255.0), round(value[1], 255.0), round(value[2], 255.0), round(value[3], 255.0)],
+        consts::RGBA4 => [round(value[0], 15.0), round(value[1], 15.0), round(value[2], 15.0), round(value[3], 15.0)],
+        consts::R16F | consts::R32F => [value[0], 0.0, 0.0, 1.0],
+        consts::RGB16F | consts::RGB32F => [value[0], value[1], value[2], 1.0],
+        _ if is_depth_format(format) => [value[0].clamp(0.0, 1.0), 0.0, 0.0, 1.0],
+        _ => value
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32
+{
+    let mut value = [0; 4];
+    value.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_ne_bytes(value)
+}
+
+fn read_f32(bytes: &[u8], offset: usize) -> f32
+{
+    f32::from_bits(read_u32(bytes, offset))
+}
+
+fn mat4_from(values: &[f32]) -> Mat4
+{
+    if values.len() < 16 {
+        return Mat4::identity();
+    }
+    Mat4::new(values[0], values[1], values[2], values[3], values[4], values[5], values[6], values[7],
+              values[8], values[9], values[10], values[11], values[12], values[13], values[14], values[15])
+}
+
+#[cfg(all(test, not(feature = "no-renderer")))]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn built_in_shaders_are_implemented()
+    {
+        let backend = CpuBackend::new(32, 32);
+        let gl = Glstruct::new(Box::new(backend.clone()));
+        let mut pipeline = DeferredPipeline::new(&gl).unwrap();
+        let camera = Camera::new_perspective(&gl, vec3(0.0, 0.0, 5.0), vec3(0.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), degrees(45.0), 1.0, 0.1, 10.0);
+        let mesh = Mesh::new(&gl, &[0, 1, 2], &[-1.0, -1.0, 0.0, 1.0, -1.0, 0.0, 0.0, 1.0, 0.0], &[0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0]).unwrap();
+        let ambient_light = AmbientLight::new(&gl, 0.2, &vec3(1.0, 1.0, 1.0)).unwrap();
+        let directional_light = DirectionalLight::new(&gl, 0.5, &vec3(1.0, 1.0, 1.0), &vec3(0.0, 0.0, -1.0)).unwrap();
+        let spot_light = SpotLight::new(&gl, 0.5, &vec3(1.0, 1.0, 1.0), &vec3(0.0, 0.0, 2.0), &vec3(0.0, 0.0, -1.0), 25.0, 0.1, 0.001, 0.0001).unwrap();
+        let point_light = PointLight::new(&gl, 0.5, &vec3(1.0, 1.0, 1.0), &vec3(0.0, 0.0, 2.0), 0.5, 0.05, 0.005).unwrap();
+
+        pipeline.geometry_pass(32, 32, &|| { mesh.render(&Mat4::identity(), &camera); }).unwrap();
+        Screen::write(&gl, 0, 0, 32, 32, Some(&vec4(0.0, 0.0, 0.0, 1.0)), Some(1.0), &|| {
+            pipeline.light_pass(&camera, Some(&ambient_light), &[&directional_light], &[&spot_light], &[&point_light]).unwrap();
+        }).unwrap();
+
+        let state = backend.state.borrow();
+        assert!(!state.programs.is_empty());
+        for program in state.programs.values() {
+            assert!(program.vertex.is_some(), "{}", rasterizer::missing_shader("vertex", &program.sources));
+            assert!(program.fragment.is_some(), "{}", rasterizer::missing_shader("fragment", &program.sources));
+        }
+    }
+}
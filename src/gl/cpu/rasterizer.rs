@@ -0,0 +1,437 @@
+use super::*;
+
+/// A vertex after the vertex shader, with the position in clip space.
+#[derive(Clone)]
+struct ClipVertex {
+    position: Vec4,
+    varyings: Vec<f32>
+}
+
+pub(super) fn draw(state: &mut CpuState, indices: &[u32], instance_count: u32) -> Result<(), String>
+{
+    let framebuffer = state.framebuffers.get(&state.draw_framebuffer).ok_or_else(|| "The bound framebuffer does not exist".to_string())?;
+    let color_attachments: Vec<Option<(u32, usize, usize)>> = framebuffer.draw_buffers().iter()
+        .map(|draw_buffer| framebuffer.attachments.get(&attachment_point(*draw_buffer)).cloned())
+        .collect();
+    let depth_attachment = framebuffer.depth_attachment();
+
+    // The attached images are moved out of the state while drawing, so that the textures can be sampled at the same time
+    let mut targets = HashMap::new();
+    for (id, _, _) in color_attachments.iter().flatten().chain(depth_attachment.iter()) {
+        if let Some(image) = state.images.remove(id) {
+            targets.insert(*id, image);
+        }
+    }
+    let result = rasterize(state, &mut targets, color_attachments, depth_attachment, indices, instance_count);
+    state.images.extend(targets);
+    result
+}
+
+fn rasterize(state: &CpuState, targets: &mut HashMap<u32, CpuImage>, color_attachments: Vec<Option<(u32, usize, usize)>>,
+             depth_attachment: Option<(u32, usize, usize)>, indices: &[u32], instance_count: u32) -> Result<(), String>
+{
+    let program = state.current_program.and_then(|id| state.programs.get(&id)).ok_or_else(|| "Drawing without a program".to_string())?;
+    let vertex_shader = program.vertex.clone().ok_or_else(|| missing_shader("vertex", &program.sources))?;
+    let fragment_shader = program.fragment.clone().ok_or_else(|| missing_shader("fragment", &program.sources))?;
+    let context = ShaderContext { program, blocks: uniform_blocks(state, program), images: &state.images,
+        bound_textures: &state.bound_textures, sampler_units: &state.sampler_units, samplers: &state.samplers };
+    let attributes: Vec<Option<AttributeBinding>> = (0..program.attributes.len())
+        .map(|location| state.attributes.get(&(location as u32)).filter(|binding| binding.enabled).cloned())
+        .collect();
+
+    let (width, height) = targets.values().map(|image| (image.width, image.height))
+        .fold((usize::MAX, usize::MAX), |(w, h), (width, height)| (w.min(width), h.min(height)));
+    if targets.is_empty() {
+        return Ok(());
+    }
+    let raster = &state.raster;
+    let (x, y, w, h) = raster.viewport;
+    let mut region = (x, y, x + w as i32, y + h as i32);
+    if raster.enabled.contains(&consts::SCISSOR_TEST) {
+        region = intersect(region, raster.scissor);
+    }
+    let region = intersect(region, (0, 0, width, height));
+
+    let mut pipeline = Pipeline {context: &context, fragment_shader, raster, targets, color_attachments, depth_attachment, region};
+    for instance in 0..instance_count {
+        let mut cache: HashMap<u32, ClipVertex> = HashMap::new();
+        for triangle in indices.chunks_exact(3) {
+            let vertices: Vec<ClipVertex> = triangle.iter().map(|index| {
+                cache.entry(*index).or_insert_with(|| {
+                    let values: Vec<[f32; 4]> = attributes.iter().map(|binding| fetch(&state.buffers, binding, *index, instance)).collect();
+                    let input = VertexInput {names: &program.attributes, values: &values, vertex_id: *index, instance_id: instance};
+                    let mut varyings = Vec::new();
+                    let position = vertex_shader(&context, &input, &mut varyings);
+                    ClipVertex {position, varyings}
+                }).clone()
+            }).collect();
+            for clipped in clip_near(vertices) {
+                pipeline.triangle(&clipped);
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Pipeline<'a, 'b> {
+    context: &'a ShaderContext<'a>,
+    fragment_shader: FragmentShader,
+    raster: &'a RasterState,
+    targets: &'b mut HashMap<u32, CpuImage>,
+    color_attachments: Vec<Option<(u32, usize, usize)>>,
+    depth_attachment: Option<(u32, usize, usize)>,
+    region: (i32, i32, i32, i32)
+}
+
+impl<'a, 'b> Pipeline<'a, 'b> {
+    fn triangle(&mut self, vertices: &[ClipVertex; 3])
+    {
+        if vertices.iter().any(|vertex| vertex.position.w <= 0.0) {
+            return;
+        }
+        let (vx, vy, vw, vh) = self.raster.viewport;
+        let window: Vec<Vec3> = vertices.iter().map(|vertex| {
+            let ndc = vertex.position.truncate() / vertex.position.w;
+            vec3(vx as f32 + (ndc.x + 1.0) * 0.5 * vw as f32, vy as f32 + (ndc.y + 1.0) * 0.5 * vh as f32, (ndc.z + 1.0) * 0.5)
+        }).collect();
+        let area = edge(window[0], window[1], window[2]);
+        if area == 0.0 || !area.is_finite() {
+            return;
+        }
+        let front_facing = area > 0.0;
+        if self.raster.enabled.contains(&consts::CULL_FACE) {
+            let culled = match self.raster.cull_face {
+                consts::FRONT => front_facing,
+                consts::BACK => !front_facing,
+                _ => true
+            };
+            if culled {
+                return;
+            }
+        }
+
+        // Reorder the vertices to counter clockwise, so that the edge functions are positive inside the triangle
+        let order = if front_facing { [0, 1, 2] } else { [0, 2, 1] };
+        let p = [window[order[0]], window[order[1]], window[order[2]]];
+        let v = [&vertices[order[0]], &vertices[order[1]], &vertices[order[2]]];
+        let area = area.abs();
+        let inverse_w = [1.0 / v[0].position.w, 1.0 / v[1].position.w, 1.0 / v[2].position.w];
+
+        let offset = if self.raster.enabled.contains(&consts::POLYGON_OFFSET_FILL) {
+            let dzdx = ((p[1].z - p[0].z) * (p[2].y - p[0].y) - (p[2].z - p[0].z) * (p[1].y - p[0].y)) / area;
+            let dzdy = ((p[2].z - p[0].z) * (p[1].x - p[0].x) - (p[1].z - p[0].z) * (p[2].x - p[0].x)) / area;
+            let (factor, units) = self.raster.polygon_offset;
+            factor * dzdx.abs().max(dzdy.abs()) + units / (1 << 24) as f32
+        } else { 0.0 };
+
+        let (x0, y0, x1, y1) = self.region;
+        let min_x = (p.iter().map(|p| p.x).fold(f32::MAX, f32::min).floor() as i32).max(x0);
+        let max_x = (p.iter().map(|p| p.x).fold(f32::MIN, f32::max).ceil() as i32).min(x1);
+        let min_y = (p.iter().map(|p| p.y).fold(f32::MAX, f32::min).floor() as i32).max(y0);
+        let max_y = (p.iter().map(|p| p.y).fold(f32::MIN, f32::max).ceil() as i32).min(y1);
+
+        let varying_count = v[0].varyings.len();
+        let mut varyings = vec![0.0; varying_count];
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let point = vec3(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+                let weights = [edge(p[1], p[2], point), edge(p[2], p[0], point), edge(p[0], p[1], point)];
+                if !inside(weights[0], p[1], p[2]) || !inside(weights[1], p[2], p[0]) || !inside(weights[2], p[0], p[1]) {
+                    continue;
+                }
+                let b = [weights[0] / area, weights[1] / area, weights[2] / area];
+                let depth = b[0] * p[0].z + b[1] * p[1].z + b[2] * p[2].z;
+                if !(0.0..=1.0).contains(&depth) {
+                    continue;
+                }
+                let one_over_w = b[0] * inverse_w[0] + b[1] * inverse_w[1] + b[2] * inverse_w[2];
+                let perspective = [b[0] * inverse_w[0] / one_over_w, b[1] * inverse_w[1] / one_over_w, b[2] * inverse_w[2] / one_over_w];
+                for (i, varying) in varyings.iter_mut().enumerate() {
+                    *varying = (0..3).map(|j| perspective[j] * v[j].varyings.get(i).cloned().unwrap_or(0.0)).sum();
+                }
+                let frag_coord = vec4(point.x, point.y, depth + offset, one_over_w);
+                self.fragment(x as usize, y as usize, frag_coord, front_facing, &varyings);
+            }
+        }
+    }
+
+    fn fragment(&mut self, x: usize, y: usize, frag_coord: Vec4, front_facing: bool, varyings: &[f32])
+    {
+        let input = FragmentInput {varyings, front_facing, frag_coord};
+        let mut output = FragmentOutput {colors: [vec4(0.0, 0.0, 0.0, 0.0); 8], depth: None};
+        if !(self.fragment_shader)(self.context, &input, &mut output) {
+            return;
+        }
+        let raster = self.raster;
+        let depth = output.depth.unwrap_or(frag_coord.z).clamp(0.0, 1.0);
+        if raster.enabled.contains(&consts::DEPTH_TEST) {
+            if let Some((id, level, layer)) = self.depth_attachment {
+                if let Some(image) = self.targets.get_mut(&id) {
+                    if !compare(raster.depth_func, depth, image.get(level, layer, x, y)[0]) {
+                        return;
+                    }
+                    if raster.depth_mask {
+                        image.set(level, layer, x, y, [depth, 0.0, 0.0, 1.0]);
+                    }
+                }
+            }
+        }
+
+        for (i, attachment) in self.color_attachments.iter().enumerate() {
+            if let Some((id, level, layer)) = attachment {
+                if let Some(image) = self.targets.get_mut(id) {
+                    let color = output.colors[i];
+                    let source = [color.x, color.y, color.z, color.w];
+                    let destination = image.get(*level, *layer, x, y);
+                    let mut value = if raster.enabled.contains(&consts::BLEND) { blend(raster, source, destination) } else { source };
+                    for c in 0..4 {
+                        if !raster.color_mask[c] {
+                            value[c] = destination[c];
+                        }
+                    }
+                    image.set(*level, *layer, x, y, value);
+                }
+            }
+        }
+    }
+}
+
+pub(super) fn clear(state: &mut CpuState, mask: u32)
+{
+    let raster = &state.raster;
+    let framebuffer = match state.framebuffers.get(&state.draw_framebuffer) { Some(framebuffer) => framebuffer, None => return };
+    let mut writes = Vec::new();
+    if mask & consts::COLOR_BUFFER_BIT != 0 {
+        for draw_buffer in framebuffer.draw_buffers() {
+            if let Some(attachment) = framebuffer.attachments.get(&attachment_point(draw_buffer)) {
+                writes.push((*attachment, raster.clear_color, raster.color_mask));
+            }
+        }
+    }
+    if mask & consts::DEPTH_BUFFER_BIT != 0 && raster.depth_mask {
+        if let Some(attachment) = framebuffer.depth_attachment() {
+            writes.push((attachment, [raster.clear_depth, 0.0, 0.0, 1.0], [true, false, false, false]));
+        }
+    }
+    let scissor = if raster.enabled.contains(&consts::SCISSOR_TEST) { Some(raster.scissor) } else { None };
+
+    for ((id, level, layer), value, color_mask) in writes {
+        if let Some(image) = state.images.get_mut(&id) {
+            let mut region = (0, 0, image.level_width(level) as i32, image.level_height(level) as i32);
+            if let Some(scissor) = scissor {
+                region = intersect(region, scissor);
+            }
+            for y in region.1.max(0)..region.3 {
+                for x in region.0.max(0)..region.2 {
+                    let mut pixel = image.get(level, layer, x as usize, y as usize);
+                    for c in 0..4 {
+                        if color_mask[c] {
+                            pixel[c] = value[c];
+                        }
+                    }
+                    image.set(level, layer, x as usize, y as usize, pixel);
+                }
+            }
+        }
+    }
+}
+
+pub(super) fn blit(state: &mut CpuState, source: (u32, u32, u32, u32), destination: (u32, u32, u32, u32), mask: u32, filter: u32)
+{
+    let read = match state.framebuffers.get(&state.read_framebuffer) { Some(framebuffer) => framebuffer, None => return };
+    let draw = match state.framebuffers.get(&state.draw_framebuffer) { Some(framebuffer) => framebuffer, None => return };
+    let mut copies = Vec::new();
+    if mask & consts::COLOR_BUFFER_BIT != 0 {
+        if let Some(from) = read.read_attachment() {
+            for draw_buffer in draw.draw_buffers() {
+                if let Some(to) = draw.attachments.get(&attachment_point(draw_buffer)) {
+                    copies.push((from, *to, filter == consts::LINEAR));
+                }
+            }
+        }
+    }
+    if mask & consts::DEPTH_BUFFER_BIT != 0 {
+        if let (Some(from), Some(to)) = (read.depth_attachment(), draw.depth_attachment()) {
+            copies.push((from, to, false));
+        }
+    }
+
+    let (sx0, sy0, sx1, sy1) = source;
+    let (dx0, dy0, dx1, dy1) = destination;
+    let scale_x = (sx1 as f32 - sx0 as f32) / (dx1 as f32 - dx0 as f32);
+    let scale_y = (sy1 as f32 - sy0 as f32) / (dy1 as f32 - dy0 as f32);
+    for ((from, from_level, from_layer), (to, to_level, to_layer), linear) in copies {
+        let pixels: Vec<(usize, usize, [f32; 4])> = match state.images.get(&from) {
+            Some(image) => (dy0..dy1).flat_map(|y| (dx0..dx1).map(move |x| (x, y))).map(|(x, y)| {
+                let u = sx0 as f32 + (x - dx0) as f32 * scale_x + 0.5 * scale_x;
+                let v = sy0 as f32 + (y - dy0) as f32 * scale_y + 0.5 * scale_y;
+                (x as usize, y as usize, sample_level(image, from_level, from_layer, u, v, linear))
+            }).collect(),
+            None => continue
+        };
+        if let Some(image) = state.images.get_mut(&to) {
+            for (x, y, value) in pixels {
+                if x < image.level_width(to_level) && y < image.level_height(to_level) {
+                    image.set(to_level, to_layer, x, y, value);
+                }
+            }
+        }
+    }
+}
+
+/// The pixels in the given format as floats, starting with the bottom row.
+pub(super) fn read_pixels(state: &CpuState, x: usize, y: usize, width: usize, height: usize, format: u32) -> Vec<f32>
+{
+    let framebuffer = match state.framebuffers.get(&state.read_framebuffer) { Some(framebuffer) => framebuffer, None => return Vec::new() };
+    let attachment = if format == consts::DEPTH_COMPONENT { framebuffer.depth_attachment() }
+        else { framebuffer.read_attachment() };
+    let channels = channel_count(format);
+    let mut values = Vec::with_capacity(width * height * channels);
+    if let Some((image, level, layer)) = attachment.and_then(|(id, level, layer)| state.images.get(&id).map(|image| (image, level, layer))) {
+        for row in y..y + height {
+            for column in x..x + width {
+                let value = if column < image.level_width(level) && row < image.level_height(level) { image.get(level, layer, column, row) }
+                    else { [0.0; 4] };
+                values.extend_from_slice(&value[..channels]);
+            }
+        }
+    }
+    values
+}
+
+pub(super) fn missing_shader(shader_type: &str, sources: &[String]) -> String
+{
+    format!("The {} shader is not implemented on the CPU, see CpuBackend::add_{}_shader. The program has the shaders:\n{}",
+            shader_type, shader_type, sources.join("\n"))
+}
+
+fn uniform_blocks(state: &CpuState, program: &CpuProgram) -> HashMap<String, Vec<f32>>
+{
+    program.block_bindings.iter().filter_map(|(index, binding)| {
+        let name = program.uniform_blocks.get(*index as usize)?;
+        let bytes = state.uniform_buffer_bindings.get(binding).and_then(|id| state.buffers.get(id))?;
+        Some((name.clone(), bytes.chunks_exact(4).map(|value| read_f32(value, 0)).collect()))
+    }).collect()
+}
+
+fn fetch(buffers: &HashMap<u32, Vec<u8>>, binding: &Option<AttributeBinding>, vertex: u32, instance: u32) -> [f32; 4]
+{
+    let mut value = [0.0, 0.0, 0.0, 1.0];
+    if let Some(binding) = binding {
+        let element = instance.checked_div(binding.divisor).unwrap_or(vertex) as usize;
+        let stride = if binding.stride == 0 { binding.size * 4 } else { binding.stride } as usize;
+        if let Some(bytes) = buffers.get(&binding.buffer) {
+            for (c, component) in value.iter_mut().enumerate().take(binding.size as usize) {
+                let offset = binding.offset as usize + element * stride + c * 4;
+                if offset + 4 <= bytes.len() {
+                    *component = read_f32(bytes, offset);
+                }
+            }
+        }
+    }
+    value
+}
+
+/// Clips the triangle against the near plane, which results in zero, one or two triangles.
+fn clip_near(vertices: Vec<ClipVertex>) -> Vec<[ClipVertex; 3]>
+{
+    let distance = |vertex: &ClipVertex| vertex.position.z + vertex.position.w;
+    let mut polygon = Vec::new();
+    for i in 0..3 {
+        let (a, b) = (&vertices[i], &vertices[(i + 1) % 3]);
+        let (distance_a, distance_b) = (distance(a), distance(b));
+        if distance_a >= 0.0 {
+            polygon.push(a.clone());
+        }
+        if (distance_a >= 0.0) != (distance_b >= 0.0) {
+            let t = distance_a / (distance_a - distance_b);
+            polygon.push(ClipVertex {
+                position: a.position + (b.position - a.position) * t,
+                varyings: a.varyings.iter().zip(b.varyings.iter()).map(|(a, b)| a + (b - a) * t).collect()
+            });
+        }
+    }
+    (1..polygon.len().saturating_sub(1)).map(|i| [polygon[0].clone(), polygon[i].clone(), polygon[i + 1].clone()]).collect()
+}
+
+fn edge(a: Vec3, b: Vec3, c: Vec3) -> f32
+{
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+/// Whether a pixel center with the edge function value `e` for the edge from `a` to `b` is inside, where pixel centers exactly on an edge
+/// are only inside for top and left edges, so that pixels on an edge shared by two triangles are drawn once.
+fn inside(e: f32, a: Vec3, b: Vec3) -> bool
+{
+    e > 0.0 || (e == 0.0 && (b.y < a.y || (b.y == a.y && b.x < a.x)))
+}
+
+fn intersect(a: (i32, i32, i32, i32), b: (i32, i32, usize, usize)) -> (i32, i32, i32, i32)
+{
+    (a.0.max(b.0), a.1.max(b.1), a.2.min(b.0 + b.2 as i32), a.3.min(b.1 + b.3 as i32))
+}
+
+fn attachment_point(draw_buffer: u32) -> u32
+{
+    if draw_buffer == consts::BACK { consts::COLOR_ATTACHMENT0 } else { draw_buffer }
+}
+
+fn blend(raster: &RasterState, source: [f32; 4], destination: [f32; 4]) -> [f32; 4]
+{
+    let constant = raster.blend_color;
+    let factor = |function: u32, c: usize| {
+        match function {
+            consts::ZERO => 0.0,
+            consts::SRC_COLOR => source[c],
+            consts::ONE_MINUS_SRC_COLOR => 1.0 - source[c],
+            consts::DST_COLOR => destination[c],
+            consts::ONE_MINUS_DST_COLOR => 1.0 - destination[c],
+            consts::SRC_ALPHA => source[3],
+            consts::ONE_MINUS_SRC_ALPHA => 1.0 - source[3],
+            consts::DST_ALPHA => destination[3],
+            consts::ONE_MINUS_DST_ALPHA => 1.0 - destination[3],
+            consts::CONSTANT_COLOR => constant[c],
+            consts::ONE_MINUS_CONSTANT_COLOR => 1.0 - constant[c],
+            consts::CONSTANT_ALPHA => constant[3],
+            consts::ONE_MINUS_CONSTANT_ALPHA => 1.0 - constant[3],
+            consts::SRC_ALPHA_SATURATE => if c == 3 { 1.0 } else { source[3].min(1.0 - destination[3]) },
+            _ => 1.0
+        }
+    };
+    let mut result = [0.0; 4];
+    for c in 0..4 {
+        let (source_function, destination_function, equation) = if c < 3 {
+            (raster.blend_functions[0], raster.blend_functions[1], raster.blend_equations[0])
+        } else {
+            (raster.blend_functions[2], raster.blend_functions[3], raster.blend_equations[1])
+        };
+        let s = source[c] * factor(source_function, c);
+        let d = destination[c] * factor(destination_function, c);
+        result[c] = match equation {
+            consts::FUNC_SUBTRACT => s - d,
+            consts::FUNC_REVERSE_SUBTRACT => d - s,
+            consts::MIN => source[c].min(destination[c]),
+            consts::MAX => source[c].max(destination[c]),
+            _ => s + d
+        };
+    }
+    result
+}
+
+fn sample_level(image: &CpuImage, level: usize, layer: usize, x: f32, y: f32, linear: bool) -> [f32; 4]
+{
+    let (width, height) = (image.level_width(level), image.level_height(level));
+    let texel = |x: i32, y: i32| image.get(level, layer, x.max(0).min(width as i32 - 1) as usize, y.max(0).min(height as i32 - 1) as usize);
+    if !linear {
+        return texel(x.floor() as i32, y.floor() as i32);
+    }
+    let (x, y) = (x - 0.5, y - 0.5);
+    let (fx, fy) = (x - x.floor(), y - y.floor());
+    let (x0, y0) = (x.floor() as i32, y.floor() as i32);
+    let (a, b, c, d) = (texel(x0, y0), texel(x0 + 1, y0), texel(x0, y0 + 1), texel(x0 + 1, y0 + 1));
+    let mut result = [0.0; 4];
+    for i in 0..4 {
+        result[i] = (a[i] * (1.0 - fx) + b[i] * fx) * (1.0 - fy) + (c[i] * (1.0 - fx) + d[i] * fx) * fy;
+    }
+    result
+}
@@ -0,0 +1,232 @@
+use super::*;
+
+// Rust implementations of the shaders used by ImageEffect, Mesh and DeferredPipeline, which mirror the GLSL sources.
+
+pub(super) fn add_built_in_shaders(backend: &CpuBackend)
+{
+    let light_shared = include_str!("../../shaders/light_shared.frag");
+    let shadow_shared = include_str!("../../shaders/shadow_shared.frag");
+
+    backend.add_vertex_shader(include_str!("../../core/shaders/image_effect.vert"), Rc::new(image_effect));
+    backend.add_vertex_shader(include_str!("../../objects/shaders/mesh_shaded.vert"), Rc::new(mesh_shaded));
+    backend.add_fragment_shader(include_str!("../../objects/shaders/shaded.frag"), Rc::new(shaded));
+    backend.add_fragment_shader(include_str!("../../shaders/copy_depth.frag"), Rc::new(copy_depth));
+    backend.add_fragment_shader(include_str!("../../shaders/ambient_light.frag"), Rc::new(ambient_light));
+    backend.add_fragment_shader(&format!("{}\n{}\n{}", light_shared, shadow_shared, include_str!("../../shaders/directional_light.frag")),
+                                Rc::new(directional_light));
+    backend.add_fragment_shader(&format!("{}\n{}", light_shared, include_str!("../../shaders/point_light.frag")),
+                                Rc::new(point_light));
+    backend.add_fragment_shader(&format!("{}\n{}\n{}", light_shared, shadow_shared, include_str!("../../shaders/spot_light.frag")),
+                                Rc::new(spot_light));
+}
+
+fn image_effect(_context: &ShaderContext, input: &VertexInput, varyings: &mut Vec<f32>) -> Vec4
+{
+    let uv = input.attribute("uv_coordinate");
+    varyings.extend_from_slice(&[uv.x, uv.y]);
+    input.attribute("position").truncate().extend(1.0)
+}
+
+fn mesh_shaded(context: &ShaderContext, input: &VertexInput, varyings: &mut Vec<f32>) -> Vec4
+{
+    let world_position = context.uniform_mat4("modelMatrix") * input.attribute("position").truncate().extend(1.0);
+    let normal_matrix = context.uniform_mat4("normalMatrix");
+    let normal = Mat3::from_cols(normal_matrix.x.truncate(), normal_matrix.y.truncate(), normal_matrix.z.truncate()) * input.attribute("normal").truncate();
+    varyings.extend_from_slice(&[world_position.x, world_position.y, world_position.z, normal.x, normal.y, normal.z]);
+    mat4_from(block_range(context.block("Camera"), 0, 16)) * world_position
+}
+
+fn shaded(context: &ShaderContext, input: &FragmentInput, output: &mut FragmentOutput) -> bool
+{
+    let position = vec3(input.varyings[0], input.varyings[1], input.varyings[2]);
+    let normal = vec3(input.varyings[3], input.varyings[4], input.varyings[5]).normalize();
+    let normal = if input.front_facing { normal } else { -normal };
+    let color = if context.uniform_i32("use_texture") != 0 {
+        let blending = vec3(normal.x.abs().max(0.00001), normal.y.abs().max(0.00001), normal.z.abs().max(0.00001)).normalize();
+        let blending = blending / (blending.x + blending.y + blending.z);
+        let sample = |u: f32, v: f32| context.texture("tex", vec3(0.5 + 0.5 * u, 0.5 + 0.5 * v, 0.0)).truncate();
+        sample(position.y, position.z) * blending.x + sample(position.x, position.z) * blending.y + sample(position.x, position.y) * blending.z
+    }
+    else {
+        context.uniform_vec3("color")
+    };
+    output.colors[0] = color.extend(context.uniform_f32("diffuse_intensity"));
+    let intensity = (context.uniform_f32("specular_intensity") * 15.0).floor() as i32;
+    let power = (context.uniform_f32("specular_power") * 0.5).floor() as i32;
+    output.colors[1] = (normal * 0.5 + vec3(0.5, 0.5, 0.5)).extend((power << 4 | intensity) as f32 / 255.0);
+    true
+}
+
+fn copy_depth(context: &ShaderContext, input: &FragmentInput, output: &mut FragmentOutput) -> bool
+{
+    output.depth = Some(context.texture("depthMap", vec3(input.varyings[0], input.varyings[1], 0.0)).x);
+    true
+}
+
+fn ambient_light(context: &ShaderContext, input: &FragmentInput, output: &mut FragmentOutput) -> bool
+{
+    let uv = vec2(input.varyings[0], input.varyings[1]);
+    let depth = context.texture("depthMap", vec3(uv.x, uv.y, 0.0)).x;
+    if depth > 0.99999 {
+        return false;
+    }
+    let surface = context.texture("gbuffer", vec3(uv.x, uv.y, 0.0)).truncate();
+    let light = context.uniform_vec3("ambientLight.base.color") * context.uniform_f32("ambientLight.base.intensity");
+    output.colors[0] = surface.mul_element_wise(light).extend(1.0);
+    true
+}
+
+fn directional_light(context: &ShaderContext, input: &FragmentInput, output: &mut FragmentOutput) -> bool
+{
+    let block = context.block("DirectionalLight");
+    let surface = match Surface::read(context, input) { Some(surface) => surface, None => return false };
+    let base = BaseLight::read(block, 0);
+    let mut light = surface.light(context, &base, vec3_at(block, 4));
+    if value_at(block, 7) > 0.5 {
+        light *= shadow(context, mat4_from(block_range(block, 8, 24)), surface.position);
+    }
+    output.colors[0] = surface.color.mul_element_wise(light).extend(1.0);
+    true
+}
+
+fn point_light(context: &ShaderContext, input: &FragmentInput, output: &mut FragmentOutput) -> bool
+{
+    let block = context.block("PointLight");
+    let surface = match Surface::read(context, input) { Some(surface) => surface, None => return false };
+    let light = surface.attenuated_light(context, &BaseLight::read(block, 0), attenuation(block), vec3_at(block, 8));
+    output.colors[0] = surface.color.mul_element_wise(light).extend(1.0);
+    true
+}
+
+fn spot_light(context: &ShaderContext, input: &FragmentInput, output: &mut FragmentOutput) -> bool
+{
+    let block = context.block("SpotLight");
+    let surface = match Surface::read(context, input) { Some(surface) => surface, None => return false };
+    let light_position = vec3_at(block, 8);
+    let light_direction = (surface.position - light_position).normalize();
+    let angle = light_direction.dot(vec3_at(block, 12).normalize()).clamp(-1.0, 1.0).acos();
+    let cutoff = std::f32::consts::PI * value_at(block, 11) / 180.0;
+
+    let mut light = vec3(0.0, 0.0, 0.0);
+    if angle < cutoff {
+        light = surface.attenuated_light(context, &BaseLight::read(block, 0), attenuation(block), light_position)
+            * (1.0 - smoothstep(0.75 * cutoff, cutoff, angle));
+        if value_at(block, 15) > 0.5 {
+            light *= shadow(context, mat4_from(block_range(block, 16, 32)), surface.position);
+        }
+    }
+    output.colors[0] = surface.color.mul_element_wise(light).extend(1.0);
+    true
+}
+
+struct BaseLight {
+    color: Vec3,
+    intensity: f32
+}
+
+impl BaseLight {
+    fn read(block: &[f32], offset: usize) -> BaseLight
+    {
+        BaseLight {color: vec3_at(block, offset), intensity: value_at(block, offset + 3)}
+    }
+}
+
+/// The surface at a pixel, decoded from the geometry pass textures.
+struct Surface {
+    color: Vec3,
+    position: Vec3,
+    normal: Vec3,
+    diffuse_intensity: f32,
+    specular_intensity: f32,
+    specular_power: f32
+}
+
+impl Surface {
+    fn read(context: &ShaderContext, input: &FragmentInput) -> Option<Surface>
+    {
+        let uv = vec2(input.varyings[0], input.varyings[1]);
+        let depth = context.texture("depthMap", vec3(uv.x, uv.y, 0.0)).x;
+        if depth > 0.99999 {
+            return None;
+        }
+        let c = context.texture("gbuffer", vec3(uv.x, uv.y, 0.0));
+        let n = context.texture("gbuffer", vec3(uv.x, uv.y, 1.0));
+        let clip_position = context.uniform_mat4("viewProjectionInverse") * vec4(uv.x * 2.0 - 1.0, uv.y * 2.0 - 1.0, depth * 2.0 - 1.0, 1.0);
+        let t = (n.w * 255.0).floor() as i32;
+        Some(Surface {
+            color: c.truncate(),
+            position: clip_position.truncate() / clip_position.w,
+            normal: (n.truncate() * 2.0 - vec3(1.0, 1.0, 1.0)).normalize(),
+            diffuse_intensity: c.w,
+            specular_intensity: (t & 15) as f32 / 15.0,
+            specular_power: 2.0 * ((t & 240) >> 4) as f32
+        })
+    }
+
+    fn light(&self, context: &ShaderContext, light: &BaseLight, light_direction: Vec3) -> Vec3
+    {
+        let diffuse_factor = self.normal.dot(-light_direction);
+        if diffuse_factor <= 0.0 {
+            return vec3(0.0, 0.0, 0.0);
+        }
+        let diffuse = light.color * self.diffuse_intensity * light.intensity * diffuse_factor;
+        let vertex_to_eye = (context.uniform_vec3("eyePosition") - self.position).normalize();
+        let light_reflect = (light_direction - self.normal * 2.0 * self.normal.dot(light_direction)).normalize();
+        let specular_factor = vertex_to_eye.dot(light_reflect);
+        if specular_factor <= 0.0 {
+            return diffuse;
+        }
+        diffuse + light.color * self.specular_intensity * light.intensity * specular_factor.powf(self.specular_power)
+    }
+
+    fn attenuated_light(&self, context: &ShaderContext, light: &BaseLight, attenuation: [f32; 3], light_position: Vec3) -> Vec3
+    {
+        let light_direction = self.position - light_position;
+        let distance = light_direction.magnitude();
+        let color = self.light(context, light, light_direction / distance);
+        let attenuation = attenuation[0] + attenuation[1] * distance + attenuation[2] * distance * distance;
+        color / attenuation.max(1.0)
+    }
+}
+
+fn shadow(context: &ShaderContext, shadow_mvp: Mat4, position: Vec3) -> f32
+{
+    // Shadow disabled
+    if shadow_mvp.w.w < 0.1 {
+        return 1.0;
+    }
+    let shadow_coord = shadow_mvp * position.extend(1.0);
+    let poisson_disk = [vec2(-0.94201624, -0.39906216), vec2(0.9455861, -0.7689072), vec2(-0.0941841, -0.9293887), vec2(0.3449594, 0.2938776)];
+    let visibility: f32 = poisson_disk.iter().map(|offset| {
+        let uv = (vec2(shadow_coord.x, shadow_coord.y) + offset * 0.001) / shadow_coord.w;
+        let true_distance = (shadow_coord.z - 0.005) / shadow_coord.w;
+        if uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0 { 1.0 } else { context.texture_compare("shadowMap", uv, true_distance) }
+    }).sum();
+    visibility * 0.25
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32
+{
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn attenuation(block: &[f32]) -> [f32; 3]
+{
+    [value_at(block, 4), value_at(block, 5), value_at(block, 6)]
+}
+
+fn block_range(block: &[f32], start: usize, end: usize) -> &[f32]
+{
+    if block.len() < end { &[] } else { &block[start..end] }
+}
+
+fn value_at(block: &[f32], offset: usize) -> f32
+{
+    block.get(offset).cloned().unwrap_or(0.0)
+}
+
+fn vec3_at(block: &[f32], offset: usize) -> Vec3
+{
+    vec3(value_at(block, offset), value_at(block, offset + 1), value_at(block, offset + 2))
+}
@@ -4,6 +4,7 @@ pub mod consts {
 }
 
 use consts::Gl as InnerGl;
+use crate::gl::backend::Backend;
 
 pub type AttributeLocation = u32;
 pub type UniformLocation = u32;
@@ -11,7 +12,9 @@ pub type Shader = u32;
 pub type Program = u32;
 pub type Buffer = u32;
 pub type Framebuffer = u32;
+pub type Renderbuffer = u32;
 pub type Texture = u32;
+pub type Sampler = u32;
 pub type VertexArrayObject = u32;
 pub type Sync = consts::types::GLsync;
 pub struct ActiveInfo { size: u32, type_: u32, name: String }
@@ -23,7 +26,12 @@ impl ActiveInfo {
 }
 
 pub struct Glstruct {
-    inner: InnerGl
+    backend: Box<dyn Backend>,
+    state: std::cell::RefCell<crate::core::state::StateCache>,
+    error_check: std::cell::Cell<crate::core::error_check::ErrorCheck>,
+    capabilities: crate::core::capabilities::Capabilities,
+    resources: std::cell::RefCell<crate::core::resources::ResourceRegistry>,
+    screen_framebuffer: std::cell::Cell<Option<Framebuffer>>
 }
 
 pub type Gl = std::rc::Rc<Glstruct>;
@@ -32,27 +40,210 @@ impl Glstruct {
     pub fn load_with<F>(loadfn: F) -> Gl
         where for<'r> F: FnMut(&'r str) -> *const consts::types::GLvoid
     {
-        let gl = Glstruct { inner: InnerGl::load_with(loadfn) };
+        Self::new(Box::new(OpenGL::load_with(loadfn)))
+    }
+
+    /// Creates a context which sends the calls to the given backend, for example a `RecordingBackend` for testing without a GPU.
+    pub fn new(backend: Box<dyn Backend>) -> Gl
+    {
+        let mut gl = Glstruct { backend, state: std::cell::RefCell::new(Default::default()),
+            error_check: std::cell::Cell::new(crate::core::error_check::ErrorCheck::Disabled), capabilities: Default::default(),
+            resources: Default::default(), screen_framebuffer: std::cell::Cell::new(None) };
         gl.bind_vertex_array(&gl.create_vertex_array().unwrap());
-        std::rc::Rc::new(gl)
+        gl.capabilities = crate::core::capabilities::Capabilities::query(&gl);
+        let gl = std::rc::Rc::new(gl);
+        if cfg!(feature = "debug") {
+            crate::core::error_check::set_error_check(&gl, crate::core::error_check::ErrorCheck::Panic);
+        }
+        gl
+    }
+
+    /// The render state cache of this context, see `core::state`.
+    pub(crate) fn state(&self) -> &std::cell::RefCell<crate::core::state::StateCache>
+    {
+        &self.state
+    }
+
+    /// How errors are handled for this context, see `core::error_check`.
+    pub(crate) fn error_check(&self) -> &std::cell::Cell<crate::core::error_check::ErrorCheck>
+    {
+        &self.error_check
+    }
+
+    /// The version, limits and extensions of this context, see `core::capabilities`.
+    pub(crate) fn capabilities(&self) -> &crate::core::capabilities::Capabilities
+    {
+        &self.capabilities
+    }
+
+    /// The live resources of this context, see `core::resources`.
+    pub(crate) fn resources(&self) -> &std::cell::RefCell<crate::core::resources::ResourceRegistry>
+    {
+        &self.resources
+    }
+
+    /// Binds the framebuffer, where `None` is the screen, ie. the default framebuffer unless it is changed with `set_screen_framebuffer`.
+    pub fn bind_framebuffer(&self, target: u32, framebuffer: Option<&Framebuffer>)
+    {
+        let screen = self.screen_framebuffer.get();
+        self.backend.bind_framebuffer(target, framebuffer.or(screen.as_ref()));
+    }
+
+    /// Sets the framebuffer which is used as the screen, for example the framebuffer of an application which this crate renders into.
+    pub(crate) fn set_screen_framebuffer(&self, framebuffer: Option<Framebuffer>)
+    {
+        self.screen_framebuffer.set(framebuffer);
+    }
+}
+
+impl std::ops::Deref for Glstruct {
+    type Target = dyn Backend;
+
+    fn deref(&self) -> &Self::Target
+    {
+        &*self.backend
     }
+}
 
-    pub fn finish(&self)
+/// The backend which calls the OpenGL driver.
+pub struct OpenGL {
+    inner: InnerGl,
+    debug_messages: Box<DebugMessages>,
+    debug_labels: std::cell::Cell<Option<bool>>
+}
+
+// The messages received by `debug_message_callback`, which is given a pointer to this.
+#[derive(Default)]
+struct DebugMessages {
+    enabled: std::cell::Cell<bool>,
+    errors: std::cell::RefCell<Vec<String>>,
+    output: std::cell::RefCell<std::collections::VecDeque<String>>
+}
+
+// The messages which are not errors are dropped when there are more than this, since they are only read on request.
+const MAX_DEBUG_OUTPUT: usize = 64;
+
+impl OpenGL {
+    pub fn load_with<F>(loadfn: F) -> OpenGL
+        where for<'r> F: FnMut(&'r str) -> *const consts::types::GLvoid
+    {
+        OpenGL { inner: InnerGl::load_with(loadfn), debug_messages: Box::new(Default::default()),
+            debug_labels: std::cell::Cell::new(None) }
+    }
+
+    // Whether labels and debug groups are supported, which is only checked on first use.
+    fn supports_debug_labels(&self) -> bool
+    {
+        match self.debug_labels.get() {
+            Some(supported) => supported,
+            None => {
+                let supported = self.inner.ObjectLabel.is_loaded() && self.inner.PushDebugGroup.is_loaded()
+                    && self.supports_extension("GL_KHR_debug");
+                self.debug_labels.set(Some(supported));
+                supported
+            }
+        }
+    }
+}
+
+impl Drop for OpenGL {
+    fn drop(&mut self)
+    {
+        // The driver must not call the callback after the messages are dropped
+        if self.debug_messages.enabled.get() {
+            self.set_debug_messages(false);
+        }
+    }
+}
+
+impl Backend for OpenGL {
+    fn get_error(&self) -> u32
+    {
+        unsafe {
+            self.inner.GetError()
+        }
+    }
+
+    fn set_debug_messages(&self, enable: bool)
+    {
+        // Not available on OpenGL ES 3.0 without the KHR_debug extension
+        if !self.inner.DebugMessageCallback.is_loaded() {
+            return;
+        }
+        unsafe {
+            if enable {
+                self.inner.Enable(consts::DEBUG_OUTPUT);
+                self.inner.Enable(consts::DEBUG_OUTPUT_SYNCHRONOUS);
+                self.inner.DebugMessageCallback(Some(debug_message_callback), &*self.debug_messages as *const _ as *const std::ffi::c_void);
+            }
+            else {
+                self.inner.Disable(consts::DEBUG_OUTPUT);
+                self.inner.DebugMessageCallback(None, std::ptr::null());
+            }
+        }
+        self.debug_messages.enabled.set(enable);
+    }
+
+    fn take_debug_messages(&self) -> Vec<String>
+    {
+        self.debug_messages.errors.borrow_mut().drain(..).collect()
+    }
+
+    fn take_debug_output(&self) -> Vec<String>
+    {
+        self.debug_messages.output.borrow_mut().drain(..).collect()
+    }
+
+    fn object_label(&self, identifier: u32, name: &u32, label: &str)
+    {
+        if !self.supports_debug_labels() {
+            return;
+        }
+        unsafe {
+            self.inner.ObjectLabel(identifier, *name, label.len() as i32, label.as_ptr() as *const consts::types::GLchar);
+        }
+    }
+
+    fn push_debug_group(&self, message: &str)
+    {
+        if !self.supports_debug_labels() {
+            return;
+        }
+        unsafe {
+            self.inner.PushDebugGroup(consts::DEBUG_SOURCE_APPLICATION, 0, message.len() as i32, message.as_ptr() as *const consts::types::GLchar);
+        }
+    }
+
+    fn pop_debug_group(&self)
+    {
+        if !self.supports_debug_labels() {
+            return;
+        }
+        unsafe {
+            self.inner.PopDebugGroup();
+        }
+    }
+
+    fn finish(&self)
     {
         unsafe {
             self.inner.Finish();
         }
     }
 
-    pub fn create_shader(&self, type_: u32) -> Option<Shader>
+    fn create_shader(&self, type_: u32) -> Option<Shader>
     {
         let id = unsafe { self.inner.CreateShader(type_) };
         Some(id)
     }
 
-    pub fn compile_shader(&self, source: &str, shader: &Shader)
+    fn compile_shader(&self, source: &str, shader: &Shader)
     {
+        #[cfg(not(feature = "gles"))]
         let header = "#version 330 core\n";
+        // The shaders are written to also compile as WebGL 2 shaders, so OpenGL ES uses the same header as WebGL
+        #[cfg(feature = "gles")]
+        let header = "#version 300 es\nprecision highp float;\nprecision highp int;\nprecision highp sampler2DArray;\nprecision highp sampler2DShadow;\n";
         let s: &str = &[header, source].concat();
 
         use std::ffi::{CStr, CString};
@@ -64,7 +255,7 @@ impl Glstruct {
         }
     }
 
-    pub fn get_shader_info_log(&self, shader: &Shader) -> Option<String> {
+    fn get_shader_info_log(&self, shader: &Shader) -> Option<String> {
 
         let mut len: consts::types::GLint = 0;
         unsafe {
@@ -83,28 +274,28 @@ impl Glstruct {
         }
     }
 
-    pub fn delete_shader(&self, shader: Option<&Shader>)
+    fn delete_shader(&self, shader: Option<&Shader>)
     {
         unsafe {
             self.inner.DeleteShader(*shader.unwrap());
         }
     }
 
-    pub fn attach_shader(&self, program: &Program, shader: &Shader)
+    fn attach_shader(&self, program: &Program, shader: &Shader)
     {
         unsafe {
             self.inner.AttachShader(*program, *shader);
         }
     }
 
-    pub fn detach_shader(&self, program: &Program, shader: &Shader)
+    fn detach_shader(&self, program: &Program, shader: &Shader)
     {
         unsafe {
             self.inner.DetachShader(*program, *shader);
         }
     }
 
-    pub fn get_program_parameter(&self, program: &Program, pname: u32) -> u32
+    fn get_program_parameter(&self, program: &Program, pname: u32) -> u32
     {
         let mut out = 0;
         unsafe {
@@ -113,7 +304,7 @@ impl Glstruct {
         out as u32
     }
 
-    pub fn get_active_attrib(&self, program: &Program, index: u32) -> ActiveInfo
+    fn get_active_attrib(&self, program: &Program, index: u32) -> ActiveInfo
     {
         let mut length = 128;
         let mut size = 0;
@@ -128,7 +319,7 @@ impl Glstruct {
         ActiveInfo::new(size as u32, _type as u32, s)
     }
 
-    pub fn get_active_uniform(&self, program: &Program, index: u32) -> ActiveInfo
+    fn get_active_uniform(&self, program: &Program, index: u32) -> ActiveInfo
     {
         let mut length = 128;
         let mut size = 0;
@@ -143,7 +334,7 @@ impl Glstruct {
         ActiveInfo::new(size as u32, _type as u32, s)
     }
 
-    pub fn create_buffer(&self) -> Option<Buffer>
+    fn create_buffer(&self) -> Option<Buffer>
     {
         let mut id: u32 = 0;
         unsafe {
@@ -152,14 +343,14 @@ impl Glstruct {
         Some(id)
     }
 
-    pub fn delete_buffer(&self, buffer: &Buffer)
+    fn delete_buffer(&self, buffer: &Buffer)
     {
         unsafe {
             self.inner.DeleteBuffers(1, [*buffer].as_ptr());
         }
     }
 
-    pub fn bind_buffer_base(&self, target: u32, index: u32, buffer: &Buffer)
+    fn bind_buffer_base(&self, target: u32, index: u32, buffer: &Buffer)
     {
         let pname = match target {
             consts::ARRAY_BUFFER => consts::ARRAY_BUFFER_BINDING,
@@ -180,21 +371,21 @@ impl Glstruct {
         }
     }
 
-    pub fn bind_buffer(&self, target: u32, buffer: &Buffer)
+    fn bind_buffer(&self, target: u32, buffer: &Buffer)
     {
         unsafe {
             self.inner.BindBuffer(target, *buffer);
         }
     }
 
-    pub fn unbind_buffer(&self, target: u32)
+    fn unbind_buffer(&self, target: u32)
     {
         unsafe {
             self.inner.BindBuffer(target, 0);
         }
     }
 
-    pub fn get_uniform_block_index(&self, program: &Program, name: &str) -> u32
+    fn get_uniform_block_index(&self, program: &Program, name: &str) -> u32
     {
         let c_str = std::ffi::CString::new(name).unwrap();
         unsafe {
@@ -202,14 +393,14 @@ impl Glstruct {
         }
     }
 
-    pub fn uniform_block_binding(&self, program: &Program, location: u32, index: u32)
+    fn uniform_block_binding(&self, program: &Program, location: u32, index: u32)
     {
         unsafe {
             self.inner.UniformBlockBinding(*program, location, index);
         }
     }
 
-    pub fn buffer_data(&self, target: u32, size_in_bytes: u32, usage: u32) {
+    fn buffer_data(&self, target: u32, size_in_bytes: u32, usage: u32) {
         unsafe {
             self.inner.BufferData(
                 target,
@@ -220,7 +411,7 @@ impl Glstruct {
         }
     }
 
-    pub fn buffer_data_u8(&self, target: u32, data: &[u8], usage: u32)
+    fn buffer_data_u8(&self, target: u32, data: &[u8], usage: u32)
     {
         unsafe {
             self.inner.BufferData(
@@ -232,7 +423,7 @@ impl Glstruct {
         }
     }
 
-    pub fn buffer_data_u32(&self, target: u32, data: &[u32], usage: u32)
+    fn buffer_data_u32(&self, target: u32, data: &[u32], usage: u32)
     {
         unsafe {
             self.inner.BufferData(
@@ -244,7 +435,7 @@ impl Glstruct {
         }
     }
 
-    pub fn buffer_data_f32(&self, target: u32, data: &[f32], usage: u32)
+    fn buffer_data_f32(&self, target: u32, data: &[f32], usage: u32)
     {
         unsafe {
             self.inner.BufferData(
@@ -256,7 +447,7 @@ impl Glstruct {
         }
     }
 
-    pub fn create_vertex_array(&self) -> Option<VertexArrayObject>
+    fn create_vertex_array(&self) -> Option<VertexArrayObject>
     {
         let mut id: u32 = 0;
         unsafe {
@@ -265,19 +456,19 @@ impl Glstruct {
         Some(id)
     }
 
-    pub fn bind_vertex_array(&self, array: &VertexArrayObject)
+    fn bind_vertex_array(&self, array: &VertexArrayObject)
     {
         unsafe {
             self.inner.BindVertexArray(*array);
         }
     }
 
-    pub fn create_program(&self) -> Program
+    fn create_program(&self) -> Program
     {
         unsafe { self.inner.CreateProgram() }
     }
 
-    pub fn link_program(&self, program: &Program) -> bool
+    fn link_program(&self, program: &Program) -> bool
     {
         unsafe { self.inner.LinkProgram(*program); }
 
@@ -288,7 +479,7 @@ impl Glstruct {
         success == 1
     }
 
-    pub fn get_program_info_log(&self, program: &Program) -> Option<String>
+    fn get_program_info_log(&self, program: &Program) -> Option<String>
     {
         let mut len: consts::types::GLint = 0;
         unsafe {
@@ -312,7 +503,7 @@ impl Glstruct {
         }
     }
 
-    pub fn use_program(&self, program: &Program)
+    fn use_program(&self, program: &Program)
     {
         unsafe {
             let mut current = -1;
@@ -326,21 +517,21 @@ impl Glstruct {
         }
     }
 
-    pub fn unuse_program(&self)
+    fn unuse_program(&self)
     {
         unsafe {
             self.inner.UseProgram(0);
         }
     }
 
-    pub fn delete_program(&self, program: &Program)
+    fn delete_program(&self, program: &Program)
     {
         unsafe {
             self.inner.DeleteProgram(*program);
         }
     }
 
-    pub fn get_attrib_location(&self, program: &Program, name: &str) -> Option<AttributeLocation>
+    fn get_attrib_location(&self, program: &Program, name: &str) -> Option<AttributeLocation>
     {
         let c_str = std::ffi::CString::new(name).unwrap();
         let location = unsafe {
@@ -349,21 +540,21 @@ impl Glstruct {
         if location == -1 { None } else { Some(location as AttributeLocation) }
     }
 
-    pub fn enable_vertex_attrib_array(&self, location: AttributeLocation)
+    fn enable_vertex_attrib_array(&self, location: AttributeLocation)
     {
         unsafe {
             self.inner.EnableVertexAttribArray(location);
         }
     }
 
-    pub fn disable_vertex_attrib_array(&self, location: AttributeLocation)
+    fn disable_vertex_attrib_array(&self, location: AttributeLocation)
     {
         unsafe {
             self.inner.DisableVertexAttribArray(location);
         }
     }
 
-    pub fn vertex_attrib_pointer(&self, location: AttributeLocation, size: u32, data_type: u32, normalized: bool, stride: u32, offset: u32)
+    fn vertex_attrib_pointer(&self, location: AttributeLocation, size: u32, data_type: u32, normalized: bool, stride: u32, offset: u32)
     {
         unsafe {
             self.inner.VertexAttribPointer(
@@ -377,14 +568,14 @@ impl Glstruct {
         }
     }
 
-    pub fn vertex_attrib_divisor(&self, location: AttributeLocation, divisor: u32)
+    fn vertex_attrib_divisor(&self, location: AttributeLocation, divisor: u32)
     {
         unsafe {
             self.inner.VertexAttribDivisor(location as consts::types::GLuint, divisor as consts::types::GLuint);
         }
     }
 
-    pub fn get_uniform_location(&self, program: &Program, name: &str) -> Option<UniformLocation>
+    fn get_uniform_location(&self, program: &Program, name: &str) -> Option<UniformLocation>
     {
         let c_str = std::ffi::CString::new(name).unwrap();
         let location = unsafe {
@@ -393,70 +584,77 @@ impl Glstruct {
         if location == -1 { None } else { Some(location as UniformLocation) }
     }
 
-    pub fn uniform1i(&self, location: &UniformLocation, data: i32)
+    fn uniform1i(&self, location: &UniformLocation, data: i32)
     {
         unsafe {
             self.inner.Uniform1i(*location as i32, data);
         }
     }
 
-    pub fn uniform1f(&self, location: &UniformLocation, data: f32)
+    fn uniform1f(&self, location: &UniformLocation, data: f32)
     {
         unsafe {
             self.inner.Uniform1f(*location as i32, data);
         }
     }
 
-    pub fn uniform2fv(&self, location: &UniformLocation, data: &[f32])
+    fn uniform2fv(&self, location: &UniformLocation, data: &[f32])
     {
         unsafe {
             self.inner.Uniform2fv(*location as i32, 1, data.as_ptr());
         }
     }
 
-    pub fn uniform3fv(&self, location: &UniformLocation, data: &[f32])
+    fn uniform3fv(&self, location: &UniformLocation, data: &[f32])
     {
         unsafe {
             self.inner.Uniform3fv(*location as i32, 1, data.as_ptr());
         }
     }
 
-    pub fn uniform4fv(&self, location: &UniformLocation, data: &[f32])
+    fn uniform4fv(&self, location: &UniformLocation, data: &[f32])
     {
         unsafe {
             self.inner.Uniform4fv(*location as i32, 1, data.as_ptr());
         }
     }
 
-    pub fn uniform_matrix2fv(&self, location: &UniformLocation, data: &[f32])
+    fn uniform_matrix2fv(&self, location: &UniformLocation, data: &[f32])
     {
         unsafe {
             self.inner.UniformMatrix2fv(*location as i32, 1, consts::FALSE, data.as_ptr());
         }
     }
 
-    pub fn uniform_matrix3fv(&self, location: &UniformLocation, data: &[f32])
+    fn uniform_matrix3fv(&self, location: &UniformLocation, data: &[f32])
     {
         unsafe {
             self.inner.UniformMatrix3fv(*location as i32, 1, consts::FALSE, data.as_ptr());
         }
     }
 
-    pub fn uniform_matrix4fv(&self, location: &UniformLocation, data: &[f32])
+    fn uniform_matrix4fv(&self, location: &UniformLocation, data: &[f32])
     {
         unsafe {
             self.inner.UniformMatrix4fv(*location as i32, 1, consts::FALSE, data.as_ptr());
         }
     }
 
-    pub fn draw_buffers(&self, draw_buffers: &[u32])
+    fn draw_buffers(&self, draw_buffers: &[u32])
     {
         unsafe {
             self.inner.DrawBuffers(draw_buffers.len() as i32, draw_buffers.as_ptr());
         }
     }
 
-    pub fn create_framebuffer(&self) -> Option<Framebuffer>
+    fn read_buffer(&self, source: u32)
+    {
+        unsafe {
+            self.inner.ReadBuffer(source);
+        }
+    }
+
+    fn create_framebuffer(&self) -> Option<Framebuffer>
     {
         let mut id: u32 = 0;
         unsafe {
@@ -465,7 +663,7 @@ impl Glstruct {
         Some(id)
     }
 
-    pub fn bind_framebuffer(&self, target: u32, framebuffer: Option<&Framebuffer>)
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<&Framebuffer>)
     {
         let id = match framebuffer { Some(fb) => *fb, None => 0 };
         unsafe {
@@ -473,7 +671,7 @@ impl Glstruct {
         }
     }
 
-    pub fn delete_framebuffer(&self, framebuffer: Option<&Framebuffer>)
+    fn delete_framebuffer(&self, framebuffer: Option<&Framebuffer>)
     {
         let id = match framebuffer { Some(fb) => fb, None => &0 };
         unsafe {
@@ -481,7 +679,46 @@ impl Glstruct {
         }
     }
 
-    pub fn check_framebuffer_status(&self) -> Result<(), String>
+    fn create_renderbuffer(&self) -> Option<Renderbuffer>
+    {
+        let mut id: u32 = 0;
+        unsafe {
+            self.inner.GenRenderbuffers(1, &mut id);
+        }
+        Some(id)
+    }
+
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<&Renderbuffer>)
+    {
+        let id = match renderbuffer { Some(rb) => *rb, None => 0 };
+        unsafe {
+            self.inner.BindRenderbuffer(target, id);
+        }
+    }
+
+    fn renderbuffer_storage_multisample(&self, target: u32, samples: u32, internalformat: u32, width: u32, height: u32)
+    {
+        unsafe {
+            self.inner.RenderbufferStorageMultisample(target, samples as i32, internalformat, width as i32, height as i32);
+        }
+    }
+
+    fn framebuffer_renderbuffer(&self, target: u32, attachment: u32, renderbuffertarget: u32, renderbuffer: Option<&Renderbuffer>)
+    {
+        let id = match renderbuffer { Some(rb) => *rb, None => 0 };
+        unsafe {
+            self.inner.FramebufferRenderbuffer(target, attachment, renderbuffertarget, id);
+        }
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer: &Renderbuffer)
+    {
+        unsafe {
+            self.inner.DeleteRenderbuffers(1, renderbuffer);
+        }
+    }
+
+    fn check_framebuffer_status(&self) -> Result<(), String>
     {
         let status = unsafe {
             self.inner.CheckFramebufferStatus(consts::FRAMEBUFFER)
@@ -501,7 +738,7 @@ impl Glstruct {
         }
     }
 
-    pub fn blit_framebuffer(&self, src_x0: u32, src_y0: u32, src_x1: u32, src_y1: u32,
+    fn blit_framebuffer(&self, src_x0: u32, src_y0: u32, src_x1: u32, src_y1: u32,
                                     dst_x0: u32, dst_y0: u32, dst_x1: u32, dst_y1: u32, mask: u32, filter: u32)
     {
         unsafe {
@@ -510,70 +747,133 @@ impl Glstruct {
         }
     }
 
-    pub fn viewport(&self, x: i32, y: i32, width: usize, height: usize)
+    fn pixel_storei(&self, pname: u32, param: i32)
+    {
+        unsafe {
+            self.inner.PixelStorei(pname, param);
+        }
+    }
+
+    fn scissor(&self, x: i32, y: i32, width: usize, height: usize)
+    {
+        unsafe {
+            self.inner.Scissor(x, y, width as i32, height as i32);
+        }
+    }
+
+    fn viewport(&self, x: i32, y: i32, width: usize, height: usize)
     {
         unsafe {
             self.inner.Viewport(x, y, width as i32, height as i32);
         }
     }
 
-    pub fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32)
+    fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32)
     {
         unsafe {
             self.inner.ClearColor(red, green, blue, alpha);
         }
     }
 
-    pub fn clear_depth(&self, depth: f32)
+    fn clear_depth(&self, depth: f32)
     {
         unsafe {
-            self.inner.ClearDepth(depth as f64);
+            self.inner.ClearDepthf(depth);
         }
     }
 
-    pub fn clear(&self, mask: u32)
+    fn clear_stencil(&self, stencil: i32)
+    {
+        unsafe {
+            self.inner.ClearStencil(stencil);
+        }
+    }
+
+    fn clear(&self, mask: u32)
     {
         unsafe {
             self.inner.Clear(mask);
         }
     }
 
-    pub fn enable(&self, cap: u32)
+    fn enable(&self, cap: u32)
     {
         unsafe {
             self.inner.Enable(cap);
         }
     }
 
-    pub fn disable(&self, cap: u32)
+    fn disable(&self, cap: u32)
     {
         unsafe {
             self.inner.Disable(cap);
         }
     }
 
-    pub fn blend_func(&self, sfactor: u32, dfactor: u32)
+    fn blend_func(&self, sfactor: u32, dfactor: u32)
     {
         unsafe {
             self.inner.BlendFunc(sfactor, dfactor);
         }
     }
 
-    pub fn cull_face(&self, mode: u32)
+    fn blend_func_separate(&self, src_rgb: u32, dst_rgb: u32, src_alpha: u32, dst_alpha: u32)
+    {
+        unsafe {
+            self.inner.BlendFuncSeparate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+        }
+    }
+
+    fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32)
+    {
+        unsafe {
+            self.inner.BlendEquationSeparate(mode_rgb, mode_alpha);
+        }
+    }
+
+    fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32)
+    {
+        unsafe {
+            self.inner.BlendColor(red, green, blue, alpha);
+        }
+    }
+
+    fn polygon_offset(&self, factor: f32, units: f32)
+    {
+        unsafe {
+            self.inner.PolygonOffset(factor, units);
+        }
+    }
+
+    fn line_width(&self, width: f32)
+    {
+        unsafe {
+            self.inner.LineWidth(width);
+        }
+    }
+
+    fn polygon_mode(&self, face: u32, mode: u32)
+    {
+        unsafe {
+            self.inner.PolygonMode(face, mode);
+        }
+    }
+
+    fn cull_face(&self, mode: u32)
     {
         unsafe {
             self.inner.CullFace(mode);
         }
     }
 
-    pub fn depth_func(&self, func: u32)
+    fn depth_func(&self, func: u32)
     {
         unsafe {
             self.inner.DepthFunc(func);
         }
     }
 
-    pub fn depth_mask(&self, flag: bool)
+    fn depth_mask(&self, flag: bool)
     {
         unsafe {
             if flag
@@ -586,7 +886,36 @@ impl Glstruct {
         }
     }
 
-    pub fn create_texture(&self) -> Option<Texture>
+    fn stencil_func(&self, func: u32, reference: i32, mask: u32)
+    {
+        unsafe {
+            self.inner.StencilFunc(func, reference, mask);
+        }
+    }
+
+    fn stencil_op(&self, stencil_fail: u32, depth_fail: u32, pass: u32)
+    {
+        unsafe {
+            self.inner.StencilOp(stencil_fail, depth_fail, pass);
+        }
+    }
+
+    fn stencil_mask(&self, mask: u32)
+    {
+        unsafe {
+            self.inner.StencilMask(mask);
+        }
+    }
+
+    fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool)
+    {
+        let flag = |enable: bool| if enable { consts::TRUE } else { consts::FALSE };
+        unsafe {
+            self.inner.ColorMask(flag(red), flag(green), flag(blue), flag(alpha));
+        }
+    }
+
+    fn create_texture(&self) -> Option<Texture>
     {
         let mut id: u32 = 0;
         unsafe {
@@ -595,111 +924,156 @@ impl Glstruct {
         Some(id)
     }
 
-    pub fn active_texture(&self, texture: u32)
+    fn active_texture(&self, texture: u32)
     {
         unsafe {
             self.inner.ActiveTexture(texture);
         }
     }
 
-    pub fn bind_texture(&self, target: u32, texture: &Texture)
+    fn bind_texture(&self, target: u32, texture: &Texture)
     {
         unsafe {
             self.inner.BindTexture(target, *texture);
         }
     }
 
-    pub fn generate_mipmap(&self, target: u32) {
+    fn generate_mipmap(&self, target: u32) {
         unsafe {
             self.inner.GenerateMipmap(target);
         }
     }
 
-    pub fn tex_storage_2d(&self, target: u32, levels: u32, internalformat: u32, width: u32, height: u32)
+    fn tex_storage_2d(&self, target: u32, levels: u32, internalformat: u32, width: u32, height: u32)
     {
         unsafe {
             self.inner.TexStorage2D(target, levels as i32, internalformat, width as i32, height as i32);
         }
     }
 
-    pub fn tex_storage_3d(&self, target: u32, levels: u32, internalformat: u32, width: u32, height: u32, depth: u32)
+    fn tex_storage_3d(&self, target: u32, levels: u32, internalformat: u32, width: u32, height: u32, depth: u32)
     {
         unsafe {
             self.inner.TexStorage3D(target, levels as i32, internalformat, width as i32, height as i32, depth as i32);
         }
     }
 
-    pub fn tex_image_2d(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, border: u32, format: u32, data_type: u32)
+    fn tex_image_2d(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, border: u32, format: u32, data_type: u32)
     {
         unsafe {
             self.inner.TexImage2D(target, level as i32, internalformat as i32, width as i32, height as i32, border as i32, format, data_type, std::ptr::null() as *const consts::types::GLvoid);
         }
     }
 
-    pub fn tex_image_2d_with_u8_data(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, border: u32, format: u32, data_type: u32, pixels: &[u8])
+    fn tex_image_2d_with_u8_data(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, border: u32, format: u32, data_type: u32, pixels: &[u8])
     {
         unsafe {
             self.inner.TexImage2D(target, level as i32, internalformat as i32, width as i32, height as i32, border as i32, format, data_type, pixels.as_ptr() as *const consts::types::GLvoid);
         }
     }
 
-    pub fn tex_sub_image_2d_with_u8_data(&self, target: u32, level: u32, x_offset: u32, y_offset: u32, width: u32, height: u32, format: u32, data_type: u32, pixels: &[u8])
+    fn tex_sub_image_2d_with_u8_data(&self, target: u32, level: u32, x_offset: u32, y_offset: u32, width: u32, height: u32, format: u32, data_type: u32, pixels: &[u8])
     {
         unsafe {
             self.inner.TexSubImage2D(target, level as i32, x_offset as i32, y_offset as i32, width as i32, height as i32, format, data_type, pixels.as_ptr() as *const consts::types::GLvoid);
         }
     }
 
-    pub fn tex_image_2d_with_f32_data(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, border: u32, format: u32, data_type: u32, pixels: &[f32])
+    fn tex_image_2d_with_f32_data(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, border: u32, format: u32, data_type: u32, pixels: &[f32])
     {
         unsafe {
             self.inner.TexImage2D(target, level as i32, internalformat as i32, width as i32, height as i32, border as i32, format, data_type, pixels.as_ptr() as *const consts::types::GLvoid);
         }
     }
 
-    pub fn tex_sub_image_2d_with_f32_data(&self, target: u32, level: u32, x_offset: u32, y_offset: u32, width: u32, height: u32, format: u32, data_type: u32, pixels: &[f32])
+    fn tex_sub_image_2d_with_f32_data(&self, target: u32, level: u32, x_offset: u32, y_offset: u32, width: u32, height: u32, format: u32, data_type: u32, pixels: &[f32])
     {
         unsafe {
             self.inner.TexSubImage2D(target, level as i32, x_offset as i32, y_offset as i32, width as i32, height as i32, format, data_type, pixels.as_ptr() as *const consts::types::GLvoid);
         }
     }
 
-    pub fn tex_image_3d(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, depth: u32, format: u32, data_type: u32)
+    fn tex_image_3d(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, depth: u32, format: u32, data_type: u32)
     {
         unsafe {
             self.inner.TexImage3D(target, level as i32, internalformat as i32, width as i32, height as i32, depth as i32, 0, format, data_type, std::ptr::null() as *const consts::types::GLvoid);
         }
     }
 
-    pub fn tex_parameteri(&self, target: u32, pname: u32, param: i32)
+    fn tex_parameteri(&self, target: u32, pname: u32, param: i32)
     {
         unsafe {
             self.inner.TexParameteri(target, pname, param);
         }
     }
 
-    pub fn delete_texture(&self, texture: &Texture)
+    fn delete_texture(&self, texture: &Texture)
     {
         unsafe {
             self.inner.DeleteTextures(1, texture);
         }
     }
 
-    pub fn framebuffer_texture_2d(&self, target: u32, attachment: u32, textarget: u32, texture: &Texture, level: u32)
+    fn create_sampler(&self) -> Option<Sampler>
+    {
+        let mut id: u32 = 0;
+        unsafe {
+            self.inner.GenSamplers(1, &mut id);
+        }
+        Some(id)
+    }
+
+    fn bind_sampler(&self, unit: u32, sampler: Option<&Sampler>)
+    {
+        let id = match sampler { Some(s) => *s, None => 0 };
+        unsafe {
+            self.inner.BindSampler(unit, id);
+        }
+    }
+
+    fn sampler_parameteri(&self, sampler: &Sampler, pname: u32, param: i32)
+    {
+        unsafe {
+            self.inner.SamplerParameteri(*sampler, pname, param);
+        }
+    }
+
+    fn sampler_parameterf(&self, sampler: &Sampler, pname: u32, param: f32)
+    {
+        unsafe {
+            self.inner.SamplerParameterf(*sampler, pname, param);
+        }
+    }
+
+    fn sampler_parameterfv(&self, sampler: &Sampler, pname: u32, params: &[f32])
+    {
+        unsafe {
+            self.inner.SamplerParameterfv(*sampler, pname, params.as_ptr());
+        }
+    }
+
+    fn delete_sampler(&self, sampler: &Sampler)
+    {
+        unsafe {
+            self.inner.DeleteSamplers(1, sampler);
+        }
+    }
+
+    fn framebuffer_texture_2d(&self, target: u32, attachment: u32, textarget: u32, texture: &Texture, level: u32)
     {
         unsafe {
             self.inner.FramebufferTexture2D(target, attachment, textarget, *texture, level as i32);
         }
     }
 
-    pub fn framebuffer_texture_layer(&self, target: u32, attachment: u32, texture: &Texture, level: u32, layer: u32)
+    fn framebuffer_texture_layer(&self, target: u32, attachment: u32, texture: &Texture, level: u32, layer: u32)
     {
         unsafe {
             self.inner.FramebufferTextureLayer(target, attachment, *texture, level as i32, layer as i32);
         }
     }
 
-    pub fn draw_arrays(&self, mode: u32, first: u32, count: u32)
+    fn draw_arrays(&self, mode: u32, first: u32, count: u32)
     {
         unsafe {
             self.inner.DrawArrays(
@@ -710,7 +1084,7 @@ impl Glstruct {
         }
     }
 
-    pub fn draw_arrays_instanced(&self, mode: u32, first: u32, count: u32, instance_count: u32)
+    fn draw_arrays_instanced(&self, mode: u32, first: u32, count: u32, instance_count: u32)
     {
         unsafe {
             self.inner.DrawArraysInstanced(
@@ -722,7 +1096,7 @@ impl Glstruct {
         }
     }
 
-    pub fn draw_elements(&self, mode: u32, count: u32, data_type: u32, offset: u32)
+    fn draw_elements(&self, mode: u32, count: u32, data_type: u32, offset: u32)
     {
         unsafe {
             self.inner.DrawElements(
@@ -734,7 +1108,7 @@ impl Glstruct {
         }
     }
 
-    pub fn draw_elements_instanced(&self, mode: u32, count: u32, data_type: u32, offset: u32, instance_count: u32)
+    fn draw_elements_instanced(&self, mode: u32, count: u32, data_type: u32, offset: u32, instance_count: u32)
     {
         unsafe {
             self.inner.DrawElementsInstanced(
@@ -747,47 +1121,142 @@ impl Glstruct {
         }
     }
 
-    pub fn read_pixels(&self, x: u32, y: u32, width: u32, height: u32, format: u32, data_type: u32)
+    fn draw_elements_indirect(&self, mode: u32, data_type: u32, offset: u32)
+    {
+        unsafe {
+            self.inner.DrawElementsIndirect(
+                mode as consts::types::GLenum,
+                data_type as consts::types::GLenum,
+                offset as usize as *const consts::types::GLvoid // offset in bytes in the bound draw indirect buffer
+            );
+        }
+    }
+
+    fn multi_draw_elements_indirect(&self, mode: u32, data_type: u32, offset: u32, draw_count: u32, stride: u32)
+    {
+        unsafe {
+            self.inner.MultiDrawElementsIndirect(
+                mode as consts::types::GLenum,
+                data_type as consts::types::GLenum,
+                offset as usize as *const consts::types::GLvoid, // offset in bytes in the bound draw indirect buffer
+                draw_count as consts::types::GLsizei,
+                stride as consts::types::GLsizei
+            );
+        }
+    }
+
+    fn read_pixels(&self, x: u32, y: u32, width: u32, height: u32, format: u32, data_type: u32)
     {
         unsafe {
             self.inner.ReadPixels(x as i32, y as i32, width as i32, height as i32, format, data_type, 0 as *mut consts::types::GLvoid);
         }
     }
 
-    pub fn read_pixels_with_u8_data(&self, x: u32, y: u32, width: u32, height: u32, format: u32, data_type: u32, dst_data: &mut [u8])
+    fn read_pixels_with_u8_data(&self, x: u32, y: u32, width: u32, height: u32, format: u32, data_type: u32, dst_data: &mut [u8])
     {
         unsafe {
             self.inner.ReadPixels(x as i32, y as i32, width as i32, height as i32, format, data_type, dst_data.as_ptr() as *mut consts::types::GLvoid)
         }
     }
 
-    pub fn read_pixels_with_f32_data(&self, x: u32, y: u32, width: u32, height: u32, format: u32, data_type: u32, dst_data: &mut [f32])
+    fn read_pixels_with_f32_data(&self, x: u32, y: u32, width: u32, height: u32, format: u32, data_type: u32, dst_data: &mut [f32])
     {
         unsafe {
             self.inner.ReadPixels(x as i32, y as i32, width as i32, height as i32, format, data_type, dst_data.as_ptr() as *mut consts::types::GLvoid)
         }
     }
 
-    pub fn flush(&self)
+    fn get_parameter_f32(&self, pname: u32) -> f32
+    {
+        let mut out = 0.0;
+        unsafe {
+            self.inner.GetFloatv(pname, &mut out);
+        }
+        out
+    }
+
+    fn get_parameter_f32v(&self, pname: u32, values: &mut [f32])
+    {
+        unsafe {
+            self.inner.GetFloatv(pname, values.as_mut_ptr());
+        }
+    }
+
+    fn get_parameter_i32v(&self, pname: u32, values: &mut [i32])
+    {
+        unsafe {
+            self.inner.GetIntegerv(pname, values.as_mut_ptr());
+        }
+    }
+
+    fn get_parameter_indexed_i32(&self, pname: u32, index: u32) -> i32
+    {
+        let mut out = 0;
+        unsafe {
+            self.inner.GetIntegeri_v(pname, index, &mut out);
+        }
+        out
+    }
+
+    fn is_enabled(&self, cap: u32) -> bool
+    {
+        unsafe {
+            self.inner.IsEnabled(cap) == consts::TRUE
+        }
+    }
+
+    fn get_string(&self, pname: u32) -> String
+    {
+        let string = unsafe { self.inner.GetString(pname) };
+        if string.is_null() {
+            return String::new();
+        }
+        unsafe { std::ffi::CStr::from_ptr(string as *const std::os::raw::c_char) }.to_string_lossy().into_owned()
+    }
+
+    fn get_extensions(&self) -> Vec<String>
+    {
+        let mut count = 0;
+        unsafe {
+            self.inner.GetIntegerv(consts::NUM_EXTENSIONS, &mut count);
+        }
+        (0..count as u32).map(|i| {
+            unsafe { std::ffi::CStr::from_ptr(self.inner.GetStringi(consts::EXTENSIONS, i) as *const std::os::raw::c_char) }.to_string_lossy().into_owned()
+        }).collect()
+    }
+
+    fn supports_extension(&self, name: &str) -> bool
+    {
+        let mut count = 0;
+        unsafe {
+            self.inner.GetIntegerv(consts::NUM_EXTENSIONS, &mut count);
+        }
+        (0..count as u32).any(|i| {
+            let extension = unsafe { std::ffi::CStr::from_ptr(self.inner.GetStringi(consts::EXTENSIONS, i) as *const std::os::raw::c_char) };
+            extension.to_bytes() == name.as_bytes()
+        })
+    }
+
+    fn flush(&self)
     {
         unsafe {
             self.inner.Flush();
         }
     }
 
-    pub fn fence_sync(&self) -> Sync {
+    fn fence_sync(&self) -> Sync {
         unsafe {
             self.inner.FenceSync(consts::SYNC_GPU_COMMANDS_COMPLETE, 0)
         }
     }
 
-    pub fn client_wait_sync(&self, sync: &Sync, flags: u32, timeout: u32) -> u32 {
+    fn client_wait_sync(&self, sync: &Sync, flags: u32, timeout: u32) -> u32 {
         unsafe {
             self.inner.ClientWaitSync(*sync, flags, timeout as u64)
         }
     }
 
-    pub fn delete_sync(&self, sync: &Sync) {
+    fn delete_sync(&self, sync: &Sync) {
         unsafe {
             self.inner.DeleteSync(*sync);
         }
@@ -814,4 +1283,26 @@ pub fn byte_size_for_type(data_type: u32, count: u32) -> u32
         }
         _ => { 0 }
     }
-}
\ No newline at end of file
+}
+
+extern "system" fn debug_message_callback(_source: u32, type_: u32, _id: u32, severity: u32, length: i32,
+                                          message: *const consts::types::GLchar, user_param: *mut std::ffi::c_void)
+{
+    if severity == consts::DEBUG_SEVERITY_NOTIFICATION {
+        return;
+    }
+    let messages = unsafe { &*(user_param as *const DebugMessages) };
+    let bytes = unsafe { std::slice::from_raw_parts(message as *const u8, length as usize) };
+    let message = String::from_utf8_lossy(bytes).into_owned();
+    if type_ == consts::DEBUG_TYPE_ERROR || type_ == consts::DEBUG_TYPE_UNDEFINED_BEHAVIOR {
+        if let Ok(mut errors) = messages.errors.try_borrow_mut() {
+            errors.push(message);
+        }
+    }
+    else if let Ok(mut output) = messages.output.try_borrow_mut() {
+        if output.len() >= MAX_DEBUG_OUTPUT {
+            output.pop_front();
+        }
+        output.push_back(message);
+    }
+}
@@ -0,0 +1,1212 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use crate::gl::*;
+
+/// A call made to a `RecordingBackend`, where `arguments` are the arguments formatted for debugging.
+/// Pixel and buffer data is recorded as its length.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Call {
+    pub name: &'static str,
+    pub arguments: String
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ObjectType {
+    Shader,
+    Program,
+    Buffer,
+    VertexArray,
+    Framebuffer,
+    Renderbuffer,
+    Texture,
+    Sampler,
+    Sync
+}
+
+#[derive(Default)]
+struct Recording {
+    calls: Vec<Call>,
+    next_id: u32,
+    objects: HashMap<u32, ObjectType>,
+    misuses: Vec<String>,
+    reported_misuses: usize,
+    taken_misuses: usize,
+    shader_sources: HashMap<u32, (u32, String)>,
+    attached_shaders: HashMap<u32, Vec<u32>>,
+    attributes: HashMap<u32, Vec<String>>,
+    uniforms: HashMap<u32, Vec<String>>,
+    uniform_blocks: HashMap<u32, Vec<String>>,
+    current_program: Option<u32>,
+    bound_buffers: HashMap<u32, u32>,
+    bound_framebuffers: HashMap<u32, u32>,
+    bound_renderbuffer: Option<u32>,
+    active_texture: u32,
+    bound_textures: HashMap<(u32, u32), u32>,
+    enabled: HashSet<u32>,
+    enabled_attributes: HashSet<u32>,
+    indirect_buffers: HashMap<u32, Vec<u32>>,
+    indirect_draws: Vec<Vec<[u32; 5]>>
+}
+
+impl Recording {
+    fn create(&mut self, object_type: ObjectType) -> u32
+    {
+        self.next_id += 1;
+        self.objects.insert(self.next_id, object_type);
+        self.next_id
+    }
+
+    fn delete(&mut self, id: u32, object_type: ObjectType)
+    {
+        if self.objects.get(&id) == Some(&object_type) {
+            self.objects.remove(&id);
+        }
+        else {
+            self.misuses.push(format!("Deleting {:?} {} which does not exist", object_type, id));
+        }
+    }
+
+    fn check(&mut self, id: u32, object_type: ObjectType, operation: &str)
+    {
+        if self.objects.get(&id) != Some(&object_type) {
+            self.misuses.push(format!("{} {:?} {} which does not exist", operation, object_type, id));
+        }
+    }
+}
+
+/// A backend which records the calls made to it without a GPU, for example to test that rendering issues the expected draw calls
+/// or that all GL objects are deleted when dropped. It tracks the created objects, the bound objects and the enabled capabilities,
+/// and parses the shader sources so that programs report their attributes and uniforms as a driver would.
+/// Using an object which does not exist is reported as a GL error, with a description as debug message, when error checking is enabled (see `core::set_error_check`).
+///
+/// The backend is cloned before it is given to the context, since the clones share the recording:
+/// ```ignore
+/// let recording = RecordingBackend::new();
+/// let gl = Glstruct::new(Box::new(recording.clone()));
+/// ...
+/// assert_eq!(recording.draw_call_count(), 1);
+/// ```
+/// Note that the context creates a vertex array when it is created.
+#[derive(Clone, Default)]
+pub struct RecordingBackend {
+    recording: Rc<RefCell<Recording>>
+}
+
+impl RecordingBackend {
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    pub fn calls(&self) -> Vec<Call>
+    {
+        self.recording.borrow().calls.clone()
+    }
+
+    /// The number of calls with the given name, for example `"draw_elements"`.
+    pub fn call_count(&self, name: &str) -> usize
+    {
+        self.recording.borrow().calls.iter().filter(|call| call.name == name).count()
+    }
+
+    /// The number of calls to any of the draw functions.
+    pub fn draw_call_count(&self) -> usize
+    {
+        ["draw_arrays", "draw_arrays_instanced", "draw_elements", "draw_elements_instanced", "draw_elements_indirect", "multi_draw_elements_indirect"].iter()
+            .map(|name| self.call_count(name)).sum()
+    }
+
+    /// The commands read by each indirect draw, where a command is the count, instance count, first index, base vertex and base instance.
+    pub fn indirect_draws(&self) -> Vec<Vec<[u32; 5]>>
+    {
+        self.recording.borrow().indirect_draws.clone()
+    }
+
+    pub fn clear_calls(&self)
+    {
+        let mut recording = self.recording.borrow_mut();
+        recording.calls.clear();
+        recording.indirect_draws.clear();
+    }
+
+    /// The objects which are created and not yet deleted, sorted by id.
+    pub fn live_objects(&self) -> Vec<(ObjectType, u32)>
+    {
+        let mut objects: Vec<(ObjectType, u32)> = self.recording.borrow().objects.iter().map(|(id, object_type)| (*object_type, *id)).collect();
+        objects.sort_by_key(|(_, id)| *id);
+        objects
+    }
+
+    pub fn live_object_count(&self, object_type: ObjectType) -> usize
+    {
+        self.recording.borrow().objects.values().filter(|t| **t == object_type).count()
+    }
+
+    /// Descriptions of the objects which were used or deleted without existing.
+    pub fn misuses(&self) -> Vec<String>
+    {
+        self.recording.borrow().misuses.clone()
+    }
+
+    pub fn current_program(&self) -> Option<u32>
+    {
+        self.recording.borrow().current_program
+    }
+
+    pub fn bound_buffer(&self, target: u32) -> Option<u32>
+    {
+        self.recording.borrow().bound_buffers.get(&target).cloned()
+    }
+
+    /// The framebuffer bound to the target, `None` meaning the screen.
+    pub fn bound_framebuffer(&self, target: u32) -> Option<u32>
+    {
+        self.recording.borrow().bound_framebuffers.get(&target).cloned()
+    }
+
+    pub fn bound_texture(&self, unit: u32, target: u32) -> Option<u32>
+    {
+        self.recording.borrow().bound_textures.get(&(unit, target)).cloned()
+    }
+
+    pub fn is_enabled(&self, cap: u32) -> bool
+    {
+        self.recording.borrow().enabled.contains(&cap)
+    }
+
+    fn record(&self, name: &'static str, arguments: String)
+    {
+        self.recording.borrow_mut().calls.push(Call {name, arguments});
+    }
+
+    fn bind_buffer_to(&self, target: u32, buffer: Option<u32>)
+    {
+        let mut recording = self.recording.borrow_mut();
+        match buffer {
+            Some(id) => {
+                recording.check(id, ObjectType::Buffer, "Binding");
+                recording.bound_buffers.insert(target, id);
+            },
+            None => { recording.bound_buffers.remove(&target); }
+        }
+    }
+
+    // Reads the commands of an indirect draw from the data of the bound draw indirect buffer.
+    fn record_indirect_draw(&self, offset: u32, draw_count: u32, stride: u32)
+    {
+        let mut recording = self.recording.borrow_mut();
+        let stride = if stride == 0 { 5 } else { stride as usize / 4 };
+        let commands = recording.bound_buffers.get(&consts::DRAW_INDIRECT_BUFFER)
+            .and_then(|buffer| recording.indirect_buffers.get(buffer))
+            .map(|data| (0..draw_count as usize).filter_map(|i| {
+                let start = offset as usize / 4 + i * stride;
+                data.get(start..start + 5).map(|command| [command[0], command[1], command[2], command[3], command[4]])
+            }).collect());
+        match commands {
+            Some(commands) => recording.indirect_draws.push(commands),
+            None => recording.misuses.push("Drawing indirect without a draw indirect buffer".to_string())
+        }
+    }
+
+    fn check_draw(&self)
+    {
+        let mut recording = self.recording.borrow_mut();
+        if recording.current_program.is_none() {
+            recording.misuses.push("Drawing without a program".to_string());
+        }
+    }
+}
+
+macro_rules! record {
+    ($self:ident, $name:expr) => { $self.record($name, String::new()) };
+    ($self:ident, $name:expr, $($argument:expr),+) => { $self.record($name, format!("{:?}", ($($argument,)+))) };
+}
+
+impl Backend for RecordingBackend {
+    fn get_error(&self) -> u32
+    {
+        let mut recording = self.recording.borrow_mut();
+        if recording.reported_misuses < recording.misuses.len() {
+            recording.reported_misuses = recording.misuses.len();
+            consts::INVALID_OPERATION
+        }
+        else {
+            consts::NO_ERROR
+        }
+    }
+
+    fn set_debug_messages(&self, enable: bool)
+    {
+        record!(self, "set_debug_messages", enable);
+    }
+
+    fn take_debug_messages(&self) -> Vec<String>
+    {
+        let mut recording = self.recording.borrow_mut();
+        let messages = recording.misuses[recording.taken_misuses..].to_vec();
+        recording.taken_misuses = recording.misuses.len();
+        messages
+    }
+
+    fn take_debug_output(&self) -> Vec<String>
+    {
+        Vec::new()
+    }
+
+    fn object_label(&self, identifier: u32, name: &u32, label: &str)
+    {
+        record!(self, "object_label", identifier, name, label);
+    }
+
+    fn push_debug_group(&self, message: &str)
+    {
+        record!(self, "push_debug_group", message);
+    }
+
+    fn pop_debug_group(&self)
+    {
+        record!(self, "pop_debug_group");
+    }
+
+    fn finish(&self)
+    {
+        record!(self, "finish");
+    }
+
+    fn create_shader(&self, type_: u32) -> Option<Shader>
+    {
+        record!(self, "create_shader", type_);
+        let id = self.recording.borrow_mut().create(ObjectType::Shader);
+        self.recording.borrow_mut().shader_sources.insert(id, (type_, String::new()));
+        Some(id)
+    }
+
+    fn compile_shader(&self, source: &str, shader: &Shader)
+    {
+        record!(self, "compile_shader", shader);
+        let mut recording = self.recording.borrow_mut();
+        recording.check(*shader, ObjectType::Shader, "Compiling");
+        if let Some(entry) = recording.shader_sources.get_mut(shader) {
+            entry.1 = source.to_string();
+        }
+    }
+
+    fn get_shader_info_log(&self, _shader: &Shader) -> Option<String>
+    {
+        None
+    }
+
+    fn delete_shader(&self, shader: Option<&Shader>)
+    {
+        record!(self, "delete_shader", shader);
+        if let Some(shader) = shader {
+            let mut recording = self.recording.borrow_mut();
+            recording.delete(*shader, ObjectType::Shader);
+            recording.shader_sources.remove(shader);
+        }
+    }
+
+    fn attach_shader(&self, program: &Program, shader: &Shader)
+    {
+        record!(self, "attach_shader", program, shader);
+        let mut recording = self.recording.borrow_mut();
+        recording.check(*program, ObjectType::Program, "Attaching to");
+        recording.check(*shader, ObjectType::Shader, "Attaching");
+        recording.attached_shaders.entry(*program).or_default().push(*shader);
+    }
+
+    fn detach_shader(&self, program: &Program, shader: &Shader)
+    {
+        record!(self, "detach_shader", program, shader);
+        if let Some(shaders) = self.recording.borrow_mut().attached_shaders.get_mut(program) {
+            shaders.retain(|s| s != shader);
+        }
+    }
+
+    fn get_program_parameter(&self, program: &Program, pname: u32) -> u32
+    {
+        let recording = self.recording.borrow();
+        match pname {
+            consts::ACTIVE_ATTRIBUTES => recording.attributes.get(program).map(|a| a.len()).unwrap_or(0) as u32,
+            consts::ACTIVE_UNIFORMS => recording.uniforms.get(program).map(|u| u.len()).unwrap_or(0) as u32,
+            consts::LINK_STATUS => 1,
+            _ => 0
+        }
+    }
+
+    fn get_active_attrib(&self, program: &Program, index: u32) -> ActiveInfo
+    {
+        let name = self.recording.borrow().attributes.get(program).and_then(|a| a.get(index as usize).cloned()).unwrap_or_default();
+        ActiveInfo::new(1, 0, name)
+    }
+
+    fn get_active_uniform(&self, program: &Program, index: u32) -> ActiveInfo
+    {
+        let name = self.recording.borrow().uniforms.get(program).and_then(|u| u.get(index as usize).cloned()).unwrap_or_default();
+        ActiveInfo::new(1, 0, name)
+    }
+
+    fn create_buffer(&self) -> Option<Buffer>
+    {
+        record!(self, "create_buffer");
+        Some(self.recording.borrow_mut().create(ObjectType::Buffer))
+    }
+
+    fn delete_buffer(&self, buffer: &Buffer)
+    {
+        record!(self, "delete_buffer", buffer);
+        let mut recording = self.recording.borrow_mut();
+        recording.delete(*buffer, ObjectType::Buffer);
+        recording.bound_buffers.retain(|_, id| id != buffer);
+        recording.indirect_buffers.remove(buffer);
+    }
+
+    fn bind_buffer_base(&self, target: u32, index: u32, buffer: &Buffer)
+    {
+        record!(self, "bind_buffer_base", target, index, buffer);
+        self.bind_buffer_to(target, Some(*buffer));
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: &Buffer)
+    {
+        record!(self, "bind_buffer", target, buffer);
+        self.bind_buffer_to(target, Some(*buffer));
+    }
+
+    fn unbind_buffer(&self, target: u32)
+    {
+        record!(self, "unbind_buffer", target);
+        self.bind_buffer_to(target, None);
+    }
+
+    fn get_uniform_block_index(&self, program: &Program, name: &str) -> u32
+    {
+        record!(self, "get_uniform_block_index", program, name);
+        self.recording.borrow().uniform_blocks.get(program)
+            .and_then(|blocks| blocks.iter().position(|block| block == name))
+            .map(|index| index as u32).unwrap_or(consts::INVALID_INDEX)
+    }
+
+    fn uniform_block_binding(&self, program: &Program, location: u32, index: u32)
+    {
+        record!(self, "uniform_block_binding", program, location, index);
+        if location == consts::INVALID_INDEX {
+            self.recording.borrow_mut().misuses.push(format!("Binding an unknown uniform block of program {}", program));
+        }
+    }
+
+    fn buffer_data(&self, target: u32, size_in_bytes: u32, usage: u32)
+    {
+        record!(self, "buffer_data", target, size_in_bytes, usage);
+    }
+
+    fn buffer_data_u8(&self, target: u32, data: &[u8], usage: u32)
+    {
+        record!(self, "buffer_data_u8", target, data.len(), usage);
+    }
+
+    fn buffer_data_u32(&self, target: u32, data: &[u32], usage: u32)
+    {
+        record!(self, "buffer_data_u32", target, data.len(), usage);
+        if target == consts::DRAW_INDIRECT_BUFFER {
+            let mut recording = self.recording.borrow_mut();
+            if let Some(buffer) = recording.bound_buffers.get(&target).cloned() {
+                recording.indirect_buffers.insert(buffer, data.to_vec());
+            }
+        }
+    }
+
+    fn buffer_data_f32(&self, target: u32, data: &[f32], usage: u32)
+    {
+        record!(self, "buffer_data_f32", target, data.len(), usage);
+    }
+
+    fn create_vertex_array(&self) -> Option<VertexArrayObject>
+    {
+        record!(self, "create_vertex_array");
+        Some(self.recording.borrow_mut().create(ObjectType::VertexArray))
+    }
+
+    fn bind_vertex_array(&self, array: &VertexArrayObject)
+    {
+        record!(self, "bind_vertex_array", array);
+        self.recording.borrow_mut().check(*array, ObjectType::VertexArray, "Binding");
+    }
+
+    fn create_program(&self) -> Program
+    {
+        record!(self, "create_program");
+        self.recording.borrow_mut().create(ObjectType::Program)
+    }
+
+    fn link_program(&self, program: &Program) -> bool
+    {
+        record!(self, "link_program", program);
+        let mut recording = self.recording.borrow_mut();
+        recording.check(*program, ObjectType::Program, "Linking");
+        let mut attributes = Vec::new();
+        let mut uniforms = Vec::new();
+        let mut uniform_blocks = Vec::new();
+        for shader in recording.attached_shaders.get(program).cloned().unwrap_or_default() {
+            if let Some((type_, source)) = recording.shader_sources.get(&shader) {
+                parse_declarations(source, *type_ == consts::VERTEX_SHADER, &mut attributes, &mut uniforms, &mut uniform_blocks);
+            }
+        }
+        recording.attributes.insert(*program, attributes);
+        recording.uniforms.insert(*program, uniforms);
+        recording.uniform_blocks.insert(*program, uniform_blocks);
+        true
+    }
+
+    fn get_program_info_log(&self, _program: &Program) -> Option<String>
+    {
+        None
+    }
+
+    fn use_program(&self, program: &Program)
+    {
+        record!(self, "use_program", program);
+        let mut recording = self.recording.borrow_mut();
+        recording.check(*program, ObjectType::Program, "Using");
+        recording.current_program = Some(*program);
+    }
+
+    fn unuse_program(&self)
+    {
+        record!(self, "unuse_program");
+        self.recording.borrow_mut().current_program = None;
+    }
+
+    fn delete_program(&self, program: &Program)
+    {
+        record!(self, "delete_program", program);
+        let mut recording = self.recording.borrow_mut();
+        recording.delete(*program, ObjectType::Program);
+        if recording.current_program == Some(*program) {
+            recording.current_program = None;
+        }
+    }
+
+    fn get_attrib_location(&self, program: &Program, name: &str) -> Option<AttributeLocation>
+    {
+        self.recording.borrow().attributes.get(program)
+            .and_then(|attributes| attributes.iter().position(|attribute| attribute == name))
+            .map(|location| location as u32)
+    }
+
+    fn enable_vertex_attrib_array(&self, location: AttributeLocation)
+    {
+        record!(self, "enable_vertex_attrib_array", location);
+        self.recording.borrow_mut().enabled_attributes.insert(location);
+    }
+
+    fn disable_vertex_attrib_array(&self, location: AttributeLocation)
+    {
+        record!(self, "disable_vertex_attrib_array", location);
+        self.recording.borrow_mut().enabled_attributes.remove(&location);
+    }
+
+    fn vertex_attrib_pointer(&self, location: AttributeLocation, size: u32, data_type: u32, normalized: bool, stride: u32, offset: u32)
+    {
+        record!(self, "vertex_attrib_pointer", location, size, data_type, normalized, stride, offset);
+        let mut recording = self.recording.borrow_mut();
+        if !recording.bound_buffers.contains_key(&consts::ARRAY_BUFFER) {
+            recording.misuses.push(format!("Setting vertex attribute {} without a bound array buffer", location));
+        }
+    }
+
+    fn vertex_attrib_divisor(&self, location: AttributeLocation, divisor: u32)
+    {
+        record!(self, "vertex_attrib_divisor", location, divisor);
+    }
+
+    fn get_uniform_location(&self, program: &Program, name: &str) -> Option<UniformLocation>
+    {
+        self.recording.borrow().uniforms.get(program)
+            .and_then(|uniforms| uniforms.iter().position(|uniform| uniform == name))
+            .map(|location| location as u32)
+    }
+
+    fn uniform1i(&self, location: &UniformLocation, data: i32)
+    {
+        record!(self, "uniform1i", location, data);
+    }
+
+    fn uniform1f(&self, location: &UniformLocation, data: f32)
+    {
+        record!(self, "uniform1f", location, data);
+    }
+
+    fn uniform2fv(&self, location: &UniformLocation, data: &[f32])
+    {
+        record!(self, "uniform2fv", location, data);
+    }
+
+    fn uniform3fv(&self, location: &UniformLocation, data: &[f32])
+    {
+        record!(self, "uniform3fv", location, data);
+    }
+
+    fn uniform4fv(&self, location: &UniformLocation, data: &[f32])
+    {
+        record!(self, "uniform4fv", location, data);
+    }
+
+    fn uniform_matrix2fv(&self, location: &UniformLocation, data: &[f32])
+    {
+        record!(self, "uniform_matrix2fv", location, data);
+    }
+
+    fn uniform_matrix3fv(&self, location: &UniformLocation, data: &[f32])
+    {
+        record!(self, "uniform_matrix3fv", location, data);
+    }
+
+    fn uniform_matrix4fv(&self, location: &UniformLocation, data: &[f32])
+    {
+        record!(self, "uniform_matrix4fv", location, data);
+    }
+
+    fn draw_buffers(&self, draw_buffers: &[u32])
+    {
+        record!(self, "draw_buffers", draw_buffers);
+    }
+
+    fn read_buffer(&self, source: u32)
+    {
+        record!(self, "read_buffer", source);
+    }
+
+    fn create_framebuffer(&self) -> Option<Framebuffer>
+    {
+        record!(self, "create_framebuffer");
+        Some(self.recording.borrow_mut().create(ObjectType::Framebuffer))
+    }
+
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<&Framebuffer>)
+    {
+        record!(self, "bind_framebuffer", target, framebuffer);
+        let mut recording = self.recording.borrow_mut();
+        let targets = if target == consts::FRAMEBUFFER { vec![consts::DRAW_FRAMEBUFFER, consts::READ_FRAMEBUFFER] } else { vec![target] };
+        for target in targets {
+            match framebuffer {
+                Some(id) => {
+                    recording.check(*id, ObjectType::Framebuffer, "Binding");
+                    recording.bound_framebuffers.insert(target, *id);
+                },
+                None => { recording.bound_framebuffers.remove(&target); }
+            }
+        }
+    }
+
+    fn delete_framebuffer(&self, framebuffer: Option<&Framebuffer>)
+    {
+        record!(self, "delete_framebuffer", framebuffer);
+        if let Some(framebuffer) = framebuffer {
+            let mut recording = self.recording.borrow_mut();
+            recording.delete(*framebuffer, ObjectType::Framebuffer);
+            recording.bound_framebuffers.retain(|_, id| id != framebuffer);
+        }
+    }
+
+    fn create_renderbuffer(&self) -> Option<Renderbuffer>
+    {
+        record!(self, "create_renderbuffer");
+        Some(self.recording.borrow_mut().create(ObjectType::Renderbuffer))
+    }
+
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<&Renderbuffer>)
+    {
+        record!(self, "bind_renderbuffer", target, renderbuffer);
+        let mut recording = self.recording.borrow_mut();
+        if let Some(id) = renderbuffer {
+            recording.check(*id, ObjectType::Renderbuffer, "Binding");
+        }
+        recording.bound_renderbuffer = renderbuffer.cloned();
+    }
+
+    fn renderbuffer_storage_multisample(&self, target: u32, samples: u32, internalformat: u32, width: u32, height: u32)
+    {
+        record!(self, "renderbuffer_storage_multisample", target, samples, internalformat, width, height);
+    }
+
+    fn framebuffer_renderbuffer(&self, target: u32, attachment: u32, renderbuffertarget: u32, renderbuffer: Option<&Renderbuffer>)
+    {
+        record!(self, "framebuffer_renderbuffer", target, attachment, renderbuffertarget, renderbuffer);
+        if let Some(id) = renderbuffer {
+            self.recording.borrow_mut().check(*id, ObjectType::Renderbuffer, "Attaching");
+        }
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer: &Renderbuffer)
+    {
+        record!(self, "delete_renderbuffer", renderbuffer);
+        let mut recording = self.recording.borrow_mut();
+        recording.delete(*renderbuffer, ObjectType::Renderbuffer);
+        if recording.bound_renderbuffer == Some(*renderbuffer) {
+            recording.bound_renderbuffer = None;
+        }
+    }
+
+    fn check_framebuffer_status(&self) -> Result<(), String>
+    {
+        Ok(())
+    }
+
+    fn blit_framebuffer(&self, src_x0: u32, src_y0: u32, src_x1: u32, src_y1: u32, dst_x0: u32, dst_y0: u32, dst_x1: u32, dst_y1: u32, mask: u32, filter: u32)
+    {
+        record!(self, "blit_framebuffer", src_x0, src_y0, src_x1, src_y1, dst_x0, dst_y0, dst_x1, dst_y1, mask, filter);
+    }
+
+    fn pixel_storei(&self, pname: u32, param: i32)
+    {
+        record!(self, "pixel_storei", pname, param);
+    }
+
+    fn scissor(&self, x: i32, y: i32, width: usize, height: usize)
+    {
+        record!(self, "scissor", x, y, width, height);
+    }
+
+    fn viewport(&self, x: i32, y: i32, width: usize, height: usize)
+    {
+        record!(self, "viewport", x, y, width, height);
+    }
+
+    fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32)
+    {
+        record!(self, "clear_color", red, green, blue, alpha);
+    }
+
+    fn clear_depth(&self, depth: f32)
+    {
+        record!(self, "clear_depth", depth);
+    }
+
+    fn clear_stencil(&self, stencil: i32)
+    {
+        record!(self, "clear_stencil", stencil);
+    }
+
+    fn clear(&self, mask: u32)
+    {
+        record!(self, "clear", mask);
+    }
+
+    fn enable(&self, cap: u32)
+    {
+        record!(self, "enable", cap);
+        self.recording.borrow_mut().enabled.insert(cap);
+    }
+
+    fn disable(&self, cap: u32)
+    {
+        record!(self, "disable", cap);
+        self.recording.borrow_mut().enabled.remove(&cap);
+    }
+
+    fn blend_func(&self, sfactor: u32, dfactor: u32)
+    {
+        record!(self, "blend_func", sfactor, dfactor);
+    }
+
+    fn blend_func_separate(&self, src_rgb: u32, dst_rgb: u32, src_alpha: u32, dst_alpha: u32)
+    {
+        record!(self, "blend_func_separate", src_rgb, dst_rgb, src_alpha, dst_alpha);
+    }
+
+    fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32)
+    {
+        record!(self, "blend_equation_separate", mode_rgb, mode_alpha);
+    }
+
+    fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32)
+    {
+        record!(self, "blend_color", red, green, blue, alpha);
+    }
+
+    fn polygon_offset(&self, factor: f32, units: f32)
+    {
+        record!(self, "polygon_offset", factor, units);
+    }
+
+    fn line_width(&self, width: f32)
+    {
+        record!(self, "line_width", width);
+    }
+
+    fn polygon_mode(&self, face: u32, mode: u32)
+    {
+        record!(self, "polygon_mode", face, mode);
+    }
+
+    fn cull_face(&self, mode: u32)
+    {
+        record!(self, "cull_face", mode);
+    }
+
+    fn depth_func(&self, func: u32)
+    {
+        record!(self, "depth_func", func);
+    }
+
+    fn depth_mask(&self, flag: bool)
+    {
+        record!(self, "depth_mask", flag);
+    }
+
+    fn stencil_func(&self, func: u32, reference: i32, mask: u32)
+    {
+        record!(self, "stencil_func", func, reference, mask);
+    }
+
+    fn stencil_op(&self, stencil_fail: u32, depth_fail: u32, pass: u32)
+    {
+        record!(self, "stencil_op", stencil_fail, depth_fail, pass);
+    }
+
+    fn stencil_mask(&self, mask: u32)
+    {
+        record!(self, "stencil_mask", mask);
+    }
+
+    fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool)
+    {
+        record!(self, "color_mask", red, green, blue, alpha);
+    }
+
+    fn create_texture(&self) -> Option<Texture>
+    {
+        record!(self, "create_texture");
+        Some(self.recording.borrow_mut().create(ObjectType::Texture))
+    }
+
+    fn active_texture(&self, texture: u32)
+    {
+        record!(self, "active_texture", texture);
+        self.recording.borrow_mut().active_texture = texture - consts::TEXTURE0;
+    }
+
+    fn bind_texture(&self, target: u32, texture: &Texture)
+    {
+        record!(self, "bind_texture", target, texture);
+        let mut recording = self.recording.borrow_mut();
+        recording.check(*texture, ObjectType::Texture, "Binding");
+        let unit = recording.active_texture;
+        recording.bound_textures.insert((unit, target), *texture);
+    }
+
+    fn generate_mipmap(&self, target: u32)
+    {
+        record!(self, "generate_mipmap", target);
+    }
+
+    fn tex_storage_2d(&self, target: u32, levels: u32, internalformat: u32, width: u32, height: u32)
+    {
+        record!(self, "tex_storage_2d", target, levels, internalformat, width, height);
+    }
+
+    fn tex_storage_3d(&self, target: u32, levels: u32, internalformat: u32, width: u32, height: u32, depth: u32)
+    {
+        record!(self, "tex_storage_3d", target, levels, internalformat, width, height, depth);
+    }
+
+    fn tex_image_2d(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, border: u32, format: u32, data_type: u32)
+    {
+        record!(self, "tex_image_2d", target, level, internalformat, width, height, border, format, data_type);
+    }
+
+    fn tex_image_2d_with_u8_data(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, border: u32, format: u32, data_type: u32, pixels: &[u8])
+    {
+        record!(self, "tex_image_2d_with_u8_data", target, level, internalformat, width, height, border, format, data_type, pixels.len());
+    }
+
+    fn tex_sub_image_2d_with_u8_data(&self, target: u32, level: u32, x_offset: u32, y_offset: u32, width: u32, height: u32, format: u32, data_type: u32, pixels: &[u8])
+    {
+        record!(self, "tex_sub_image_2d_with_u8_data", target, level, x_offset, y_offset, width, height, format, data_type, pixels.len());
+    }
+
+    fn tex_image_2d_with_f32_data(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, border: u32, format: u32, data_type: u32, pixels: &[f32])
+    {
+        record!(self, "tex_image_2d_with_f32_data", target, level, internalformat, width, height, border, format, data_type, pixels.len());
+    }
+
+    fn tex_sub_image_2d_with_f32_data(&self, target: u32, level: u32, x_offset: u32, y_offset: u32, width: u32, height: u32, format: u32, data_type: u32, pixels: &[f32])
+    {
+        record!(self, "tex_sub_image_2d_with_f32_data", target, level, x_offset, y_offset, width, height, format, data_type, pixels.len());
+    }
+
+    fn tex_image_3d(&self, target: u32, level: u32, internalformat: u32, width: u32, height: u32, depth: u32, format: u32, data_type: u32)
+    {
+        record!(self, "tex_image_3d", target, level, internalformat, width, height, depth, format, data_type);
+    }
+
+    fn tex_parameteri(&self, target: u32, pname: u32, param: i32)
+    {
+        record!(self, "tex_parameteri", target, pname, param);
+    }
+
+    fn delete_texture(&self, texture: &Texture)
+    {
+        record!(self, "delete_texture", texture);
+        let mut recording = self.recording.borrow_mut();
+        recording.delete(*texture, ObjectType::Texture);
+        recording.bound_textures.retain(|_, id| id != texture);
+    }
+
+    fn create_sampler(&self) -> Option<Sampler>
+    {
+        record!(self, "create_sampler");
+        Some(self.recording.borrow_mut().create(ObjectType::Sampler))
+    }
+
+    fn bind_sampler(&self, unit: u32, sampler: Option<&Sampler>)
+    {
+        record!(self, "bind_sampler", unit, sampler);
+        if let Some(id) = sampler {
+            self.recording.borrow_mut().check(*id, ObjectType::Sampler, "Binding");
+        }
+    }
+
+    fn sampler_parameteri(&self, sampler: &Sampler, pname: u32, param: i32)
+    {
+        record!(self, "sampler_parameteri", sampler, pname, param);
+    }
+
+    fn sampler_parameterf(&self, sampler: &Sampler, pname: u32, param: f32)
+    {
+        record!(self, "sampler_parameterf", sampler, pname, param);
+    }
+
+    fn sampler_parameterfv(&self, sampler: &Sampler, pname: u32, params: &[f32])
+    {
+        record!(self, "sampler_parameterfv", sampler, pname, params);
+    }
+
+    fn delete_sampler(&self, sampler: &Sampler)
+    {
+        record!(self, "delete_sampler", sampler);
+        self.recording.borrow_mut().delete(*sampler, ObjectType::Sampler);
+    }
+
+    fn framebuffer_texture_2d(&self, target: u32, attachment: u32, textarget: u32, texture: &Texture, level: u32)
+    {
+        record!(self, "framebuffer_texture_2d", target, attachment, textarget, texture, level);
+        self.recording.borrow_mut().check(*texture, ObjectType::Texture, "Attaching");
+    }
+
+    fn framebuffer_texture_layer(&self, target: u32, attachment: u32, texture: &Texture, level: u32, layer: u32)
+    {
+        record!(self, "framebuffer_texture_layer", target, attachment, texture, level, layer);
+        self.recording.borrow_mut().check(*texture, ObjectType::Texture, "Attaching");
+    }
+
+    fn draw_arrays(&self, mode: u32, first: u32, count: u32)
+    {
+        record!(self, "draw_arrays", mode, first, count);
+        self.check_draw();
+    }
+
+    fn draw_arrays_instanced(&self, mode: u32, first: u32, count: u32, instance_count: u32)
+    {
+        record!(self, "draw_arrays_instanced", mode, first, count, instance_count);
+        self.check_draw();
+    }
+
+    fn draw_elements(&self, mode: u32, count: u32, data_type: u32, offset: u32)
+    {
+        record!(self, "draw_elements", mode, count, data_type, offset);
+        self.check_draw();
+    }
+
+    fn draw_elements_instanced(&self, mode: u32, count: u32, data_type: u32, offset: u32, instance_count: u32)
+    {
+        record!(self, "draw_elements_instanced", mode, count, data_type, offset, instance_count);
+        self.check_draw();
+    }
+
+    fn draw_elements_indirect(&self, mode: u32, data_type: u32, offset: u32)
+    {
+        record!(self, "draw_elements_indirect", mode, data_type, offset);
+        self.check_draw();
+        self.record_indirect_draw(offset, 1, 0);
+    }
+
+    fn multi_draw_elements_indirect(&self, mode: u32, data_type: u32, offset: u32, draw_count: u32, stride: u32)
+    {
+        record!(self, "multi_draw_elements_indirect", mode, data_type, offset, draw_count, stride);
+        self.check_draw();
+        self.record_indirect_draw(offset, draw_count, stride);
+    }
+
+    fn read_pixels(&self, x: u32, y: u32, width: u32, height: u32, format: u32, data_type: u32)
+    {
+        record!(self, "read_pixels", x, y, width, height, format, data_type);
+    }
+
+    fn read_pixels_with_u8_data(&self, x: u32, y: u32, width: u32, height: u32, format: u32, data_type: u32, dst_data: &mut [u8])
+    {
+        record!(self, "read_pixels_with_u8_data", x, y, width, height, format, data_type, dst_data.len());
+    }
+
+    fn read_pixels_with_f32_data(&self, x: u32, y: u32, width: u32, height: u32, format: u32, data_type: u32, dst_data: &mut [f32])
+    {
+        record!(self, "read_pixels_with_f32_data", x, y, width, height, format, data_type, dst_data.len());
+    }
+
+    fn get_parameter_f32(&self, pname: u32) -> f32
+    {
+        match pname {
+            consts::MAX_SAMPLES => 4.0,
+            consts::MAX_TEXTURE_SIZE | consts::MAX_CUBE_MAP_TEXTURE_SIZE => 16384.0,
+            consts::MAX_ARRAY_TEXTURE_LAYERS => 2048.0,
+            consts::MAX_DRAW_BUFFERS | consts::MAX_COLOR_ATTACHMENTS => 8.0,
+            _ => 0.0
+        }
+    }
+
+    fn get_parameter_f32v(&self, _pname: u32, _values: &mut [f32])
+    {
+    }
+
+    fn get_parameter_i32v(&self, _pname: u32, _values: &mut [i32])
+    {
+    }
+
+    fn get_parameter_indexed_i32(&self, _pname: u32, _index: u32) -> i32
+    {
+        0
+    }
+
+    fn is_enabled(&self, cap: u32) -> bool
+    {
+        self.recording.borrow().enabled.contains(&cap)
+    }
+
+    fn get_string(&self, pname: u32) -> String
+    {
+        match pname {
+            consts::VERSION => "4.3 RecordingBackend".to_string(),
+            consts::SHADING_LANGUAGE_VERSION => "4.30".to_string(),
+            consts::RENDERER => "RecordingBackend".to_string(),
+            _ => String::new()
+        }
+    }
+
+    fn get_extensions(&self) -> Vec<String>
+    {
+        Vec::new()
+    }
+
+    fn supports_extension(&self, _name: &str) -> bool
+    {
+        false
+    }
+
+    fn flush(&self)
+    {
+        record!(self, "flush");
+    }
+
+    fn fence_sync(&self) -> Sync
+    {
+        record!(self, "fence_sync");
+        self.recording.borrow_mut().create(ObjectType::Sync) as usize as Sync
+    }
+
+    fn client_wait_sync(&self, sync: &Sync, flags: u32, timeout: u32) -> u32
+    {
+        record!(self, "client_wait_sync", *sync as usize, flags, timeout);
+        consts::ALREADY_SIGNALED
+    }
+
+    fn delete_sync(&self, sync: &Sync)
+    {
+        record!(self, "delete_sync", *sync as usize);
+        self.recording.borrow_mut().delete(*sync as usize as u32, ObjectType::Sync);
+    }
+}
+
+// Finds the vertex attributes (`in` declarations of the vertex shader), the uniforms, with structs expanded into their fields,
+// and the uniform blocks declared at the top level of the shader source.
+pub(crate) fn parse_declarations(source: &str, is_vertex_shader: bool, attributes: &mut Vec<String>, uniforms: &mut Vec<String>, uniform_blocks: &mut Vec<String>)
+{
+    let source = strip_comments(source);
+    let mut structs: HashMap<String, Vec<String>> = HashMap::new();
+    let mut open_struct: Option<String> = None;
+    let mut depth = 0;
+    let mut statement = String::new();
+    for c in source.chars() {
+        match c {
+            '{' => {
+                let tokens = tokens(&statement);
+                if depth == 0 {
+                    if tokens.len() == 2 && tokens[0] == "struct" {
+                        open_struct = Some(tokens[1].clone());
+                        structs.insert(tokens[1].clone(), Vec::new());
+                    }
+                    else if tokens.len() >= 2 && tokens[tokens.len() - 2] == "uniform" {
+                        uniform_blocks.push(tokens[tokens.len() - 1].clone());
+                    }
+                }
+                depth += 1;
+                statement.clear();
+            },
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    open_struct = None;
+                }
+                statement.clear();
+            },
+            ';' => {
+                let is_array = statement.contains('[');
+                let tokens = tokens(statement.split('[').next().unwrap_or(""));
+                if tokens.len() >= 2 {
+                    let name = tokens[tokens.len() - 1].clone();
+                    let type_ = tokens[tokens.len() - 2].clone();
+                    let name = if is_array { format!("{}[0]", name) } else { name };
+                    if depth == 1 {
+                        if let Some(ref struct_name) = open_struct {
+                            let fields = match structs.get(&type_) {
+                                Some(nested) => nested.iter().map(|field| format!("{}.{}", name, field)).collect(),
+                                None => vec![name]
+                            };
+                            structs.get_mut(struct_name).unwrap().extend(fields);
+                        }
+                    }
+                    else if depth == 0 && tokens[0] == "uniform" {
+                        match structs.get(&type_) {
+                            Some(fields) => uniforms.extend(fields.iter().map(|field| format!("{}.{}", name, field))),
+                            None => uniforms.push(name)
+                        }
+                    }
+                    else if depth == 0 && tokens[0] == "in" && is_vertex_shader {
+                        attributes.push(name);
+                    }
+                }
+                statement.clear();
+            },
+            _ => statement.push(c)
+        }
+    }
+}
+
+// Splits a declaration into words, skipping a layout qualifier.
+fn tokens(statement: &str) -> Vec<String>
+{
+    let mut statement = statement.trim().to_string();
+    if statement.starts_with("layout") {
+        if let Some(end) = statement.find(')') {
+            statement = statement[end + 1..].to_string();
+        }
+    }
+    statement.split_whitespace().map(|word| word.to_string()).collect()
+}
+
+pub(crate) fn strip_comments(source: &str) -> String
+{
+    let mut result = String::new();
+    let mut rest = source;
+    while !rest.is_empty() {
+        if rest.starts_with("//") {
+            rest = &rest[rest.find('\n').unwrap_or(rest.len())..];
+        }
+        else if rest.starts_with("/*") {
+            rest = rest.find("*/").map(|end| &rest[end + 2..]).unwrap_or("");
+        }
+        else {
+            let c = rest.chars().next().unwrap();
+            // Preprocessor directives are not terminated by a semicolon
+            if c == '#' {
+                rest = &rest[rest.find('\n').unwrap_or(rest.len())..];
+                continue;
+            }
+            result.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    result
+}
+
+#[cfg(all(test, not(feature = "no-renderer")))]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn nested_writes_restore_the_outer_render_target()
+    {
+        let recording = RecordingBackend::new();
+        let gl = Glstruct::new(Box::new(recording.clone()));
+        let texture = |size| Texture2D::new(&gl, size, size, Interpolation::Nearest, Interpolation::Nearest, None,
+                                            Wrapping::ClampToEdge, Wrapping::ClampToEdge, Format::RGBA8).unwrap();
+        let outer = RenderTarget::new_color(&gl, texture(64)).unwrap();
+        let inner = RenderTarget::new_color(&gl, texture(16)).unwrap();
+
+        outer.write(None, None, &|| {
+            let outer_framebuffer = recording.bound_framebuffer(consts::DRAW_FRAMEBUFFER);
+            inner.write(None, None, &|| {
+                assert_ne!(recording.bound_framebuffer(consts::DRAW_FRAMEBUFFER), outer_framebuffer);
+            }).unwrap();
+            assert_eq!(recording.bound_framebuffer(consts::DRAW_FRAMEBUFFER), outer_framebuffer);
+            assert_eq!(current_state(&gl).viewport, Some(Viewport::new_at_origo(64, 64)));
+        }).unwrap();
+        assert_eq!(recording.bound_framebuffer(consts::DRAW_FRAMEBUFFER), None);
+    }
+
+    #[cfg(not(feature = "gles"))]
+    #[test]
+    fn mesh_batch_draws_all_meshes_with_one_indirect_draw()
+    {
+        let recording = RecordingBackend::new();
+        let gl = Glstruct::new(Box::new(recording.clone()));
+        let triangle = ([0, 1, 2], [0.0; 9], [0.0; 9]);
+        let quad = ([0, 1, 2, 0, 2, 3], [0.0; 12], [0.0; 12]);
+        let meshes: Vec<(&[u32], &[f32], &[f32])> = vec![(&triangle.0, &triangle.1, &triangle.2), (&quad.0, &quad.1, &quad.2),
+                                                         (&triangle.0, &triangle.1, &triangle.2)];
+        let batch = MeshBatch::new(&gl, &meshes, &[Mat4::identity(); 3]).unwrap();
+        let camera = Camera::new_perspective(&gl, vec3(0.0, 0.0, 5.0), vec3(0.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), degrees(45.0), 1.0, 0.1, 10.0);
+
+        recording.clear_calls();
+        batch.render(&camera);
+        assert_eq!(recording.draw_call_count(), 1);
+        assert_eq!(recording.call_count("multi_draw_elements_indirect"), 1);
+        assert_eq!(recording.indirect_draws(), vec![vec![[3, 1, 0, 0, 0], [6, 1, 3, 3, 1], [3, 1, 9, 7, 2]]]);
+        assert!(recording.misuses().is_empty());
+    }
+
+    #[test]
+    fn light_pass_draws_once_per_point_light()
+    {
+        let recording = RecordingBackend::new();
+        let gl = Glstruct::new(Box::new(recording.clone()));
+        let mut pipeline = DeferredPipeline::new(&gl).unwrap();
+        let camera = Camera::new_perspective(&gl, vec3(0.0, 0.0, 5.0), vec3(0.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), degrees(45.0), 1.0, 0.1, 10.0);
+        let first = PointLight::new(&gl, 1.0, &vec3(1.0, 0.0, 0.0), &vec3(1.0, 1.0, 1.0), 0.5, 0.05, 0.005).unwrap();
+        let second = PointLight::new(&gl, 1.0, &vec3(0.0, 0.0, 1.0), &vec3(-1.0, 1.0, 1.0), 0.5, 0.05, 0.005).unwrap();
+        pipeline.geometry_pass(64, 64, &|| {}).unwrap();
+
+        recording.clear_calls();
+        pipeline.light_pass(&camera, None, &[], &[], &[&first, &second]).unwrap();
+        assert_eq!(recording.draw_call_count(), 2);
+    }
+
+    #[test]
+    fn dropping_deletes_all_objects()
+    {
+        let recording = RecordingBackend::new();
+        {
+            let gl = Glstruct::new(Box::new(recording.clone()));
+            let mut pipeline = DeferredPipeline::new(&gl).unwrap();
+            let camera = Camera::new_perspective(&gl, vec3(0.0, 0.0, 5.0), vec3(0.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), degrees(45.0), 1.0, 0.1, 10.0);
+            let light = PointLight::new(&gl, 1.0, &vec3(1.0, 1.0, 1.0), &vec3(1.0, 1.0, 1.0), 0.5, 0.05, 0.005).unwrap();
+            pipeline.geometry_pass(64, 64, &|| {}).unwrap();
+            pipeline.light_pass(&camera, None, &[], &[], &[&light]).unwrap();
+        }
+        // Only the vertex array created by the context itself remains
+        assert_eq!(recording.live_objects(), vec![(ObjectType::VertexArray, 1)]);
+        assert!(recording.misuses().is_empty());
+    }
+}
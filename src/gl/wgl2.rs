@@ -10,7 +10,9 @@ pub use web_sys::WebGlShader as Shader;
 pub use web_sys::WebGlProgram as Program;
 pub use web_sys::WebGlBuffer as Buffer;
 pub use web_sys::WebGlFramebuffer as Framebuffer;
+pub use web_sys::WebGlRenderbuffer as Renderbuffer;
 pub use web_sys::WebGlTexture as Texture;
+pub use web_sys::WebGlSampler as Sampler;
 pub use web_sys::WebGlVertexArrayObject as VertexArrayObject;
 pub use web_sys::WebGlActiveInfo as ActiveInfo;
 pub use web_sys::WebGlSync as Sync;
@@ -18,6 +20,11 @@ pub use web_sys::WebGlSync as Sync;
 #[derive(Clone)]
 pub struct Glstruct {
     inner: InnerGl,
+    state: std::cell::RefCell<crate::core::state::StateCache>,
+    error_check: std::cell::Cell<crate::core::error_check::ErrorCheck>,
+    capabilities: crate::core::capabilities::Capabilities,
+    resources: std::cell::RefCell<crate::core::resources::ResourceRegistry>,
+    enabled_extensions: std::cell::RefCell<Vec<String>>
 }
 
 pub type Gl = std::rc::Rc<Glstruct>;
@@ -25,7 +32,74 @@ pub type Gl = std::rc::Rc<Glstruct>;
 impl Glstruct {
     pub fn new(webgl_context: InnerGl) -> Gl
     {
-        std::rc::Rc::new(Glstruct { inner: webgl_context })
+        let mut gl = Glstruct { inner: webgl_context, state: std::cell::RefCell::new(Default::default()),
+            error_check: std::cell::Cell::new(crate::core::error_check::ErrorCheck::Disabled), capabilities: Default::default(),
+            resources: Default::default(), enabled_extensions: Default::default() };
+        gl.capabilities = crate::core::capabilities::Capabilities::query(&gl);
+        std::rc::Rc::new(gl)
+    }
+
+    /// The render state cache of this context, see `core::state`.
+    pub(crate) fn state(&self) -> &std::cell::RefCell<crate::core::state::StateCache>
+    {
+        &self.state
+    }
+
+    /// How errors are handled for this context, see `core::error_check`.
+    pub(crate) fn error_check(&self) -> &std::cell::Cell<crate::core::error_check::ErrorCheck>
+    {
+        &self.error_check
+    }
+
+    /// The version, limits and extensions of this context, see `core::capabilities`.
+    pub(crate) fn capabilities(&self) -> &crate::core::capabilities::Capabilities
+    {
+        &self.capabilities
+    }
+
+    /// The live resources of this context, see `core::resources`.
+    pub(crate) fn resources(&self) -> &std::cell::RefCell<crate::core::resources::ResourceRegistry>
+    {
+        &self.resources
+    }
+
+    /// Prepares the context for rendering after the browser has restored it, which resets all state and disables all extensions.
+    /// Resources created before the context was lost are invalid and must be recreated, see `core::resources::on_context_restored`.
+    pub(crate) fn restore_context(&self)
+    {
+        for name in self.enabled_extensions.borrow().iter() {
+            self.inner.get_extension(name).ok();
+        }
+        *self.state.borrow_mut() = Default::default();
+    }
+
+    /// WebGL has no debug messages, so this does nothing.
+    pub fn set_debug_messages(&self, _enable: bool)
+    {
+    }
+
+    pub fn take_debug_messages(&self) -> Vec<String>
+    {
+        Vec::new()
+    }
+
+    pub fn take_debug_output(&self) -> Vec<String>
+    {
+        Vec::new()
+    }
+
+    /// WebGL has no debug groups, so this does nothing.
+    pub fn push_debug_group(&self, _message: &str)
+    {
+    }
+
+    pub fn pop_debug_group(&self)
+    {
+    }
+
+    /// WebGL has no object labels, so this does nothing.
+    pub fn object_label<T>(&self, _identifier: u32, _name: &T, _label: &str)
+    {
     }
 
     pub fn finish(&self)
@@ -99,7 +173,7 @@ impl Glstruct {
 
     pub fn compile_shader(&self, source: &str, shader: &Shader)
     {
-        let header = "#version 300 es\nprecision highp float;\nprecision highp int;\nprecision highp sampler2DArray;\n";
+        let header = "#version 300 es\nprecision highp float;\nprecision highp int;\nprecision highp sampler2DArray;\nprecision highp sampler2DShadow;\n";
         let s: &str = &[header, source].concat();
 
         self.inner.shader_source(shader, s);
@@ -224,6 +298,11 @@ impl Glstruct {
 
     }
 
+    pub fn delete_sampler(&self, sampler: &Sampler)
+    {
+        self.inner.delete_sampler(Some(sampler));
+    }
+
     pub fn framebuffer_texture_2d(&self, target: u32, attachment: u32, textarget: u32, texture: &Texture, level: u32)
     {
         self.inner.framebuffer_texture_2d(target, attachment, textarget, Some(texture), level as i32);
@@ -234,6 +313,11 @@ impl Glstruct {
         self.inner.framebuffer_texture_layer(target, attachment, Some(texture), level as i32, layer as i32);
     }
 
+    pub fn scissor(&self, x: i32, y: i32, width: usize, height: usize)
+    {
+        self.inner.scissor(x, y, width as i32, height as i32);
+    }
+
     pub fn viewport(&self, x: i32, y: i32, width: usize, height: usize)
     {
         self.inner.viewport(x, y, width as i32, height as i32);
@@ -295,6 +379,23 @@ impl Glstruct {
                                     dst_x0 as i32, dst_y0 as i32, dst_x1 as i32, dst_y1 as i32, mask, filter);
     }
 
+    pub fn read_pixels_with_u8_data(&self, x: u32, y: u32, width: u32, height: u32, format: u32, data_type: u32, dst_data: &mut [u8])
+    {
+        self.inner.read_pixels_with_opt_u8_array(x as i32, y as i32, width as i32, height as i32, format, data_type, Some(dst_data)).unwrap();
+    }
+
+    pub fn read_pixels_with_f32_data(&self, x: u32, y: u32, width: u32, height: u32, format: u32, data_type: u32, dst_data: &mut [f32])
+    {
+        use wasm_bindgen::JsCast;
+        let memory_buffer = wasm_bindgen::memory()
+            .dyn_into::<js_sys::WebAssembly::Memory>().unwrap()
+            .buffer();
+        let data_location = dst_data.as_ptr() as u32 / 4;
+        let array = js_sys::Float32Array::new(&memory_buffer)
+            .subarray(data_location, data_location + dst_data.len() as u32);
+        self.inner.read_pixels_with_array_buffer_view(x as i32, y as i32, width as i32, height as i32, format, data_type, &array).unwrap();
+    }
+
     pub fn draw_buffers(&self, draw_buffers: &[u32])
     {
         use wasm_bindgen::JsCast;
@@ -308,6 +409,21 @@ impl Glstruct {
         self.inner.draw_buffers(&array);
     }
 
+    pub fn read_buffer(&self, source: u32)
+    {
+        self.inner.read_buffer(source);
+    }
+
+    pub fn renderbuffer_storage_multisample(&self, target: u32, samples: u32, internalformat: u32, width: u32, height: u32)
+    {
+        self.inner.renderbuffer_storage_multisample(target, samples as i32, internalformat, width as i32, height as i32);
+    }
+
+    pub fn delete_renderbuffer(&self, renderbuffer: &Renderbuffer)
+    {
+        self.inner.delete_renderbuffer(Some(renderbuffer));
+    }
+
     pub fn check_framebuffer_status(&self) -> Result<(), String>
     {
         let status = self.inner.check_framebuffer_status(consts::FRAMEBUFFER);
@@ -384,6 +500,31 @@ impl Glstruct {
         self.inner.get_active_uniform(program, index).unwrap()
     }
 
+    pub fn get_parameter_f32(&self, pname: u32) -> f32
+    {
+        self.inner.get_parameter(pname).ok().and_then(|v| v.as_f64()).unwrap_or(0.0) as f32
+    }
+
+    pub fn get_string(&self, pname: u32) -> String
+    {
+        self.inner.get_parameter(pname).ok().and_then(|v| v.as_string()).unwrap_or_default()
+    }
+
+    pub fn get_extensions(&self) -> Vec<String>
+    {
+        self.inner.get_supported_extensions().map(|extensions| extensions.iter().filter_map(|e| e.as_string()).collect()).unwrap_or_default()
+    }
+
+    /// Enables the extension if it is supported, so it is enabled again when a lost context is restored.
+    pub fn supports_extension(&self, name: &str) -> bool
+    {
+        let supported = self.inner.get_extension(name).map(|e| e.is_some()).unwrap_or(false);
+        if supported && !self.enabled_extensions.borrow().iter().any(|e| e == name) {
+            self.enabled_extensions.borrow_mut().push(name.to_string());
+        }
+        supported
+    }
+
     pub fn fence_sync(&self) -> Sync {
         self.inner.fence_sync(consts::SYNC_GPU_COMMANDS_COMPLETE, 0).unwrap()
     }
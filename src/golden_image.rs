@@ -0,0 +1,221 @@
+use crate::*;
+
+#[derive(Debug)]
+pub enum Error {
+    Core(core::Error),
+    Image(image::ImageError),
+    IO(std::io::Error),
+    MissingReference {path: String},
+    SizeMismatch {message: String},
+    Mismatch {reference_path: String, diff_path: String, result: ComparisonResult}
+}
+
+impl From<core::Error> for Error {
+    fn from(other: core::Error) -> Self {
+        Error::Core(other)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(other: image::ImageError) -> Self {
+        Error::Image(other)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(other: std::io::Error) -> Self {
+        Error::IO(other)
+    }
+}
+
+/// Setting this environment variable makes `compare_with_reference` overwrite the reference images instead of comparing with them.
+pub const UPDATE_REFERENCE_VARIABLE: &str = "THREE_D_UPDATE_GOLDEN_IMAGES";
+
+/// When two images are considered equal.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ComparisonSettings {
+    /// The largest difference in any color channel (0-255) for two pixels to be considered equal.
+    pub pixel_tolerance: u8,
+    /// The largest fraction of pixels (0-1) that may differ by more than `pixel_tolerance`.
+    pub max_different_pixels: f32,
+    /// The smallest accepted peak signal-to-noise ratio in decibel.
+    pub min_psnr: Option<f32>,
+    /// The smallest accepted structural similarity index (-1 to 1, 1 means identical).
+    pub min_ssim: Option<f32>
+}
+
+impl Default for ComparisonSettings {
+    fn default() -> Self
+    {
+        ComparisonSettings {pixel_tolerance: 2, max_different_pixels: 0.001, min_psnr: None, min_ssim: Some(0.98)}
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ComparisonResult {
+    pub different_pixels: usize,
+    pub max_difference: u8,
+    /// Peak signal-to-noise ratio in decibel, infinite if the images are identical.
+    pub psnr: f32,
+    /// Mean structural similarity index of the luminance, computed over 8x8 pixel windows.
+    pub ssim: f32,
+    total_pixels: usize
+}
+
+impl ComparisonResult {
+    pub fn different_pixels_fraction(&self) -> f32
+    {
+        self.different_pixels as f32 / self.total_pixels.max(1) as f32
+    }
+
+    pub fn passed(&self, settings: &ComparisonSettings) -> bool
+    {
+        self.different_pixels_fraction() <= settings.max_different_pixels
+            && settings.min_psnr.map(|min| self.psnr >= min).unwrap_or(true)
+            && settings.min_ssim.map(|min| self.ssim >= min).unwrap_or(true)
+    }
+}
+
+/// Renders with `render` to the screen (which is offscreen when using a headless context) and compares the result to the reference PNG image at `reference_path`.
+/// On failure, an image highlighting the differing pixels in red is written next to the reference with the extension `.diff.png`
+/// together with the rendered image with the extension `.actual.png`.
+/// If the environment variable `THREE_D_UPDATE_GOLDEN_IMAGES` is set, the reference image is overwritten with the rendered image instead.
+pub fn compare_with_reference(gl: &Gl, reference_path: &str, width: usize, height: usize,
+                              settings: &ComparisonSettings, render: &dyn Fn()) -> Result<ComparisonResult, Error>
+{
+    Screen::write(gl, 0, 0, width, height, Some(&vec4(0.0, 0.0, 0.0, 1.0)), Some(1.0), render)?;
+    let actual = flip_vertically(&Screen::read_color(gl, 0, 0, width, height)?, width, height);
+
+    if std::env::var_os(UPDATE_REFERENCE_VARIABLE).is_some() {
+        image::save_buffer(reference_path, &actual, width as u32, height as u32, image::RGB(8))?;
+        return compare_images(&actual, &actual, width, height, settings.pixel_tolerance);
+    }
+
+    if !std::path::Path::new(reference_path).exists() {
+        Err(Error::MissingReference {path: reference_path.to_string()})?
+    }
+    let reference = image::open(reference_path)?.to_rgb();
+    let (diff_path, actual_path) = output_paths(reference_path);
+    if reference.width() as usize != width || reference.height() as usize != height {
+        image::save_buffer(&actual_path, &actual, width as u32, height as u32, image::RGB(8))?;
+        Err(Error::SizeMismatch {message:
+            format!("The reference image has size {}x{} but the rendered image has size {}x{}", reference.width(), reference.height(), width, height)})?
+    }
+
+    let reference = reference.into_raw();
+    let result = compare_images(&reference, &actual, width, height, settings.pixel_tolerance)?;
+    if !result.passed(settings) {
+        let diff = diff_image(&reference, &actual, width, height, settings.pixel_tolerance)?;
+        image::save_buffer(&diff_path, &diff, width as u32, height as u32, image::RGB(8))?;
+        image::save_buffer(&actual_path, &actual, width as u32, height as u32, image::RGB(8))?;
+        Err(Error::Mismatch {reference_path: reference_path.to_string(), diff_path, result})?
+    }
+    Ok(result)
+}
+
+/// Compares two RGB images with 8 bits per channel and the given size, pixels are counted as different if any channel differs by more than `pixel_tolerance`.
+pub fn compare_images(reference: &[u8], actual: &[u8], width: usize, height: usize, pixel_tolerance: u8) -> Result<ComparisonResult, Error>
+{
+    check_sizes(reference, actual, width, height)?;
+    let total_pixels = width * height;
+    let mut different_pixels = 0;
+    let mut max_difference = 0;
+    let mut squared_error_sum = 0.0;
+    for pixel in 0..total_pixels {
+        let mut pixel_difference = 0;
+        for channel in 0..3 {
+            let i = pixel * 3 + channel;
+            let difference = reference[i].abs_diff(actual[i]);
+            pixel_difference = pixel_difference.max(difference);
+            squared_error_sum += (difference as f64) * (difference as f64);
+        }
+        if pixel_difference > pixel_tolerance {
+            different_pixels += 1;
+        }
+        max_difference = max_difference.max(pixel_difference);
+    }
+
+    let mean_squared_error = squared_error_sum / (total_pixels * 3).max(1) as f64;
+    let psnr = if mean_squared_error == 0.0 { f32::INFINITY } else { (10.0 * (255.0 * 255.0 / mean_squared_error).log10()) as f32 };
+    Ok(ComparisonResult {different_pixels, max_difference, psnr, ssim: ssim(reference, actual, width, height), total_pixels})
+}
+
+/// Returns an RGB image which is a darkened copy of the reference where the pixels that differ by more than the tolerance are red.
+pub fn diff_image(reference: &[u8], actual: &[u8], width: usize, height: usize, tolerance: u8) -> Result<Vec<u8>, Error>
+{
+    check_sizes(reference, actual, width, height)?;
+    let mut diff = Vec::with_capacity(reference.len());
+    for (r, a) in reference.chunks(3).zip(actual.chunks(3)) {
+        let different = r.iter().zip(a.iter()).any(|(r, a)| (*r as i32 - *a as i32).abs() > tolerance as i32);
+        if different {
+            diff.extend_from_slice(&[255, 0, 0]);
+        }
+        else {
+            let luminance = (luminance(r) * 0.3) as u8;
+            diff.extend_from_slice(&[luminance, luminance, luminance]);
+        }
+    }
+    Ok(diff)
+}
+
+fn check_sizes(reference: &[u8], actual: &[u8], width: usize, height: usize) -> Result<(), Error>
+{
+    let length = width * height * 3;
+    if reference.len() != length || actual.len() != length {
+        Err(Error::SizeMismatch {message:
+            format!("Expected RGB images of size {}x{} with {} bytes, but the reference has {} bytes and the actual image has {} bytes",
+                    width, height, length, reference.len(), actual.len())})?
+    }
+    Ok(())
+}
+
+fn ssim(reference: &[u8], actual: &[u8], width: usize, height: usize) -> f32
+{
+    const WINDOW_SIZE: usize = 8;
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let mut sum = 0.0;
+    let mut windows = 0;
+    for window_y in (0..height).step_by(WINDOW_SIZE) {
+        for window_x in (0..width).step_by(WINDOW_SIZE) {
+            let mut values = Vec::new();
+            for y in window_y..(window_y + WINDOW_SIZE).min(height) {
+                for x in window_x..(window_x + WINDOW_SIZE).min(width) {
+                    let i = (y * width + x) * 3;
+                    values.push((luminance(&reference[i..i+3]), luminance(&actual[i..i+3])));
+                }
+            }
+            let n = values.len() as f64;
+            let mean_r = values.iter().map(|v| v.0).sum::<f64>() / n;
+            let mean_a = values.iter().map(|v| v.1).sum::<f64>() / n;
+            let variance_r = values.iter().map(|v| (v.0 - mean_r) * (v.0 - mean_r)).sum::<f64>() / n;
+            let variance_a = values.iter().map(|v| (v.1 - mean_a) * (v.1 - mean_a)).sum::<f64>() / n;
+            let covariance = values.iter().map(|v| (v.0 - mean_r) * (v.1 - mean_a)).sum::<f64>() / n;
+            sum += ((2.0 * mean_r * mean_a + C1) * (2.0 * covariance + C2))
+                / ((mean_r * mean_r + mean_a * mean_a + C1) * (variance_r + variance_a + C2));
+            windows += 1;
+        }
+    }
+    if windows == 0 { 1.0 } else { (sum / windows as f64) as f32 }
+}
+
+fn luminance(rgb: &[u8]) -> f64
+{
+    0.299 * rgb[0] as f64 + 0.587 * rgb[1] as f64 + 0.114 * rgb[2] as f64
+}
+
+fn flip_vertically(pixels: &[u8], width: usize, height: usize) -> Vec<u8>
+{
+    let mut flipped = Vec::with_capacity(pixels.len());
+    for row in (0..height).rev() {
+        flipped.extend_from_slice(&pixels[row * width * 3..(row + 1) * width * 3]);
+    }
+    flipped
+}
+
+fn output_paths(reference_path: &str) -> (String, String)
+{
+    let stem = reference_path.trim_end_matches(".png");
+    (format!("{}.diff.png", stem), format!("{}.actual.png", stem))
+}
@@ -22,7 +22,8 @@ pub use crate::objects::*;
 #[cfg(not(feature = "no-renderer"))]
 pub use crate::effects::*;
 
-#[cfg(any(feature = "glutin-window", feature = "canvas"))]
+#[cfg(all(feature = "image-io", not(target_arch = "wasm32")))]
+pub mod golden_image;
+
 pub mod window;
-#[cfg(any(feature = "glutin-window", feature = "canvas"))]
 pub use window::*;
\ No newline at end of file
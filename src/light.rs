@@ -39,7 +39,7 @@ impl AmbientLight
 pub struct DirectionalLight {
     gl: Gl,
     light_buffer: UniformBuffer,
-    shadow_texture: Option<Texture2D>,
+    shadow_target: Option<RenderTarget>,
     shadow_camera: Option<Camera>
 }
 
@@ -50,7 +50,7 @@ impl DirectionalLight {
         let mut light = DirectionalLight {
             gl: gl.clone(),
             light_buffer: UniformBuffer::new(gl, &[3u32, 1, 3, 1, 16])?,
-            shadow_texture: None,
+            shadow_target: None,
             shadow_camera: None};
 
         light.set_intensity(intensity);
@@ -82,7 +82,7 @@ impl DirectionalLight {
     pub fn clear_shadow_map(&mut self)
     {
         self.shadow_camera = None;
-        self.shadow_texture = None;
+        self.shadow_target = None;
         self.light_buffer.update(3, &[0.0]).unwrap();
     }
 
@@ -90,6 +90,9 @@ impl DirectionalLight {
                                   frustrum_width: f32, frustrum_height: f32, frustrum_depth: f32,
                                   texture_width: usize, texture_height: usize, render_scene: &dyn Fn(&Camera))
     {
+        let _group = DebugGroup::new(&self.gl, "Directional light shadow map");
+        let max_size = capabilities(&self.gl).max_texture_size;
+        let (texture_width, texture_height) = (texture_width.min(max_size), texture_height.min(max_size));
         let direction = self.direction();
         let up = compute_up_direction(direction);
 
@@ -100,18 +103,19 @@ impl DirectionalLight {
         state::depth_write(&self.gl, true);
         state::depth_test(&self.gl, state::DepthTestType::LessOrEqual);
 
-        self.shadow_texture = Some(Texture2D::new(&self.gl, texture_width, texture_height,
-                                                        Interpolation::Nearest, Interpolation::Nearest, None, // Linear filtering is not working on web
-                                                        Wrapping::ClampToEdge, Wrapping::ClampToEdge, Format::Depth32F).unwrap());
-        RenderTarget::write_to_depth(&self.gl, 0, 0, texture_width, texture_height, Some(1.0),
-            self.shadow_texture.as_ref(),
+        if self.shadow_target.as_ref().map(|t| t.width != texture_width || t.height != texture_height).unwrap_or(true) {
+            self.shadow_target = Some(new_shadow_target(&self.gl, texture_width, texture_height));
+        }
+        state::polygon_offset(&self.gl, Some(state::PolygonOffset {factor: 2.0, units: 4.0}));
+        self.shadow_target.as_ref().unwrap().write(None, Some(1.0),
             &|| render_scene(self.shadow_camera.as_ref().unwrap())).unwrap();
+        state::polygon_offset(&self.gl, None);
         self.light_buffer.update(3, &[1.0]).unwrap();
     }
 
     pub(crate) fn shadow_map(&self) -> Option<&Texture2D>
     {
-        self.shadow_texture.as_ref()
+        self.shadow_target.as_ref().and_then(|t| t.depth_attachment()).and_then(|a| a.as_texture_2d())
     }
 
     pub(crate) fn buffer(&self) -> &UniformBuffer
@@ -169,7 +173,7 @@ impl PointLight {
 pub struct SpotLight {
     gl: Gl,
     light_buffer: UniformBuffer,
-    shadow_texture: Option<Texture2D>,
+    shadow_target: Option<RenderTarget>,
     shadow_camera: Option<Camera>
 }
 
@@ -182,7 +186,7 @@ impl SpotLight {
         let mut light = SpotLight {
             gl: gl.clone(),
             light_buffer: UniformBuffer::new(gl, &uniform_sizes)?,
-            shadow_texture: None,
+            shadow_target: None,
             shadow_camera: None
         };
         light.set_intensity(intensity);
@@ -241,13 +245,15 @@ impl SpotLight {
     pub fn clear_shadow_map(&mut self)
     {
         self.shadow_camera = None;
-        self.shadow_texture = None;
+        self.shadow_target = None;
         self.light_buffer.update(9, &[0.0]).unwrap();
     }
 
     pub fn generate_shadow_map<F>(&mut self, frustrum_depth: f32, texture_size: usize, render_scene: &F)
         where F: Fn(&Camera)
     {
+        let _group = DebugGroup::new(&self.gl, "Spot light shadow map");
+        let texture_size = texture_size.min(capabilities(&self.gl).max_texture_size);
         let position = self.position();
         let direction = self.direction();
         let up = compute_up_direction(direction);
@@ -260,17 +266,19 @@ impl SpotLight {
         state::depth_write(&self.gl, true);
         state::depth_test(&self.gl, state::DepthTestType::LessOrEqual);
 
-        self.shadow_texture = Some(Texture2D::new(&self.gl, texture_size, texture_size,
-                                                        Interpolation::Nearest, Interpolation::Nearest, None, // Linear filtering is not working on web
-                                                        Wrapping::ClampToEdge, Wrapping::ClampToEdge, Format::Depth32F).unwrap());
-        RenderTarget::write_to_depth(&self.gl, 0, 0, texture_size, texture_size, Some(1.0),
-            self.shadow_texture.as_ref(), &|| render_scene(self.shadow_camera.as_ref().unwrap())).unwrap();
+        if self.shadow_target.as_ref().map(|t| t.width != texture_size || t.height != texture_size).unwrap_or(true) {
+            self.shadow_target = Some(new_shadow_target(&self.gl, texture_size, texture_size));
+        }
+        state::polygon_offset(&self.gl, Some(state::PolygonOffset {factor: 2.0, units: 4.0}));
+        self.shadow_target.as_ref().unwrap().write(None, Some(1.0),
+            &|| render_scene(self.shadow_camera.as_ref().unwrap())).unwrap();
+        state::polygon_offset(&self.gl, None);
         self.light_buffer.update(9, &[1.0]).unwrap();
     }
 
     pub(crate) fn shadow_map(&self) -> Option<&Texture2D>
     {
-        self.shadow_texture.as_ref()
+        self.shadow_target.as_ref().and_then(|t| t.depth_attachment()).and_then(|a| a.as_texture_2d())
     }
 
     pub(crate) fn buffer(&self) -> &UniformBuffer
@@ -279,6 +287,15 @@ impl SpotLight {
     }
 }
 
+fn new_shadow_target(gl: &Gl, width: usize, height: usize) -> RenderTarget
+{
+    let filter = if capabilities(gl).depth_linear_filtering { Interpolation::Linear } else { Interpolation::Nearest };
+    let texture = Texture2D::new(gl, width, height, filter, filter, None,
+                                 Wrapping::ClampToEdge, Wrapping::ClampToEdge, Format::Depth32F).unwrap();
+    texture.set_label("Shadow map");
+    RenderTarget::new_depth(gl, texture).unwrap()
+}
+
 fn shadow_matrix(camera: &Camera) -> Mat4
 {
     let bias_matrix = crate::Mat4::new(
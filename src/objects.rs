@@ -1,16 +1,24 @@
 pub mod vertices;
 pub mod edges;
 pub mod mesh;
+#[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+pub mod mesh_batch;
 pub mod cpu_mesh;
 pub mod skybox;
 pub mod imposter;
+pub mod reflection_probe;
+pub mod upload_queue;
 
 pub use crate::objects::vertices::*;
 pub use crate::objects::edges::*;
 pub use crate::objects::mesh::*;
+#[cfg(not(any(target_arch = "wasm32", feature = "gles")))]
+pub use crate::objects::mesh_batch::*;
 pub use crate::objects::cpu_mesh::*;
 pub use crate::objects::skybox::*;
 pub use crate::objects::imposter::*;
+pub use crate::objects::reflection_probe::*;
+pub use crate::objects::upload_queue::*;
 
 #[derive(Debug)]
 pub enum Error {
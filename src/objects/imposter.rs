@@ -23,6 +23,7 @@ impl Imposter {
         let mut camera = camera::Camera::new_orthographic(gl, center + vec3(0.0, 0.0, -1.0),
                           center, vec3(0.0, 1.0, 0.0), width, height, 4.0*(width+height));
 
+        let max_texture_size = max_texture_size.min(capabilities(gl).max_texture_size);
         let texture_width = (max_texture_size as f32 * (width / height).min(1.0)) as usize;
         let texture_height = (max_texture_size as f32 * (height / width).min(1.0)) as usize;
         let texture = Texture2DArray::new(gl, texture_width, texture_height, NO_VIEW_ANGLES*2,
@@ -37,16 +38,21 @@ impl Imposter {
         state::cull(&gl, state::CullType::None);
         state::blend(&gl, state::BlendType::None);
 
+        let mut render_target = RenderTarget::new(gl,
+                                  Some(Attachment::Texture2DArray {texture, layers: vec![0, NO_VIEW_ANGLES]}),
+                                  Some(Attachment::Texture2DArray {texture: depth_texture, layers: vec![0]})).unwrap();
         for i in 0..NO_VIEW_ANGLES {
             let angle = i as f32 * 2.0 * PI / NO_VIEW_ANGLES as f32;
             camera.set_view(center + width * vec3(f32::sin(-angle), 0.0, f32::cos(-angle)),
                             center, vec3(0.0, 1.0, 0.0));
-            RenderTarget::write_array(gl, 0, 0, texture_width, texture_height,
-                              Some(&vec4(0.0, 0.0, 0.0, 0.0)), Some(1.0),
-                              Some(&texture), Some(&depth_texture),
-                              2, &|channel| { i + channel * NO_VIEW_ANGLES },
-                              i, &|| render(&camera)).unwrap();
+            render_target.set_color_layers(&[i, i + NO_VIEW_ANGLES]).unwrap();
+            render_target.set_depth_layer(i).unwrap();
+            render_target.write(Some(&vec4(0.0, 0.0, 0.0, 0.0)), Some(1.0), &|| render(&camera)).unwrap();
         }
+        let texture = match render_target.into_attachments().0 {
+            Some(Attachment::Texture2DArray {texture, ..}) => texture,
+            _ => unreachable!()
+        };
 
         let xmin = center.x - 0.5 * width;
         let xmax = center.x + 0.5 * width;
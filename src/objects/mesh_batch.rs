@@ -0,0 +1,95 @@
+
+use crate::*;
+
+/// Many static meshes which share the material and program of a `Mesh` without a texture, and which are rendered with a single draw call.
+/// The geometries are merged into one vertex and index buffer and each mesh is drawn by a command in a `DrawIndirectBuffer`,
+/// where the transformation of a mesh is read from an instanced vertex attribute at the index given by the base instance of its command.
+/// Requires OpenGL 4.3, see `Capabilities::multi_draw_indirect`.
+pub struct MeshBatch {
+    position_buffer: VertexBuffer,
+    normal_buffer: VertexBuffer,
+    index_buffer: ElementBuffer,
+    transformation_buffer: VertexBuffer,
+    indirect_buffer: DrawIndirectBuffer,
+    program: program::Program,
+    pub color: Vec3,
+    pub diffuse_intensity: f32,
+    pub specular_intensity: f32,
+    pub specular_power: f32
+}
+
+impl MeshBatch
+{
+    /// Each mesh is given by its indices, positions and normals as in `Mesh::new`, together with its transformation.
+    pub fn new(gl: &Gl, meshes: &[(&[u32], &[f32], &[f32])], transformations: &[Mat4]) -> Result<Self, objects::Error>
+    {
+        if meshes.len() != transformations.len() {
+            Err(core::Error::FailedToCreateBuffer {message: format!("The number of transformations ({}) does not match the number of meshes ({})", transformations.len(), meshes.len())})?;
+        }
+        let mut indices = Vec::new();
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut commands = Vec::new();
+        for (i, (mesh_indices, mesh_positions, mesh_normals)) in meshes.iter().enumerate() {
+            commands.push(DrawElementsIndirectCommand {count: mesh_indices.len() as u32, instance_count: 1, first_index: indices.len() as u32,
+                base_vertex: (positions.len() / 3) as i32, base_instance: i as u32});
+            indices.extend_from_slice(mesh_indices);
+            positions.extend_from_slice(mesh_positions);
+            normals.extend_from_slice(mesh_normals);
+        }
+
+        let position_buffer = VertexBuffer::new_with_static_f32(gl, &positions)?;
+        let normal_buffer = VertexBuffer::new_with_static_f32(gl, &normals)?;
+        let index_buffer = ElementBuffer::new_with_u32(gl, &indices)?;
+        let transformation_buffer = VertexBuffer::new_with_dynamic_f32(gl, &transformation_data(transformations))?;
+        let indirect_buffer = DrawIndirectBuffer::new(gl, &commands)?;
+
+        let program = program::Program::from_source(gl,
+                                                    include_str!("shaders/mesh_batch.vert"),
+                                                    include_str!("shaders/shaded.frag"))?;
+
+        Ok(MeshBatch { position_buffer, normal_buffer, index_buffer, transformation_buffer, indirect_buffer, program, color: vec3(1.0, 1.0, 1.0),
+            diffuse_intensity: 0.5, specular_intensity: 0.2, specular_power: 6.0 })
+    }
+
+    /// Sets the transformation of each mesh, in the same order as the meshes were given to `new`.
+    pub fn update_transformations(&mut self, transformations: &[Mat4]) -> Result<(), objects::Error>
+    {
+        if transformations.len() != self.count() {
+            Err(core::Error::BufferUpdateFailed {message: format!("The number of transformations ({}) does not match the number of meshes ({})", transformations.len(), self.count())})?;
+        }
+        self.transformation_buffer.fill_with_dynamic_f32(&transformation_data(transformations));
+        Ok(())
+    }
+
+    /// The number of meshes in the batch.
+    pub fn count(&self) -> usize
+    {
+        self.indirect_buffer.count()
+    }
+
+    pub fn render(&self, camera: &camera::Camera)
+    {
+        self.program.add_uniform_float("diffuse_intensity", &self.diffuse_intensity).unwrap();
+        self.program.add_uniform_float("specular_intensity", &self.specular_intensity).unwrap();
+        self.program.add_uniform_float("specular_power", &self.specular_power).unwrap();
+        self.program.add_uniform_int("use_texture", &0).unwrap();
+        self.program.add_uniform_vec3("color", &self.color).unwrap();
+
+        self.program.use_uniform_block(camera.matrix_buffer(), "Camera");
+
+        self.program.use_attribute_vec3_float(&self.position_buffer, "position").unwrap();
+        self.program.use_attribute_vec3_float(&self.normal_buffer, "normal").unwrap();
+        self.program.use_attribute_mat4_float_divisor(&self.transformation_buffer, "transformation", 1).unwrap();
+
+        self.program.multi_draw_elements_indirect(&self.index_buffer, &self.indirect_buffer);
+    }
+}
+
+fn transformation_data(transformations: &[Mat4]) -> Vec<f32>
+{
+    transformations.iter().flat_map(|transformation| {
+        let data: &[f32; 16] = transformation.as_ref();
+        data.to_vec()
+    }).collect()
+}
@@ -0,0 +1,67 @@
+use crate::*;
+use crate::core::Error;
+
+/// Captures the scene surrounding a point into a cube map texture which can be used as the texture of a `Skybox` or as an environment map by reflective materials.
+pub struct ReflectionProbe {
+    gl: Gl,
+    camera: Camera,
+    position: Vec3,
+    render_target: RenderTarget
+}
+
+impl ReflectionProbe
+{
+    pub fn new(gl: &Gl, position: &Vec3, texture_size: usize, format: Format, z_near: f32, z_far: f32) -> Result<ReflectionProbe, Error>
+    {
+        let texture = TextureCubeMap::new(gl, texture_size, texture_size, Interpolation::Linear, Interpolation::Linear, Some(Interpolation::Linear),
+                                          Wrapping::ClampToEdge, Wrapping::ClampToEdge, Wrapping::ClampToEdge, format)?;
+        let depth_texture = TextureCubeMap::new(gl, texture_size, texture_size, Interpolation::Nearest, Interpolation::Nearest, None,
+                                                Wrapping::ClampToEdge, Wrapping::ClampToEdge, Wrapping::ClampToEdge, Format::Depth32F)?;
+        let side = CubeMapSide::Right;
+        let camera = Camera::new_perspective(gl, *position, position + side.direction(), side.up(),
+                                             degrees(90.0), 1.0, z_near, z_far);
+        let render_target = RenderTarget::new(gl,
+                                              Some(Attachment::TextureCubeMap {texture, side, mip_level: 0}),
+                                              Some(Attachment::TextureCubeMap {texture: depth_texture, side, mip_level: 0}))?;
+        Ok(ReflectionProbe { gl: gl.clone(), camera, position: *position, render_target })
+    }
+
+    pub fn position(&self) -> &Vec3
+    {
+        &self.position
+    }
+
+    pub fn set_position(&mut self, position: &Vec3)
+    {
+        self.position = *position;
+    }
+
+    /// Renders the scene, using `render_scene`, from the position of the probe in the direction of each of the six sides of the cube map.
+    pub fn update(&mut self, clear_color: &Vec4, render_scene: &dyn Fn(&Camera)) -> Result<(), Error>
+    {
+        state::depth_write(&self.gl, true);
+        state::depth_test(&self.gl, state::DepthTestType::LessOrEqual);
+
+        for side in CubeMapSide::all().iter() {
+            self.camera.set_view(self.position, self.position + side.direction(), side.up());
+            let camera = &self.camera;
+            self.render_target.set_cube_map_side(*side, 0)?;
+            self.render_target.write(Some(clear_color), Some(1.0), &|| render_scene(camera))?;
+        }
+        self.render_target.generate_mip_maps();
+        Ok(())
+    }
+
+    pub fn texture(&self) -> &TextureCubeMap
+    {
+        self.render_target.color_attachment().and_then(|attachment| attachment.as_texture_cube_map()).unwrap()
+    }
+
+    pub fn into_texture(self) -> TextureCubeMap
+    {
+        match self.render_target.into_attachments().0 {
+            Some(Attachment::TextureCubeMap {texture, ..}) => texture,
+            _ => unreachable!()
+        }
+    }
+}
@@ -0,0 +1,23 @@
+layout (std140) uniform Camera
+{
+    mat4 viewProjection;
+    mat4 view;
+    mat4 projection;
+    vec3 position;
+    float padding;
+} camera;
+
+in vec3 position;
+in vec3 normal;
+in mat4 transformation;
+
+out vec3 pos;
+out vec3 nor;
+
+void main()
+{
+    vec4 worldPosition = transformation * vec4(position, 1.);
+    nor = mat3(transpose(inverse(transformation))) * normal;
+    pos = worldPosition.xyz;
+    gl_Position = camera.viewProjection * worldPosition;
+}
@@ -0,0 +1,174 @@
+use crate::*;
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// Data which is prepared on the CPU, for example on a worker thread, and uploaded to the GPU by an `UploadQueue`.
+pub enum Upload {
+    #[cfg(feature = "3d-io")]
+    Mesh(CPUMesh),
+    Texture(CPUTexture),
+    Program(ProgramSource)
+}
+
+impl Upload {
+    /// The size of the data which is uploaded, which for programs is the size of the shader sources.
+    pub fn bytes(&self) -> usize
+    {
+        match self {
+            #[cfg(feature = "3d-io")]
+            Upload::Mesh(mesh) => (mesh.indices.len() + mesh.positions.len() + mesh.normals.len()) * 4,
+            Upload::Texture(texture) => texture.bytes(),
+            Upload::Program(program) => program.vertex_shader.len() + program.fragment_shader.len()
+        }
+    }
+
+    fn upload(&self, gl: &Gl) -> Result<Uploaded, objects::Error>
+    {
+        Ok(match self {
+            #[cfg(feature = "3d-io")]
+            Upload::Mesh(mesh) => Uploaded::Mesh(mesh.to_mesh(gl)?),
+            Upload::Texture(texture) => Uploaded::Texture(texture.to_texture(gl)?),
+            Upload::Program(program) => Uploaded::Program(program.to_program(gl)?)
+        })
+    }
+}
+
+pub enum Uploaded {
+    #[cfg(feature = "3d-io")]
+    Mesh(Mesh),
+    Texture(Texture2D),
+    Program(Program)
+}
+
+/// Uploads meshes, textures and programs which are prepared on worker threads, since the context and all objects created from it can only be used on the render thread.
+/// The worker threads send the data together with a key through a `sender` and the render thread calls `upload` once per frame,
+/// which uploads a limited amount of data so a frame is not blocked by a large number of uploads.
+pub struct UploadQueue<K> {
+    sender: Sender<(K, Upload)>,
+    receiver: Receiver<(K, Upload)>,
+    pending: VecDeque<(K, Upload)>
+}
+
+impl<K: Send> UploadQueue<K> {
+    pub fn new() -> UploadQueue<K>
+    {
+        let (sender, receiver) = channel();
+        UploadQueue { sender, receiver, pending: VecDeque::new() }
+    }
+
+    /// A sender which can be moved to a worker thread.
+    pub fn sender(&self) -> Sender<(K, Upload)>
+    {
+        self.sender.clone()
+    }
+
+    pub fn push(&mut self, key: K, upload: Upload)
+    {
+        self.pending.push_back((key, upload));
+    }
+
+    /// The number of uploads which have been received and are waiting to be uploaded.
+    pub fn len(&mut self) -> usize
+    {
+        self.receive();
+        self.pending.len()
+    }
+
+    pub fn is_empty(&mut self) -> bool
+    {
+        self.len() == 0
+    }
+
+    /// Uploads the received data in the order it was received as long as at most `max_bytes` are uploaded in total,
+    /// and returns the created objects together with the keys they were sent with.
+    /// Compiling a program takes long compared to the size of its sources, so the upload also stops before compiling more than `max_programs` programs.
+    /// At least one upload is done each time, so data larger than `max_bytes` is also uploaded.
+    pub fn upload(&mut self, gl: &Gl, max_bytes: usize, max_programs: usize) -> Vec<(K, Result<Uploaded, objects::Error>)>
+    {
+        self.receive();
+        let mut uploaded = Vec::new();
+        let mut bytes = 0;
+        let mut programs = 0;
+        while let Some((_, next)) = self.pending.front() {
+            let is_program = matches!(next, Upload::Program(_));
+            let next_bytes = next.bytes();
+            if !uploaded.is_empty() && (bytes + next_bytes > max_bytes || (is_program && programs >= max_programs)) {
+                break;
+            }
+            let (key, upload) = self.pending.pop_front().unwrap();
+            if is_program {
+                programs += 1;
+            }
+            bytes += next_bytes;
+            uploaded.push((key, upload.upload(gl)));
+        }
+        uploaded
+    }
+
+    fn receive(&mut self)
+    {
+        while let Ok(upload) = self.receiver.try_recv() {
+            self.pending.push_back(upload);
+        }
+    }
+}
+
+impl<K: Send> Default for UploadQueue<K> {
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    fn texture(bytes: usize) -> Upload
+    {
+        Upload::Texture(CPUTexture { width: bytes as u32 / 4, height: 1, data: TextureData::U8(vec![0; bytes]),
+            min_filter: Interpolation::Nearest, mag_filter: Interpolation::Nearest, mip_map_filter: None,
+            wrap_s: Wrapping::ClampToEdge, wrap_t: Wrapping::ClampToEdge })
+    }
+
+    fn program() -> Upload
+    {
+        Upload::Program(ProgramSource::new("void main() {}", "void main() {}"))
+    }
+
+    fn keys(uploaded: Vec<(u32, Result<Uploaded, objects::Error>)>) -> Vec<u32>
+    {
+        uploaded.into_iter().map(|(key, result)| { assert!(result.is_ok()); key }).collect()
+    }
+
+    #[test]
+    fn upload_stops_before_exceeding_the_byte_limit()
+    {
+        let gl = gl::Glstruct::new(Box::new(gl::RecordingBackend::new()));
+        let mut queue = UploadQueue::new();
+        queue.push(0, texture(400));
+        queue.push(1, texture(400));
+        queue.push(2, texture(2000));
+        queue.push(3, texture(400));
+
+        assert_eq!(keys(queue.upload(&gl, 1000, 4)), vec![0, 1]);
+        assert_eq!(keys(queue.upload(&gl, 1000, 4)), vec![2]);
+        assert_eq!(keys(queue.upload(&gl, 1000, 4)), vec![3]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn upload_compiles_at_most_max_programs()
+    {
+        let gl = gl::Glstruct::new(Box::new(gl::RecordingBackend::new()));
+        let mut queue = UploadQueue::new();
+        queue.push(0, program());
+        queue.push(1, texture(400));
+        queue.push(2, program());
+        queue.push(3, program());
+
+        assert_eq!(keys(queue.upload(&gl, 1000, 1)), vec![0, 1]);
+        assert_eq!(keys(queue.upload(&gl, 1000, 1)), vec![2]);
+        assert_eq!(keys(queue.upload(&gl, 1000, 0)), vec![3]);
+    }
+}
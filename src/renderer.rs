@@ -22,9 +22,10 @@ pub struct DeferredPipeline {
     point_light_effect: ImageEffect,
     spot_light_effect: ImageEffect,
     debug_effect: Option<ImageEffect>,
+    copy_depth_effect: ImageEffect,
     debug_type: DebugType,
-    geometry_pass_texture: Option<Texture2DArray>,
-    geometry_pass_depth_texture: Option<Texture2DArray>
+    shadow_sampler: Sampler,
+    geometry_pass_target: RenderTarget
 }
 
 
@@ -32,6 +33,9 @@ impl DeferredPipeline
 {
     pub fn new(gl: &Gl) -> Result<DeferredPipeline, Error>
     {
+        if capabilities(gl).max_draw_buffers < 2 {
+            Err(core::Error::FailedToCreateFramebuffer {message: "The geometry pass requires at least two draw buffers".to_string()})?
+        }
         let renderer = DeferredPipeline {
             gl: gl.clone(),
             ambient_light_effect: ImageEffect::new(gl, include_str!("shaders/ambient_light.frag"))?,
@@ -47,13 +51,10 @@ impl DeferredPipeline
                                                                        &include_str!("shaders/shadow_shared.frag"),
                                                                        &include_str!("shaders/spot_light.frag")))?,
             debug_effect: None,
+            copy_depth_effect: ImageEffect::new(gl, include_str!("shaders/copy_depth.frag"))?,
             debug_type: DebugType::NONE,
-            geometry_pass_texture: Some(Texture2DArray::new(gl, 1, 1, 2,
-                  Interpolation::Nearest, Interpolation::Nearest, None, Wrapping::ClampToEdge,
-                  Wrapping::ClampToEdge, Format::RGBA8)?),
-            geometry_pass_depth_texture: Some(Texture2DArray::new(gl, 1, 1, 1,
-                    Interpolation::Nearest, Interpolation::Nearest, None,Wrapping::ClampToEdge,
-                    Wrapping::ClampToEdge, Format::Depth32F)?)
+            shadow_sampler: Sampler::new_depth_compare(gl, Interpolation::Linear, state::DepthTestType::Less)?,
+            geometry_pass_target: Self::new_geometry_pass_target(gl, 1, 1)?
         };
 
         renderer.ambient_light_effect.program().use_texture(renderer.geometry_pass_texture(), "gbuffer")?;
@@ -63,27 +64,38 @@ impl DeferredPipeline
 
     pub fn geometry_pass(&mut self, width: usize, height: usize, render_scene: &dyn Fn()) -> Result<(), Error>
     {
+        let _group = DebugGroup::new(&self.gl, "Geometry pass");
         state::depth_write(&self.gl, true);
         state::depth_test(&self.gl, state::DepthTestType::LessOrEqual);
         state::cull(&self.gl, state::CullType::None);
         state::blend(&self.gl, state::BlendType::None);
 
-        self.geometry_pass_texture = Some(Texture2DArray::new(&self.gl, width, height, 2,
+        if self.geometry_pass_target.width != width || self.geometry_pass_target.height != height {
+            self.geometry_pass_target = Self::new_geometry_pass_target(&self.gl, width, height)?;
+        }
+        self.geometry_pass_target.write(Some(&vec4(0.0, 0.0, 0.0, 0.0)), Some(1.0), render_scene)?;
+        Ok(())
+    }
+
+    fn new_geometry_pass_target(gl: &Gl, width: usize, height: usize) -> Result<RenderTarget, Error>
+    {
+        let texture = Texture2DArray::new(gl, width, height, 2,
                   Interpolation::Nearest, Interpolation::Nearest, None, Wrapping::ClampToEdge,
-                  Wrapping::ClampToEdge, Format::RGBA8)?);
-        self.geometry_pass_depth_texture = Some(Texture2DArray::new(&self.gl, width, height, 1,
+                  Wrapping::ClampToEdge, Format::RGBA8)?;
+        let depth_texture = Texture2DArray::new(gl, width, height, 1,
                     Interpolation::Nearest, Interpolation::Nearest, None, Wrapping::ClampToEdge,
-                    Wrapping::ClampToEdge, Format::Depth32F)?);
-        RenderTarget::write_array(&self.gl,0, 0, width, height,
-            Some(&vec4(0.0, 0.0, 0.0, 0.0)), Some(1.0),
-            self.geometry_pass_texture.as_ref(), self.geometry_pass_depth_texture.as_ref(),
-            2, &|channel| {channel},
-            0, render_scene)?;
-        Ok(())
+                    Wrapping::ClampToEdge, Format::Depth32F)?;
+        texture.set_label("Geometry pass color");
+        depth_texture.set_label("Geometry pass depth");
+        let target = RenderTarget::new(gl, Some(Attachment::Texture2DArray {texture, layers: vec![0, 1]}),
+                             Some(Attachment::Texture2DArray {texture: depth_texture, layers: vec![0]}))?;
+        target.set_label("Geometry pass");
+        Ok(target)
     }
 
     pub fn light_pass(&self, camera: &Camera, ambient_light: Option<&AmbientLight>, directional_lights: &[&DirectionalLight], spot_lights: &[&SpotLight], point_lights: &[&PointLight]) -> Result<(), Error>
     {
+        let _group = DebugGroup::new(&self.gl, "Light pass");
         state::depth_write(&self.gl,false);
         state::depth_test(&self.gl, state::DepthTestType::None);
         state::blend(&self.gl, state::BlendType::None);
@@ -99,6 +111,7 @@ impl DeferredPipeline
 
         // Ambient light
         if let Some(light) = ambient_light {
+            let _group = DebugGroup::new(&self.gl, "Ambient light");
             self.ambient_light_effect.program().use_texture(self.geometry_pass_texture(), "gbuffer")?;
             self.ambient_light_effect.program().use_texture(self.geometry_pass_depth_texture(), "depthMap")?;
             self.ambient_light_effect.program().add_uniform_vec3("ambientLight.base.color", &light.color())?;
@@ -109,16 +122,17 @@ impl DeferredPipeline
 
         // Directional light
         for light in directional_lights {
+            let _group = DebugGroup::new(&self.gl, "Directional light");
             self.directional_light_effect.program().use_texture(self.geometry_pass_texture(), "gbuffer")?;
             self.directional_light_effect.program().use_texture(self.geometry_pass_depth_texture(), "depthMap")?;
             self.directional_light_effect.program().add_uniform_vec3("eyePosition", &camera.position())?;
             self.directional_light_effect.program().add_uniform_mat4("viewProjectionInverse", &(camera.get_projection() * camera.get_view()).invert().unwrap())?;
             if let Some(texture) = light.shadow_map() {
-                self.directional_light_effect.program().use_texture(texture, "shadowMap")?;
+                self.directional_light_effect.program().use_texture_with_sampler(texture, &self.shadow_sampler, "shadowMap")?;
             }
             else {
                 let dummy = Texture2D::new(&self.gl, 1, 1, Interpolation::Nearest, Interpolation::Nearest, None,Wrapping::ClampToEdge, Wrapping::ClampToEdge, Format::Depth32F).unwrap();
-                self.directional_light_effect.program().use_texture(&dummy, "shadowMap")?;
+                self.directional_light_effect.program().use_texture_with_sampler(&dummy, &self.shadow_sampler, "shadowMap")?;
             }
             self.directional_light_effect.program().use_uniform_block(light.buffer(), "DirectionalLight");
             self.directional_light_effect.apply();
@@ -127,16 +141,17 @@ impl DeferredPipeline
 
         // Spot lights
         for light in spot_lights {
+            let _group = DebugGroup::new(&self.gl, "Spot light");
             self.spot_light_effect.program().use_texture(self.geometry_pass_texture(), "gbuffer")?;
             self.spot_light_effect.program().use_texture(self.geometry_pass_depth_texture(), "depthMap")?;
             self.spot_light_effect.program().add_uniform_vec3("eyePosition", &camera.position())?;
             self.spot_light_effect.program().add_uniform_mat4("viewProjectionInverse", &(camera.get_projection() * camera.get_view()).invert().unwrap())?;
             if let Some(texture) = light.shadow_map() {
-                self.spot_light_effect.program().use_texture(texture, "shadowMap")?;
+                self.spot_light_effect.program().use_texture_with_sampler(texture, &self.shadow_sampler, "shadowMap")?;
             }
             else {
                 let dummy = Texture2D::new(&self.gl, 1, 1, Interpolation::Nearest, Interpolation::Nearest, None,Wrapping::ClampToEdge, Wrapping::ClampToEdge, Format::Depth32F).unwrap();
-                self.spot_light_effect.program().use_texture(&dummy, "shadowMap")?;
+                self.spot_light_effect.program().use_texture_with_sampler(&dummy, &self.shadow_sampler, "shadowMap")?;
             }
             self.spot_light_effect.program().use_uniform_block(light.buffer(), "SpotLight");
             self.spot_light_effect.apply();
@@ -145,6 +160,7 @@ impl DeferredPipeline
 
         // Point lights
         for light in point_lights {
+            let _group = DebugGroup::new(&self.gl, "Point light");
             self.point_light_effect.program().use_texture(self.geometry_pass_texture(), "gbuffer")?;
             self.point_light_effect.program().use_texture(self.geometry_pass_depth_texture(), "depthMap")?;
             self.point_light_effect.program().add_uniform_vec3("eyePosition", &camera.position())?;
@@ -157,13 +173,34 @@ impl DeferredPipeline
         Ok(())
     }
 
+    /// Writes the depth from the geometry pass into the currently bound render target or screen, without changing the color,
+    /// so that forward rendered objects are depth tested against the deferred rendered scene.
+    /// Unlike `RenderTarget::blit_to_screen`, this does not require the depth formats to be the same.
+    pub fn copy_depth(&self) -> Result<(), Error>
+    {
+        let _group = DebugGroup::new(&self.gl, "Copy depth");
+        state::depth_write(&self.gl, true);
+        state::depth_test(&self.gl, state::DepthTestType::Always);
+        state::blend(&self.gl, state::BlendType::None);
+        state::color_write(&self.gl, false);
+        self.copy_depth_effect.program().use_texture(self.geometry_pass_depth_texture(), "depthMap")?;
+        self.copy_depth_effect.apply();
+        state::color_write(&self.gl, true);
+        Ok(())
+    }
+
+    pub fn geometry_pass_target(&self) -> &RenderTarget
+    {
+        &self.geometry_pass_target
+    }
+
     pub fn geometry_pass_texture(&self) -> &Texture2DArray
     {
-        &self.geometry_pass_texture.as_ref().unwrap()
+        self.geometry_pass_target.color_attachment().and_then(|attachment| attachment.as_texture_2d_array()).unwrap()
     }
     pub fn geometry_pass_depth_texture(&self) -> &Texture2DArray
     {
-        &self.geometry_pass_depth_texture.as_ref().unwrap()
+        self.geometry_pass_target.depth_attachment().and_then(|attachment| attachment.as_texture_2d_array()).unwrap()
     }
 
     pub fn debug_type(&self) -> DebugType
@@ -0,0 +1,6 @@
+uniform sampler2DArray depthMap;
+in vec2 uv;
+void main()
+{
+    gl_FragDepth = texture(depthMap, vec3(uv, 0.0)).r;
+}
@@ -1,12 +1,15 @@
 
-uniform sampler2D shadowMap;
+uniform sampler2DShadow shadowMap;
 
 float is_visible(vec4 shadow_coord, vec2 offset)
 {
     vec2 uv = (shadow_coord.xy + offset)/shadow_coord.w;
     float true_distance = (shadow_coord.z - 0.005)/shadow_coord.w;
-    float shadow_cast_distance = texture(shadowMap, uv).x;
-    return uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0 || shadow_cast_distance > true_distance ? 1.0 : 0.0;
+    if(uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0)
+    {
+        return 1.0;
+    }
+    return texture(shadowMap, vec3(uv, true_distance));
 }
 
 float calculate_shadow(mat4 shadowMVP, vec3 position)
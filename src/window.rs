@@ -3,6 +3,16 @@
 pub mod frame_input;
 pub use frame_input::*;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod frame_capture;
+#[cfg(not(target_arch = "wasm32"))]
+pub use frame_capture::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod embedded;
+#[cfg(not(target_arch = "wasm32"))]
+pub use embedded::*;
+
 #[cfg(all(feature = "glutin-window", not(target_arch = "wasm32")))]
 pub mod glutin_window;
 #[cfg(all(feature = "glutin-window", not(target_arch = "wasm32")))]
@@ -11,4 +21,9 @@ pub use crate::glutin_window::*;
 #[cfg(all(feature = "canvas", target_arch = "wasm32"))]
 pub mod canvas;
 #[cfg(all(feature = "canvas", target_arch = "wasm32"))]
-pub use crate::canvas::*;
\ No newline at end of file
+pub use crate::canvas::*;
+
+#[cfg(all(feature = "headless", target_os = "linux"))]
+pub mod headless;
+#[cfg(all(feature = "headless", target_os = "linux"))]
+pub use crate::headless::HeadlessContext;
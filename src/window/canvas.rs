@@ -28,7 +28,7 @@ struct CanvasContextAttribs {
     // alpha : bool,
 
     /// Whether there's a stencil buffer (at least 8 bits)
-    // stencil : bool,
+    stencil : bool,
 
     /// Whether the canvas paint should be async with event loop
     desynchronized : bool,
@@ -50,6 +50,7 @@ impl Default for CanvasContextAttribs { fn default() -> Self { CanvasContextAttr
     desynchronized : true,
     antialias : true,
     depth : true,
+    stencil : true,
     failIfMajorPerformanceCaveat : true,
     powerPreference : String::from("high-performance"),
     premultipliedAlpha : true,
@@ -59,8 +60,10 @@ impl Default for CanvasContextAttribs { fn default() -> Self { CanvasContextAttr
 pub struct Window
 {
     gl: crate::Gl,
+    context: WebGl2RenderingContext,
     canvas: web_sys::HtmlCanvasElement,
-    window: web_sys::Window
+    window: web_sys::Window,
+    context_lost_callback: Rc<RefCell<Option<Box<dyn FnMut()>>>>
 }
 
 impl Window
@@ -69,27 +72,42 @@ impl Window
     {
         Window::new(title, 512, 512)
     }
-    pub fn new(_title: &str, _width: u32, _height: u32) -> Result<Window, Error>
+    pub fn new(title: &str, width: u32, height: u32) -> Result<Window, Error>
+    {
+        Window::new_with_multisampling(title, width, height, 4)
+    }
+
+    /// The browser decides the number of samples, so any number larger than zero enables anti aliasing of the default framebuffer.
+    pub fn new_with_multisampling(_title: &str, _width: u32, _height: u32, samples: u16) -> Result<Window, Error>
     {
         let window = web_sys::window().ok_or(Error::WindowCreationError {message: "Unable to create web window".to_string()})?;
         let document = window.document().ok_or(Error::WindowCreationError {message: "Unable to get document".to_string()})?;
         let canvas = document.get_element_by_id("canvas").ok_or(Error::WindowCreationError {message: "Unable to get canvas, is the id different from 'canvas'?".to_string()})?;
         let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>().map_err(|e| Error::WindowCreationError {message: format!("Unable to convert to HtmlCanvasElement. Error code: {:?}", e)})?;
 
-        let context_attribs = CanvasContextAttribs::default();
+        let mut context_attribs = CanvasContextAttribs::default();
+        context_attribs.antialias = samples > 0;
         let context_attribs_js = JsValue::from_serde(&context_attribs).map_err(|e| Error::WindowCreationError {message: format!("Failed to setup Canvas context attributes. Error code: {:?}", e)})?;
 
         let context = canvas
             .get_context_with_context_options("webgl2", &context_attribs_js).map_err(|e| Error::ContextError {message: format!("Unable to get webgl2 context for the given canvas. Maybe your browser doesn't support WebGL2? Error code: {:?}", e)})?
             .ok_or(Error::ContextError {message: "Unable to get webgl2 context for the given canvas. Maybe your browser doesn't support WebGL2?".to_string()})?
             .dyn_into::<WebGl2RenderingContext>().map_err(|e| Error::ContextError {message: format!("Unable to get webgl2 context for the given canvas. Maybe your browser doesn't support WebGL2? Error code: {:?}", e)})?;
-        context.get_extension("EXT_color_buffer_float").map_err(|e| Error::ContextError {message: format!("Unable to get EXT_color_buffer_float extension for the given context. Maybe your browser doesn't support the get color_buffer_float extension? Error code: {:?}", e)})?;
-        context.get_extension("OES_texture_float").map_err(|e| Error::ContextError {message: format!("Unable to get OES_texture_float extension for the given context. Maybe your browser doesn't support the get OES_texture_float extension? Error code: {:?}", e)})?;
+        enable_extensions(&context)?;
 
         canvas.set_width(canvas.offset_width() as u32);
         canvas.set_height(canvas.offset_height() as u32);
 
-        Ok(Window { gl: crate::gl::Glstruct::new(context), canvas, window })
+        Ok(Window { gl: crate::gl::Glstruct::new(context.clone()), context, canvas, window, context_lost_callback: Rc::new(RefCell::new(None)) })
+    }
+
+    /// Sets a callback which is called when the browser drops the WebGL context, for example to show a message.
+    /// The render loop is paused until the context is restored, then the callbacks given to `resources::on_context_restored` recreate the resources
+    /// and the next frame contains an `Event::ContextRestored`.
+    pub fn on_context_lost<F: 'static>(&mut self, callback: F)
+        where F: FnMut()
+    {
+        *self.context_lost_callback.borrow_mut() = Some(Box::new(callback));
     }
 
     pub fn render_loop<F: 'static>(&mut self, mut callback: F) -> Result<(), Error>
@@ -103,6 +121,7 @@ impl Window
         let mut last_time = performance.now();
         let last_position = Rc::new(RefCell::new(None));
         let last_zoom = Rc::new(RefCell::new(None));
+        let context_lost = Rc::new(std::cell::Cell::new(false));
 
         self.add_mousedown_event_listener(events.clone())?;
         self.add_touchstart_event_listener(events.clone(), last_position.clone(), last_zoom.clone())?;
@@ -113,16 +132,21 @@ impl Window
         self.add_mousewheel_event_listener(events.clone())?;
         self.add_key_down_event_listener(events.clone())?;
         self.add_key_up_event_listener(events.clone())?;
+        self.add_context_lost_event_listener(context_lost.clone())?;
+        self.add_context_restored_event_listener(events.clone(), context_lost.clone())?;
 
         *g.borrow_mut() = Some(Closure::wrap(Box::new(move || {
             let now = performance.now();
             let elapsed_time = now - last_time;
             last_time = now;
-            let (screen_width, screen_height) = (window().inner_width().unwrap().as_f64().unwrap() as usize,
-                        window().inner_height().unwrap().as_f64().unwrap() as usize);
-            let frame_input = crate::FrameInput {events: (*events).borrow().clone(), elapsed_time, screen_width, screen_height};
-            callback(frame_input);
-            &(*events).borrow_mut().clear();
+            // All rendering fails while the context is lost, so the frames are skipped until it is restored
+            if !context_lost.get() {
+                let (screen_width, screen_height) = (window().inner_width().unwrap().as_f64().unwrap() as usize,
+                            window().inner_height().unwrap().as_f64().unwrap() as usize);
+                let frame_input = crate::FrameInput {events: (*events).borrow().clone(), elapsed_time, screen_width, screen_height};
+                callback(frame_input);
+                &(*events).borrow_mut().clear();
+            }
 
             request_animation_frame(f.borrow().as_ref().unwrap());
         }) as Box<dyn FnMut()>));
@@ -295,6 +319,39 @@ impl Window
         Ok(())
     }
 
+    fn add_context_lost_event_listener(&self, context_lost: Rc<std::cell::Cell<bool>>) -> Result<(), Error>
+    {
+        let context_lost_callback = self.context_lost_callback.clone();
+        let closure = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            // The browser only restores the context if the default handling is prevented
+            event.prevent_default();
+            context_lost.set(true);
+            if let Some(ref mut callback) = *context_lost_callback.borrow_mut() {
+                callback();
+            }
+        }) as Box<dyn FnMut(_)>);
+        self.canvas.add_event_listener_with_callback("webglcontextlost", closure.as_ref().unchecked_ref()).map_err(|e| Error::EventListenerError {message: format!("Unable to add context lost event listener. Error code: {:?}", e)})?;
+        closure.forget();
+        Ok(())
+    }
+
+    fn add_context_restored_event_listener(&self, events: Rc<RefCell<Vec<Event>>>, context_lost: Rc<std::cell::Cell<bool>>) -> Result<(), Error>
+    {
+        let gl = self.gl.clone();
+        let context = self.context.clone();
+        let closure = Closure::wrap(Box::new(move |_: web_sys::Event| {
+            // The extensions were available when the window was created, so enabling them again does not fail
+            enable_extensions(&context).ok();
+            gl.restore_context();
+            crate::core::resources::restore_resources(&gl);
+            context_lost.set(false);
+            (*events).borrow_mut().push(Event::ContextRestored);
+        }) as Box<dyn FnMut(_)>);
+        self.canvas.add_event_listener_with_callback("webglcontextrestored", closure.as_ref().unchecked_ref()).map_err(|e| Error::EventListenerError {message: format!("Unable to add context restored event listener. Error code: {:?}", e)})?;
+        closure.forget();
+        Ok(())
+    }
+
     pub fn size(&self) -> (usize, usize)
     {
         (self.canvas.width() as usize, self.canvas.height() as usize)
@@ -311,6 +368,13 @@ impl Window
     }
 }
 
+fn enable_extensions(context: &WebGl2RenderingContext) -> Result<(), Error>
+{
+    context.get_extension("EXT_color_buffer_float").map_err(|e| Error::ContextError {message: format!("Unable to get EXT_color_buffer_float extension for the given context. Maybe your browser doesn't support the get color_buffer_float extension? Error code: {:?}", e)})?;
+    context.get_extension("OES_texture_float").map_err(|e| Error::ContextError {message: format!("Unable to get OES_texture_float extension for the given context. Maybe your browser doesn't support the get OES_texture_float extension? Error code: {:?}", e)})?;
+    Ok(())
+}
+
 fn map_key_code(code: String) -> String
 {
     code.trim_start_matches("Key").to_string()
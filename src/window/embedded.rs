@@ -0,0 +1,61 @@
+use crate::core::*;
+use crate::gl;
+use crate::gl::Backend;
+use crate::window::frame_input::FrameInput;
+
+/// Renders into an OpenGL context and event loop which are owned by the application, for example a winit, glutin, SDL or Qt window,
+/// instead of a `Window` which owns the context and runs the render loop.
+/// The application makes its context current, creates a `FrameInput` from its own events and calls `render` whenever it wants a frame.
+/// The OpenGL state of the application is saved before and restored after each frame, see `ExternalState`.
+pub struct EmbeddedContext {
+    gl: Gl,
+    vertex_array: gl::VertexArrayObject
+}
+
+impl EmbeddedContext {
+    /// Loads the OpenGL functions with the loader of the application, for example `get_proc_address` of glutin or SDL.
+    /// The context of the application must be current and support OpenGL 3.3 core (or OpenGL ES 3.0 with the `gles` feature).
+    pub fn load_with<F>(loadfn: F) -> EmbeddedContext
+        where for<'r> F: FnMut(&'r str) -> *const gl::consts::types::GLvoid
+    {
+        let backend = gl::OpenGL::load_with(loadfn);
+        let mut external_vertex_array = [0];
+        backend.get_parameter_i32v(consts::VERTEX_ARRAY_BINDING, &mut external_vertex_array);
+
+        // Creating the context binds the vertex array object used by this crate
+        let gl = gl::Glstruct::new(Box::new(backend));
+        let mut vertex_array = [0];
+        gl.get_parameter_i32v(consts::VERTEX_ARRAY_BINDING, &mut vertex_array);
+        gl.bind_vertex_array(&(external_vertex_array[0] as u32));
+        EmbeddedContext { gl, vertex_array: vertex_array[0] as u32 }
+    }
+
+    pub fn gl(&self) -> Gl
+    {
+        self.gl.clone()
+    }
+
+    /// Renders a frame with the callback. The framebuffer which the application has bound for drawing, for example the framebuffer of a Qt widget,
+    /// is used as the screen, so `Screen::write` renders into it. Afterwards, all of the state changed by this crate is restored.
+    pub fn render<F>(&self, frame_input: FrameInput, callback: F)
+        where F: FnOnce(FrameInput)
+    {
+        let external_state = ExternalState::save(&self.gl);
+        state::invalidate_state(&self.gl);
+        self.gl.set_screen_framebuffer(Some(external_state.draw_framebuffer()));
+        self.gl.bind_vertex_array(&self.vertex_array);
+
+        callback(frame_input);
+
+        self.gl.set_screen_framebuffer(None);
+        external_state.restore(&self.gl);
+    }
+}
+
+impl Drop for EmbeddedContext
+{
+    fn drop(&mut self)
+    {
+        resources::report_live_resources(&self.gl);
+    }
+}
@@ -0,0 +1,190 @@
+use std::io::Write;
+
+/// Receives the frames captured by a `FrameCapture`.
+pub trait FrameSink {
+    /// Called for each captured frame with RGBA pixels with 8 bits per channel, starting with the top row.
+    fn write_frame(&mut self, frame_number: usize, pixels: &[u8], width: usize, height: usize) -> std::io::Result<()>;
+}
+
+/// Writes each frame to a numbered PNG file, for example `frames/frame_00042.png` when created with the prefix `frames/frame_`.
+#[cfg(feature = "image-io")]
+pub struct PngSequence {
+    path_prefix: String
+}
+
+#[cfg(feature = "image-io")]
+impl PngSequence {
+    pub fn new(path_prefix: &str) -> Self
+    {
+        PngSequence {path_prefix: path_prefix.to_string()}
+    }
+}
+
+#[cfg(feature = "image-io")]
+impl FrameSink for PngSequence {
+    fn write_frame(&mut self, frame_number: usize, pixels: &[u8], width: usize, height: usize) -> std::io::Result<()>
+    {
+        image::save_buffer(format!("{}{:05}.png", self.path_prefix, frame_number), pixels, width as u32, height as u32, image::RGBA(8))
+    }
+}
+
+/// Writes the raw RGBA bytes of each frame to a writer, for example the standard input of
+/// `ffmpeg -f rawvideo -pix_fmt rgba -s <width>x<height> -r <frame rate> -i - video.mp4`.
+pub struct RawFrameSink<W: Write> {
+    writer: W
+}
+
+impl<W: Write> RawFrameSink<W> {
+    pub fn new(writer: W) -> Self
+    {
+        RawFrameSink {writer}
+    }
+
+    pub fn into_inner(self) -> W
+    {
+        self.writer
+    }
+}
+
+impl<W: Write> FrameSink for RawFrameSink<W> {
+    fn write_frame(&mut self, _frame_number: usize, pixels: &[u8], _width: usize, _height: usize) -> std::io::Result<()>
+    {
+        self.writer.write_all(pixels)?;
+        self.writer.flush()
+    }
+}
+
+/// Captures the frames rendered in a render loop, see `Window::render_loop_with_capture`.
+/// The elapsed time of every frame is set to a fixed time step independent of the wall clock, so that animations are captured smoothly and deterministically.
+pub struct FrameCapture {
+    sink: Box<dyn FrameSink>,
+    time_step: f64,
+    frame_count: Option<usize>,
+    frame_number: usize
+}
+
+impl FrameCapture {
+    /// Captures frames at the given number of frames per second, until `frame_count` frames are captured or forever if it is `None`.
+    /// Fails if the number of frames per second is not positive.
+    pub fn new(sink: Box<dyn FrameSink>, frames_per_second: f64, frame_count: Option<usize>) -> std::io::Result<Self>
+    {
+        if !(frames_per_second > 0.0 && frames_per_second.is_finite()) {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput,
+                                           format!("Cannot capture {} frames per second", frames_per_second)));
+        }
+        Ok(FrameCapture {sink, time_step: 1000.0 / frames_per_second, frame_count, frame_number: 0})
+    }
+
+    /// The elapsed time in milliseconds reported for each frame.
+    pub fn time_step(&self) -> f64
+    {
+        self.time_step
+    }
+
+    /// The number of frames captured so far.
+    pub fn frame_number(&self) -> usize
+    {
+        self.frame_number
+    }
+
+    pub fn is_done(&self) -> bool
+    {
+        self.frame_count.map(|count| self.frame_number >= count).unwrap_or(false)
+    }
+
+    /// Reads the frame from the screen and passes it to the sink.
+    pub fn capture(&mut self, gl: &crate::Gl, width: usize, height: usize) -> std::io::Result<()>
+    {
+        let pixels = crate::Screen::read_color_rgba(gl, 0, 0, width, height)
+            .map_err(|e| std::io::Error::other(format!("{:?}", e)))?;
+        let mut flipped = Vec::with_capacity(pixels.len());
+        for row in (0..height).rev() {
+            flipped.extend_from_slice(&pixels[row * width * 4..(row + 1) * width * 4]);
+        }
+        self.sink.write_frame(self.frame_number, &flipped, width, height)?;
+        self.frame_number += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 3;
+
+    /// A writer whose bytes can be read after the sink is moved into the capture.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize>
+        {
+            self.0.borrow_mut().write(bytes)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()>
+        {
+            Ok(())
+        }
+    }
+
+    /// Clears the screen to red except the bottom row, which is green.
+    fn render(gl: &Gl)
+    {
+        Screen::write(gl, 0, 0, WIDTH, HEIGHT, Some(&vec4(1.0, 0.0, 0.0, 1.0)), None, &|| {}).unwrap();
+        Screen::write(gl, 0, 0, WIDTH, 1, Some(&vec4(0.0, 1.0, 0.0, 1.0)), None, &|| {}).unwrap();
+    }
+
+    #[test]
+    fn capture_writes_frames_starting_with_the_top_row()
+    {
+        let gl = gl::Glstruct::new(Box::new(gl::CpuBackend::new(WIDTH, HEIGHT)));
+        let buffer = SharedBuffer::default();
+        let mut capture = FrameCapture::new(Box::new(RawFrameSink::new(buffer.clone())), 25.0, Some(2)).unwrap();
+        assert_eq!(capture.time_step(), 40.0);
+
+        render(&gl);
+        capture.capture(&gl, WIDTH, HEIGHT).unwrap();
+        assert!(!capture.is_done());
+        capture.capture(&gl, WIDTH, HEIGHT).unwrap();
+        assert!(capture.is_done());
+        assert_eq!(capture.frame_number(), 2);
+
+        let bytes = buffer.0.borrow();
+        let frame_size = WIDTH * HEIGHT * 4;
+        assert_eq!(bytes.len(), 2 * frame_size);
+        assert_eq!(&bytes[0..4], &[255, 0, 0, 255]);
+        assert_eq!(&bytes[frame_size - 4..frame_size], &[0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn capture_rejects_invalid_frame_rates()
+    {
+        assert!(FrameCapture::new(Box::new(RawFrameSink::new(Vec::new())), 0.0, None).is_err());
+        assert!(FrameCapture::new(Box::new(RawFrameSink::new(Vec::new())), -30.0, None).is_err());
+    }
+
+    #[cfg(feature = "image-io")]
+    #[test]
+    fn png_sequence_writes_numbered_images()
+    {
+        let gl = gl::Glstruct::new(Box::new(gl::CpuBackend::new(WIDTH, HEIGHT)));
+        let prefix = std::env::temp_dir().join(format!("three-d-png-sequence-{}-", std::process::id()));
+        let prefix = prefix.to_str().unwrap();
+        let mut capture = FrameCapture::new(Box::new(PngSequence::new(prefix)), 30.0, Some(1)).unwrap();
+        render(&gl);
+        capture.capture(&gl, WIDTH, HEIGHT).unwrap();
+
+        let path = format!("{}00000.png", prefix);
+        let image = image::open(&path).unwrap().to_rgba();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(image.dimensions(), (WIDTH as u32, HEIGHT as u32));
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(0, HEIGHT as u32 - 1).0, [0, 255, 0, 255]);
+    }
+}
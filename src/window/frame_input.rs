@@ -1,4 +1,6 @@
 
+/// The input to the render callback for each frame. Applications which drive the frames themselves, see `EmbeddedContext`, create it from their own events.
+#[derive(Debug, Clone)]
 pub struct FrameInput {
     pub events: Vec<Event>,
     pub elapsed_time: f64,
@@ -20,7 +22,9 @@ pub enum MouseButton {
     Middle,
 }
 
+/// An input event, new kinds of events may be added so a match on events needs a wildcard arm.
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum Event
 {
     MouseClick {
@@ -38,4 +42,6 @@ pub enum Event
         state: State,
         kind: String
     },
+    /// The WebGL context was lost and has been restored, after the callbacks given to `resources::on_context_restored` have recreated the resources.
+    ContextRestored,
 }
\ No newline at end of file
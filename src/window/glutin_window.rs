@@ -1,12 +1,14 @@
 
 use glutin::*;
+use crate::window::frame_capture;
 use crate::window::frame_input;
 use crate::gl;
 
 #[derive(Debug)]
 pub enum Error {
     WindowCreationError(glutin::CreationError),
-    ContextError(glutin::ContextError)
+    ContextError(glutin::ContextError),
+    FrameCaptureError(std::io::Error)
 }
 
 impl From<glutin::CreationError> for Error {
@@ -36,6 +38,12 @@ impl Window
     }
 
     pub fn new(title: &str, width: u32, height: u32) -> Result<Window, Error>
+    {
+        Window::new_with_multisampling(title, width, height, 0)
+    }
+
+    /// Creates a window where the default framebuffer has the given number of samples per pixel, zero disables multisampling.
+    pub fn new_with_multisampling(title: &str, width: u32, height: u32, samples: u16) -> Result<Window, Error>
     {
         let window = WindowBuilder::new()
             .with_title(title)
@@ -43,7 +51,9 @@ impl Window
 
         let events_loop = EventsLoop::new();
 
-        let context = ContextBuilder::new().with_vsync(true);
+        let context = ContextBuilder::new().with_vsync(true).with_multisampling(samples).with_stencil_buffer(8);
+        #[cfg(feature = "gles")]
+        let context = context.with_gl(GlRequest::Specific(Api::OpenGlEs, (3, 0)));
 
         let gl_window = GlWindow::new(window, context, &events_loop)?;
 
@@ -54,8 +64,22 @@ impl Window
         Ok(Window {gl_window, events_loop, gl})
     }
 
-    pub fn render_loop<F: 'static>(&mut self, mut callback: F) -> Result<(), Error>
-        where F: FnMut(frame_input::FrameInput)
+    pub fn render_loop<F>(&mut self, callback: F) -> Result<(), Error>
+        where F: 'static + FnMut(frame_input::FrameInput)
+    {
+        self.run(callback, None)
+    }
+
+    /// Same as `render_loop`, except that the elapsed time of each frame is the fixed time step of the capture and that each frame is captured after it is rendered.
+    /// The loop stops when the capture is done.
+    pub fn render_loop_with_capture<F>(&mut self, mut capture: frame_capture::FrameCapture, callback: F) -> Result<(), Error>
+        where F: 'static + FnMut(frame_input::FrameInput)
+    {
+        self.run(callback, Some(&mut capture))
+    }
+
+    fn run<F>(&mut self, mut callback: F, mut capture: Option<&mut frame_capture::FrameCapture>) -> Result<(), Error>
+        where F: 'static + FnMut(frame_input::FrameInput)
     {
         let mut last_time = std::time::Instant::now();
         let mut count = 0;
@@ -85,8 +109,13 @@ impl Window
             }
 
             let (screen_width, screen_height) = self.framebuffer_size();
+            let elapsed_time = capture.as_ref().map(|c| c.time_step()).unwrap_or(elapsed_time);
             let frame_input = frame_input::FrameInput {events, elapsed_time, screen_width, screen_height};
             callback(frame_input);
+            if let Some(ref mut capture) = capture {
+                capture.capture(&self.gl, screen_width, screen_height).map_err(Error::FrameCaptureError)?;
+                exit = exit || capture.is_done();
+            }
             error = self.gl_window.swap_buffers();
         }
         error?;
@@ -174,3 +203,11 @@ impl Window
         }
     }
 }
+
+impl Drop for Window
+{
+    fn drop(&mut self)
+    {
+        crate::core::resources::report_live_resources(&self.gl);
+    }
+}
@@ -0,0 +1,131 @@
+use khronos_egl as egl;
+use crate::gl;
+
+#[derive(Debug)]
+pub enum Error {
+    LoadingError {message: String},
+    ContextCreationError {message: String}
+}
+
+impl From<egl::Error> for Error {
+    fn from(other: egl::Error) -> Self {
+        Error::ContextCreationError {message: format!("{}", other)}
+    }
+}
+
+const PLATFORM_SURFACELESS_MESA: egl::Enum = 0x31DD;
+
+/// An OpenGL (or OpenGL ES 3.0 with the `gles` feature) context which renders into an offscreen buffer instead of a window, so it requires neither a display nor a GPU.
+/// The context is created with EGL on Mesa's surfaceless platform, which falls back to the software rasterizer when there is no GPU
+/// (set `LIBGL_ALWAYS_SOFTWARE=1` to force it).
+/// The offscreen buffer acts as the screen, so it is written to with `Screen::write` and read back with `Screen::read_color` and, on desktop OpenGL, `Screen::read_depth`.
+/// Dropping the context destroys the EGL context, so all objects created from it and all clones of `gl` must be dropped first.
+pub struct HeadlessContext
+{
+    egl: egl::DynamicInstance<egl::EGL1_5>,
+    display: egl::Display,
+    surface: egl::Surface,
+    context: egl::Context,
+    width: usize,
+    height: usize,
+    gl: crate::Gl
+}
+
+impl HeadlessContext
+{
+    /// Creates a context where the offscreen buffer has the given size.
+    pub fn new(width: usize, height: usize) -> Result<HeadlessContext, Error>
+    {
+        let egl = unsafe { egl::DynamicInstance::<egl::EGL1_5>::load_required() }
+            .map_err(|e| Error::LoadingError {message: format!("Failed to load libEGL: {}", e)})?;
+        let display = egl.get_platform_display(PLATFORM_SURFACELESS_MESA, egl::DEFAULT_DISPLAY as *mut std::ffi::c_void, &[egl::ATTRIB_NONE])?;
+        egl.initialize(display)?;
+        #[cfg(not(feature = "gles"))]
+        egl.bind_api(egl::OPENGL_API)?;
+        #[cfg(feature = "gles")]
+        egl.bind_api(egl::OPENGL_ES_API)?;
+
+        #[cfg(not(feature = "gles"))]
+        let renderable_type = egl::OPENGL_BIT;
+        #[cfg(feature = "gles")]
+        let renderable_type = egl::OPENGL_ES3_BIT;
+
+        let config_attributes = [
+            egl::SURFACE_TYPE, egl::PBUFFER_BIT,
+            egl::RENDERABLE_TYPE, renderable_type,
+            egl::RED_SIZE, 8,
+            egl::GREEN_SIZE, 8,
+            egl::BLUE_SIZE, 8,
+            egl::ALPHA_SIZE, 8,
+            egl::DEPTH_SIZE, 24,
+            egl::STENCIL_SIZE, 8,
+            egl::NONE
+        ];
+        let config = egl.choose_first_config(display, &config_attributes)?
+            .ok_or_else(|| Error::ContextCreationError {message: "No suitable EGL config found".to_string()})?;
+
+        let surface_attributes = [
+            egl::WIDTH, width as egl::Int,
+            egl::HEIGHT, height as egl::Int,
+            egl::NONE
+        ];
+        let surface = egl.create_pbuffer_surface(display, config, &surface_attributes)?;
+
+        #[cfg(not(feature = "gles"))]
+        let context_attributes = [
+            egl::CONTEXT_MAJOR_VERSION, 3,
+            egl::CONTEXT_MINOR_VERSION, 3,
+            egl::CONTEXT_OPENGL_PROFILE_MASK, egl::CONTEXT_OPENGL_CORE_PROFILE_BIT,
+            egl::NONE
+        ];
+        #[cfg(feature = "gles")]
+        let context_attributes = [
+            egl::CONTEXT_MAJOR_VERSION, 3,
+            egl::CONTEXT_MINOR_VERSION, 0,
+            egl::NONE
+        ];
+        let context = egl.create_context(display, config, None, &context_attributes)?;
+        egl.make_current(display, Some(surface), Some(surface), Some(context))?;
+
+        let gl = gl::Glstruct::load_with(|s| {
+            egl.get_proc_address(s).map(|f| f as *const std::os::raw::c_void).unwrap_or(std::ptr::null())
+        });
+        Ok(HeadlessContext {egl, display, surface, context, width, height, gl})
+    }
+
+    /// Makes this the current context of the calling thread, which is needed when using several contexts on the same thread,
+    /// since creating a context makes it current.
+    pub fn make_current(&self) -> Result<(), Error>
+    {
+        self.egl.make_current(self.display, Some(self.surface), Some(self.surface), Some(self.context))?;
+        Ok(())
+    }
+
+    /// The size of the offscreen buffer.
+    pub fn size(&self) -> (usize, usize)
+    {
+        (self.width, self.height)
+    }
+
+    /// The returned handle must not outlive the context, see `HeadlessContext`.
+    pub fn gl(&self) -> crate::Gl
+    {
+        self.gl.clone()
+    }
+}
+
+impl Drop for HeadlessContext
+{
+    fn drop(&mut self)
+    {
+        crate::core::resources::report_live_resources(&self.gl);
+        if self.egl.get_current_context() == Some(self.context) {
+            // The context may be used by clones of `gl` after this, but the driver must not call back into them
+            self.gl.set_debug_messages(false);
+            self.egl.make_current(self.display, None, None, None).ok();
+        }
+        self.egl.destroy_context(self.display, self.context).ok();
+        self.egl.destroy_surface(self.display, self.surface).ok();
+        // The display is shared by all contexts in the process, so it is not terminated
+    }
+}
@@ -0,0 +1,93 @@
+#![cfg(all(feature = "image-io", not(feature = "no-renderer")))]
+
+use three_d::*;
+use three_d::golden_image::*;
+
+const WIDTH: usize = 160;
+const HEIGHT: usize = 120;
+const DEFERRED_PIPELINE_REFERENCE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/deferred_pipeline.png");
+
+fn cube(gl: &Gl) -> Mesh
+{
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut indices = Vec::new();
+    let sides = [(vec3(1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0)), (vec3(-1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0)),
+                 (vec3(0.0, 1.0, 0.0), vec3(0.0, 0.0, 1.0)), (vec3(0.0, -1.0, 0.0), vec3(0.0, 0.0, 1.0)),
+                 (vec3(0.0, 0.0, 1.0), vec3(1.0, 0.0, 0.0)), (vec3(0.0, 0.0, -1.0), vec3(1.0, 0.0, 0.0))];
+    for (normal, u) in sides.iter() {
+        let v = normal.cross(*u);
+        let first = positions.len() as u32 / 3;
+        for (a, b) in &[(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            let position = normal + u * *a + v * *b;
+            positions.extend_from_slice(&[position.x, position.y, position.z]);
+            normals.extend_from_slice(&[normal.x, normal.y, normal.z]);
+        }
+        indices.extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+    Mesh::new(gl, &indices, &positions, &normals).unwrap()
+}
+
+/// Renders a cube on a floor into the geometry buffer of a deferred pipeline and passes the light pass to `render_to_screen`.
+fn render_deferred_pipeline(gl: &Gl, render_to_screen: &dyn Fn(&dyn Fn()))
+{
+    let mut pipeline = DeferredPipeline::new(gl).unwrap();
+    let camera = Camera::new_perspective(gl, vec3(4.0, 3.0, 5.0), vec3(0.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0),
+                                         degrees(45.0), WIDTH as f32 / HEIGHT as f32, 0.1, 100.0);
+    let mesh = cube(gl);
+    let floor = Mat4::from_translation(vec3(0.0, -1.5, 0.0)) * Mat4::from_nonuniform_scale(5.0, 0.2, 5.0);
+
+    let ambient_light = AmbientLight::new(gl, 0.2, &vec3(1.0, 1.0, 1.0)).unwrap();
+    let mut directional_light = DirectionalLight::new(gl, 0.7, &vec3(1.0, 1.0, 1.0), &vec3(-1.0, -2.0, -0.5)).unwrap();
+    directional_light.generate_shadow_map(&vec3(0.0, 0.0, 0.0), 12.0, 12.0, 20.0, 256, 256,
+                                          &|camera: &Camera| { mesh.render(&Mat4::identity(), camera); });
+    let point_light = PointLight::new(gl, 0.5, &vec3(1.0, 0.3, 0.3), &vec3(2.0, 1.5, 2.0), 0.5, 0.05, 0.005).unwrap();
+    let spot_light = SpotLight::new(gl, 0.8, &vec3(0.3, 0.3, 1.0), &vec3(-2.0, 3.0, 2.0), &vec3(1.0, -1.5, -1.0),
+                                    25.0, 0.1, 0.001, 0.0001).unwrap();
+
+    pipeline.geometry_pass(WIDTH, HEIGHT, &|| {
+        mesh.render(&Mat4::identity(), &camera);
+        mesh.render(&floor, &camera);
+    }).unwrap();
+    render_to_screen(&|| {
+        pipeline.light_pass(&camera, Some(&ambient_light), &[&directional_light], &[&spot_light], &[&point_light]).unwrap();
+    });
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn deferred_pipeline()
+{
+    let context = HeadlessContext::new(WIDTH, HEIGHT).unwrap();
+    let gl = context.gl();
+    render_deferred_pipeline(&gl, &|render| {
+        compare_with_reference(&gl, DEFERRED_PIPELINE_REFERENCE, WIDTH, HEIGHT, &ComparisonSettings::default(), render).unwrap();
+    });
+}
+
+/// The CPU backend approximates the GPU, so it is compared with the same reference but with a larger tolerance.
+#[test]
+fn deferred_pipeline_on_cpu()
+{
+    let backend = gl::CpuBackend::new(WIDTH, HEIGHT);
+    let gl = gl::Glstruct::new(Box::new(backend));
+    render_deferred_pipeline(&gl, &|render| {
+        Screen::write(&gl, 0, 0, WIDTH, HEIGHT, Some(&vec4(0.0, 0.0, 0.0, 1.0)), Some(1.0), render).unwrap();
+    });
+
+    let pixels = Screen::read_color(&gl, 0, 0, WIDTH, HEIGHT).unwrap();
+    let actual: Vec<u8> = (0..HEIGHT).rev().flat_map(|row| pixels[row * WIDTH * 3..(row + 1) * WIDTH * 3].to_vec()).collect();
+    let reference = image::open(DEFERRED_PIPELINE_REFERENCE).unwrap().to_rgb().into_raw();
+    let result = compare_images(&reference, &actual, WIDTH, HEIGHT, 6).unwrap();
+    assert!(result.passed(&ComparisonSettings {pixel_tolerance: 6, max_different_pixels: 0.01, min_psnr: Some(35.0), min_ssim: Some(0.95)}),
+            "{:?}", result);
+}
+
+#[test]
+fn compare_images_rejects_wrong_sizes()
+{
+    let image = vec![0u8; 4 * 3 * 3];
+    assert!(compare_images(&image, &image, 4, 3, 0).unwrap().passed(&ComparisonSettings::default()));
+    assert!(compare_images(&image, &image[3..], 4, 3, 0).is_err());
+    assert!(diff_image(&image, &image, 3, 3, 0).is_err());
+}